@@ -1,14 +1,17 @@
-use crate::ai::llm::{ContentBlock, LlmCompletion, LlmError, LlmProvider, Message, ToolCall, ToolResult};
-use crate::db::{CategoryInfo, DataSummary};
-use crate::tools::{ChartSpec, ToolError, ToolRegistry};
+use crate::ai::llm::{CompletionRequest, ContentBlock, LlmCompletion, LlmError, LlmProvider, Message, StreamChunk, ToolCall, ToolResult};
+use crate::db::{self, CategoryInfo, DataSummary};
+use crate::tools::{ChartSpec, ToolContext, ToolRegistry};
 use sqlx::SqlitePool;
+use std::sync::Arc;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::RwLock;
 use tracing::{info, warn, debug};
 
 #[derive(Clone)]
 pub struct Agent {
-    llm: LlmProvider,
+    llm: Arc<dyn LlmProvider>,
     tools: ToolRegistry,
-    system_prompt: String,
+    system_prompt: Arc<RwLock<String>>,
     pool: SqlitePool,
 }
 
@@ -22,6 +25,7 @@ pub struct AgentReply {
 /// Events emitted during the agent loop, in order.
 #[derive(Debug)]
 pub enum AgentEvent {
+    TextDelta(String),
     ToolRunning { tool: String },
     ToolCompleted { tool: String },
     ChartArtifact(ChartSpec),
@@ -30,43 +34,91 @@ pub enum AgentEvent {
 #[derive(Debug)]
 pub enum AgentError {
     Llm(LlmError),
-    Tool(ToolError),
+}
+
+impl std::fmt::Display for AgentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AgentError::Llm(err) => write!(f, "LLM error: {}", err),
+        }
+    }
 }
 
 impl Agent {
-    pub fn new(llm: LlmProvider, tools: ToolRegistry, system_prompt: String, pool: SqlitePool) -> Self {
+    pub fn new(llm: Arc<dyn LlmProvider>, tools: ToolRegistry, system_prompt: String, pool: SqlitePool) -> Self {
         Self {
             llm,
             tools,
-            system_prompt,
+            system_prompt: Arc::new(RwLock::new(system_prompt)),
             pool,
         }
     }
 
+    /// Rebuild the system prompt from the current database contents, e.g. after an import.
+    pub async fn refresh_data_summary(&self) -> Result<DataSummary, sqlx::Error> {
+        let summary = db::load_data_summary(&self.pool).await?;
+        let categories = db::load_categories(&self.pool).await?;
+        *self.system_prompt.write().await = build_system_prompt(&summary, &categories);
+        Ok(summary)
+    }
+
+    /// Run the agent loop for one user message in conversation
+    /// `conversation_id`. Events are sent on `events` as they happen, so callers
+    /// can stream them; the send side ignores a closed channel so the turn still
+    /// completes (and can be saved) if the client goes away.
     pub async fn chat(
         &self,
+        conversation_id: &str,
         history: &mut Vec<Message>,
         user_input: &str,
-    ) -> Result<(AgentReply, Vec<AgentEvent>), AgentError> {
+        events: &UnboundedSender<AgentEvent>,
+    ) -> Result<AgentReply, AgentError> {
         history.push(Message {
             role: "user".to_string(),
             content: vec![ContentBlock::Text { text: user_input.to_string() }],
         });
+        // Tool results are sent as user messages too; only count what the user wrote
+        let context = ToolContext {
+            conversation_id: conversation_id.to_string(),
+            turn: history
+                .iter()
+                .filter(|m| m.role == "user" && m.content.iter().any(|c| matches!(c, ContentBlock::Text { .. })))
+                .count(),
+        };
 
         let mut charts: Vec<ChartSpec> = Vec::new();
         let mut tools_used: Vec<String> = Vec::new();
-        let mut events: Vec<AgentEvent> = Vec::new();
         let mut final_text = String::new();
         let mut incomplete = false;
 
+        let system_prompt = self.system_prompt.read().await.clone();
+
         for iteration in 0..10 {
             debug!(iteration, "llm call");
+            let mut on_chunk = |chunk: StreamChunk| match chunk {
+                StreamChunk::Text(text) => {
+                    let _ = events.send(AgentEvent::TextDelta(text));
+                }
+                StreamChunk::ToolUse(call) => {
+                    let _ = events.send(AgentEvent::ToolRunning { tool: call.name });
+                }
+                StreamChunk::Done { stop_reason } => {
+                    debug!(iteration, stop_reason = ?stop_reason, "llm stream finished");
+                }
+            };
+            let request = CompletionRequest {
+                system: &system_prompt,
+                messages: history,
+                tools: self.tools.definitions(),
+                json_output: false,
+            };
             let completion = self
                 .llm
-                .complete(&self.system_prompt, history, self.tools.definitions())
+                .complete_stream(request, &mut on_chunk)
                 .await
                 .map_err(AgentError::Llm)?;
 
+            debug!(iteration, stop_reason = ?completion.stop_reason, "llm response");
             let (assistant_message, tool_calls, text_chunk) = unpack_completion(&completion);
             if !assistant_message.content.is_empty() {
                 history.push(assistant_message);
@@ -84,20 +136,20 @@ impl Agent {
 
             for tool_call in &tool_calls {
                 info!(tool = %tool_call.name, input = %tool_call.input, "tool call");
-                events.push(AgentEvent::ToolRunning { tool: tool_call.name.clone() });
 
                 let result = match self
                     .tools
-                    .run(&self.pool, &tool_call.name, tool_call.input.clone())
+                    .run(&self.pool, &tool_call.name, tool_call.input.clone(), &context)
                     .await
                 {
                     Ok(output) => {
                         info!(tool = %tool_call.name, summary_len = output.summary.len(), charts = output.charts.len(), "tool ok");
                         for chart in output.charts {
-                            events.push(AgentEvent::ChartArtifact(chart));
+                            charts.push(chart.clone());
+                            let _ = events.send(AgentEvent::ChartArtifact(chart));
                         }
                         tools_used.push(tool_call.name.clone());
-                        events.push(AgentEvent::ToolCompleted { tool: tool_call.name.clone() });
+                        let _ = events.send(AgentEvent::ToolCompleted { tool: tool_call.name.clone() });
                         ToolResult {
                             tool_use_id: tool_call.id.clone(),
                             content: output.summary,
@@ -105,11 +157,11 @@ impl Agent {
                         }
                     }
                     Err(err) => {
-                        warn!(tool = %tool_call.name, error = ?err, "tool error");
-                        events.push(AgentEvent::ToolCompleted { tool: tool_call.name.clone() });
+                        warn!(tool = %tool_call.name, error = %err, "tool error");
+                        let _ = events.send(AgentEvent::ToolCompleted { tool: tool_call.name.clone() });
                         ToolResult {
                             tool_use_id: tool_call.id.clone(),
-                            content: format!("Tool error: {:?}", err),
+                            content: format!("Tool error: {}", err),
                             is_error: Some(true),
                         }
                     },
@@ -126,22 +178,12 @@ impl Agent {
             }
         }
 
-        // Collect charts from events for the reply
-        for event in &events {
-            if let AgentEvent::ChartArtifact(chart) = event {
-                charts.push(chart.clone());
-            }
-        }
-
-        Ok((
-            AgentReply {
-                text: final_text,
-                charts,
-                tools_used,
-                incomplete,
-            },
-            events,
-        ))
+        Ok(AgentReply {
+            text: final_text,
+            charts,
+            tools_used,
+            incomplete,
+        })
     }
 }
 
@@ -156,6 +198,14 @@ pub fn build_system_prompt(summary: &DataSummary, categories: &[CategoryInfo]) -
         category_counts.push_str(&format!("- {} ({} tx)\n", entry.name, entry.count));
     }
 
+    let mut account_lines = String::new();
+    for account in &summary.accounts {
+        account_lines.push_str(&format!("- {} ({} tx)\n", account.label(), account.transactions));
+    }
+    if account_lines.is_empty() {
+        account_lines.push_str("- none recorded\n");
+    }
+
     let date_range = match (&summary.min_date, &summary.max_date) {
         (Some(min), Some(max)) => format!("{} to {}", min, max),
         _ => "unknown range".to_string(),
@@ -163,10 +213,11 @@ pub fn build_system_prompt(summary: &DataSummary, categories: &[CategoryInfo]) -
 
     format!(
         "You are a budget analysis assistant. Use the provided tools to answer questions about spending.\n\n
-tDATA SUMMARY\n- Date range: {}\n- Total transactions: {}\n- Categories and counts:\n{}\n\nCATEGORY SCHEMA\n{}\nTOOLS\n- spending_by_category: totals by category with optional year/month filters\n- monthly_trend: monthly spending totals with optional category/year filters\n- merchant_breakdown: top merchants within a category\n- income_vs_spending: monthly income vs spending, optional year filter\n\nGuidance: keep summaries concise, and use tools for quantitative questions.\n\nCharts are rendered visually by the frontend. Do not generate text-based charts, ASCII bar charts, or markdown tables of monthly data. Just summarize insights in words.",
+tDATA SUMMARY\n- Date range: {}\n- Total transactions: {}\n- Categories and counts:\n{}- Accounts and cards:\n{}\nCATEGORY SCHEMA\n{}\nTOOLS\n- spending_by_category: totals by category with optional year/month filters\n- monthly_trend: monthly spending totals with optional category/year filters\n- merchant_breakdown: top merchants within a category\n- income_vs_spending: monthly income vs spending, optional year filter\n- budget_status: actual vs budget per category for a month or year\n- recurring_payments: subscriptions and other periodic charges, with price increases and missed payments\n- run_sql: read-only SELECT escape hatch; prefer the dedicated tools and only use this when none of them can answer\n- recategorise: move matching transactions to another category; always show the preview to the user and only pass the confirmation_id once they have explicitly agreed\n- split_transaction: split one transaction across categories by id (from list_transactions); category totals count each part separately\n- balance_history: month-end balances per account and net worth over time, and gaps where a statement seems to be missing\n- import_status: which periods have been imported per account, with gaps, overlaps and duplicate files\n- transaction_history: what the classifier originally said about a transaction and every later correction, with when and by what\n\nThe spending, trend, merchant, income and search tools take an optional account filter (number, IBAN, card holder or account name) for questions about one card or person.\n\nGuidance: keep summaries concise, and use tools for quantitative questions.\n\nCharts are rendered visually by the frontend. Do not generate text-based charts, ASCII bar charts, or markdown tables of monthly data. Just summarize insights in words.",
         date_range,
         summary.total_transactions,
         category_counts,
+        account_lines,
         category_lines
     )
 }
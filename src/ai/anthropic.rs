@@ -0,0 +1,268 @@
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+use futures_util::StreamExt;
+use serde::Serialize;
+
+use crate::ai::llm::{
+    http_client, CompletionRequest, ContentBlock, LlmCompletion, LlmError, LlmProvider, Message, StreamChunk,
+    ToolCall, ToolDefinition, DEFAULT_TIMEOUT,
+};
+
+const DEFAULT_ENDPOINT: &str = "https://api.anthropic.com";
+
+/// Claude via the Anthropic Messages API.
+#[derive(Debug, Clone)]
+pub struct AnthropicProvider {
+    client: reqwest::Client,
+    endpoint: String,
+    api_key: String,
+    model: String,
+    max_tokens: u32,
+}
+
+impl AnthropicProvider {
+    pub fn new(api_key: &str, model: &str, endpoint: Option<&str>) -> Self {
+        Self {
+            client: http_client(DEFAULT_TIMEOUT),
+            endpoint: endpoint.unwrap_or(DEFAULT_ENDPOINT).trim_end_matches('/').to_string(),
+            api_key: api_key.to_string(),
+            model: model.to_string(),
+            max_tokens: 1024,
+        }
+    }
+
+    /// Replace the default request timeout.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client = http_client(timeout);
+        self
+    }
+
+    async fn stream(
+        &self,
+        request: CompletionRequest<'_>,
+        on_chunk: &mut (dyn FnMut(StreamChunk) + Send),
+    ) -> Result<LlmCompletion, LlmError> {
+        // There is no JSON mode; callers that need JSON ask for it in the prompt
+        let request = ClaudeMessageRequest {
+            model: self.model.clone(),
+            max_tokens: self.max_tokens,
+            system: request.system.to_string(),
+            messages: request.messages.to_vec(),
+            tools: request.tools.to_vec(),
+            stream: true,
+        };
+
+        let response = self
+            .client
+            .post(format!("{}/v1/messages", self.endpoint))
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", "2023-06-01")
+            .json(&request)
+            .send()
+            .await
+            .map_err(LlmError::Http)?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(LlmError::Api { status, body });
+        }
+
+        let mut parser = StreamParser::default();
+        let mut stream = response.bytes_stream();
+        while let Some(item) = stream.next().await {
+            let bytes = item.map_err(LlmError::Http)?;
+            parser.feed(&bytes, on_chunk)?;
+        }
+
+        Ok(parser.finish())
+    }
+}
+
+impl LlmProvider for AnthropicProvider {
+    fn complete_stream<'a>(
+        &'a self,
+        request: CompletionRequest<'a>,
+        on_chunk: &'a mut (dyn FnMut(StreamChunk) + Send),
+    ) -> BoxFuture<'a, Result<LlmCompletion, LlmError>> {
+        Box::pin(self.stream(request, on_chunk))
+    }
+}
+
+/// Incremental parser for the Messages API server-sent event stream.
+#[derive(Default)]
+struct StreamParser {
+    // Raw bytes, so multi-byte characters split across network reads stay intact
+    buffer: Vec<u8>,
+    content_blocks: Vec<ContentBlock>,
+    stop_reason: Option<String>,
+    current_tool: Option<StreamingToolUse>,
+}
+
+impl StreamParser {
+    fn feed(&mut self, bytes: &[u8], on_chunk: &mut (dyn FnMut(StreamChunk) + Send)) -> Result<(), LlmError> {
+        self.buffer.extend_from_slice(bytes);
+
+        while let Some(idx) = self.buffer.windows(2).position(|w| w == b"\n\n") {
+            let event = String::from_utf8_lossy(&self.buffer[..idx]).to_string();
+            self.buffer.drain(..idx + 2);
+
+            for line in event.lines() {
+                let Some(data) = line.strip_prefix("data: ") else { continue };
+                if data.trim() == "[DONE]" {
+                    continue;
+                }
+
+                let value: serde_json::Value = match serde_json::from_str(data) {
+                    Ok(v) => v,
+                    Err(_) => continue,
+                };
+                self.handle_event(&value, on_chunk)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_event(&mut self, value: &serde_json::Value, on_chunk: &mut (dyn FnMut(StreamChunk) + Send)) -> Result<(), LlmError> {
+        let event_type = value.get("type").and_then(|v| v.as_str()).unwrap_or("");
+        match event_type {
+            "content_block_start" => {
+                if let Some(block) = value.get("content_block") {
+                    if block.get("type").and_then(|v| v.as_str()) == Some("tool_use") {
+                        let id = block.get("id").and_then(|v| v.as_str()).unwrap_or("").to_string();
+                        let name = block.get("name").and_then(|v| v.as_str()).unwrap_or("").to_string();
+                        self.current_tool = Some(StreamingToolUse {
+                            id,
+                            name,
+                            input: String::new(),
+                        });
+                    }
+                }
+            }
+            "content_block_delta" => {
+                if let Some(delta) = value.get("delta") {
+                    let delta_type = delta.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                    if delta_type == "text_delta" {
+                        if let Some(text) = delta.get("text").and_then(|v| v.as_str()) {
+                            on_chunk(StreamChunk::Text(text.to_string()));
+                            match self.content_blocks.last_mut() {
+                                Some(ContentBlock::Text { text: existing }) => existing.push_str(text),
+                                _ => self.content_blocks.push(ContentBlock::Text { text: text.to_string() }),
+                            }
+                        }
+                    } else if delta_type == "input_json_delta" {
+                        if let Some(partial) = delta.get("partial_json").and_then(|v| v.as_str()) {
+                            if let Some(tool) = self.current_tool.as_mut() {
+                                tool.input.push_str(partial);
+                            }
+                        }
+                    }
+                }
+            }
+            "content_block_stop" => {
+                if let Some(tool) = self.current_tool.take() {
+                    // Tools without arguments stream no input at all
+                    let input_value = serde_json::from_str(&tool.input)
+                        .unwrap_or_else(|_| serde_json::json!({}));
+                    let call = ToolCall {
+                        id: tool.id,
+                        name: tool.name,
+                        input: input_value,
+                    };
+                    self.content_blocks.push(ContentBlock::ToolUse(call.clone()));
+                    on_chunk(StreamChunk::ToolUse(call));
+                }
+            }
+            "message_delta" => {
+                if let Some(reason) = value.pointer("/delta/stop_reason").and_then(|v| v.as_str()) {
+                    self.stop_reason = Some(reason.to_string());
+                }
+            }
+            "message_stop" => {
+                on_chunk(StreamChunk::Done { stop_reason: self.stop_reason.clone() });
+            }
+            "error" => {
+                let body = value.get("error").map(|e| e.to_string()).unwrap_or_default();
+                return Err(LlmError::Stream(body));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> LlmCompletion {
+        LlmCompletion {
+            content: self.content_blocks,
+            stop_reason: self.stop_reason,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ClaudeMessageRequest {
+    model: String,
+    max_tokens: u32,
+    system: String,
+    messages: Vec<Message>,
+    tools: Vec<ToolDefinition>,
+    stream: bool,
+}
+
+#[derive(Debug, Clone)]
+struct StreamingToolUse {
+    id: String,
+    name: String,
+    input: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_parser_assembles_text_and_tool_calls() {
+        let events = concat!(
+            "event: message_start\ndata: {\"type\":\"message_start\",\"message\":{}}\n\n",
+            "event: content_block_start\ndata: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n",
+            "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Let me check Zürich\"}}\n\n",
+            "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\" spending.\"}}\n\n",
+            "event: content_block_stop\ndata: {\"type\":\"content_block_stop\",\"index\":0}\n\n",
+            "event: content_block_start\ndata: {\"type\":\"content_block_start\",\"index\":1,\"content_block\":{\"type\":\"tool_use\",\"id\":\"toolu_1\",\"name\":\"spending_by_category\",\"input\":{}}}\n\n",
+            "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"{\\\"year\\\": 20\"}}\n\n",
+            "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"26}\"}}\n\n",
+            "event: content_block_stop\ndata: {\"type\":\"content_block_stop\",\"index\":1}\n\n",
+            "event: message_delta\ndata: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"tool_use\"}}\n\n",
+            "event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n",
+        )
+        .as_bytes();
+
+        let mut parser = StreamParser::default();
+        let mut chunks = Vec::new();
+        // Feed in small pieces, splitting events and the multi-byte 'ü'
+        for piece in events.chunks(7) {
+            parser.feed(piece, &mut |c| chunks.push(c)).unwrap();
+        }
+        let completion = parser.finish();
+
+        let texts: Vec<String> = chunks
+            .iter()
+            .filter_map(|c| match c {
+                StreamChunk::Text(t) => Some(t.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(texts, vec!["Let me check Zürich", " spending."]);
+        assert!(matches!(chunks.last(), Some(StreamChunk::Done { stop_reason: Some(r) }) if r == "tool_use"));
+
+        assert_eq!(completion.stop_reason.as_deref(), Some("tool_use"));
+        assert_eq!(completion.content.len(), 2);
+        match &completion.content[1] {
+            ContentBlock::ToolUse(call) => {
+                assert_eq!(call.name, "spending_by_category");
+                assert_eq!(call.input, serde_json::json!({ "year": 2026 }));
+            }
+            other => panic!("expected tool use, got {:?}", other),
+        }
+    }
+}
@@ -1,2 +1,5 @@
 pub mod llm;
 pub mod agent;
+pub mod anthropic;
+pub mod ollama;
+pub mod openai;
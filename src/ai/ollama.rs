@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+use futures_util::StreamExt;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::ai::llm::{
+    http_client, CompletionRequest, ContentBlock, LlmCompletion, LlmError, LlmProvider, Message, StreamChunk,
+    ToolCall, DEFAULT_TIMEOUT,
+};
+
+/// A local model served by Ollama's `/api/chat` endpoint.
+#[derive(Debug, Clone)]
+pub struct OllamaProvider {
+    client: reqwest::Client,
+    base_url: String,
+    model: String,
+}
+
+#[derive(Deserialize)]
+struct ChatResponseLine {
+    message: Option<ResponseMessage>,
+    #[serde(default)]
+    done: bool,
+    done_reason: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ResponseMessage {
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    tool_calls: Vec<ResponseToolCall>,
+}
+
+#[derive(Deserialize)]
+struct ResponseToolCall {
+    function: ResponseFunction,
+}
+
+#[derive(Deserialize)]
+struct ResponseFunction {
+    name: String,
+    #[serde(default)]
+    arguments: Value,
+}
+
+impl OllamaProvider {
+    pub fn new(base_url: &str, model: &str) -> Self {
+        Self {
+            client: http_client(DEFAULT_TIMEOUT),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            model: model.to_string(),
+        }
+    }
+
+    /// Replace the default request timeout.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client = http_client(timeout);
+        self
+    }
+
+    fn request_body(&self, request: &CompletionRequest<'_>) -> Value {
+        let mut body = json!({
+            "model": self.model,
+            "messages": to_ollama_messages(request.system, request.messages),
+            "stream": true,
+            "think": false,
+        });
+        if !request.tools.is_empty() {
+            body["tools"] = request
+                .tools
+                .iter()
+                .map(|t| json!({
+                    "type": "function",
+                    "function": { "name": t.name, "description": t.description, "parameters": t.input_schema },
+                }))
+                .collect();
+        }
+        if request.json_output {
+            body["format"] = json!("json");
+        }
+        body
+    }
+
+    async fn stream(
+        &self,
+        request: CompletionRequest<'_>,
+        on_chunk: &mut (dyn FnMut(StreamChunk) + Send),
+    ) -> Result<LlmCompletion, LlmError> {
+        let response = self
+            .client
+            .post(format!("{}/api/chat", self.base_url))
+            .json(&self.request_body(&request))
+            .send()
+            .await
+            .map_err(LlmError::Http)?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(LlmError::Api { status, body });
+        }
+
+        let mut parser = StreamParser::default();
+        let mut stream = response.bytes_stream();
+        while let Some(item) = stream.next().await {
+            let bytes = item.map_err(LlmError::Http)?;
+            parser.feed(&bytes, on_chunk)?;
+        }
+        parser.feed(b"\n", on_chunk)?;
+
+        Ok(parser.finish())
+    }
+}
+
+impl LlmProvider for OllamaProvider {
+    fn complete_stream<'a>(
+        &'a self,
+        request: CompletionRequest<'a>,
+        on_chunk: &'a mut (dyn FnMut(StreamChunk) + Send),
+    ) -> BoxFuture<'a, Result<LlmCompletion, LlmError>> {
+        Box::pin(self.stream(request, on_chunk))
+    }
+}
+
+/// Convert Anthropic-shaped history into Ollama chat messages. Tool results
+/// become `tool` messages named after the call they answer.
+fn to_ollama_messages(system: &str, messages: &[Message]) -> Vec<Value> {
+    let mut out = vec![json!({ "role": "system", "content": system })];
+    let mut tool_names: HashMap<&str, &str> = HashMap::new();
+
+    for message in messages {
+        let mut text = String::new();
+        let mut tool_calls = Vec::new();
+        for block in &message.content {
+            match block {
+                ContentBlock::Text { text: t } => text.push_str(t),
+                ContentBlock::ToolUse(call) => {
+                    tool_names.insert(&call.id, &call.name);
+                    tool_calls.push(json!({ "function": { "name": call.name, "arguments": call.input } }));
+                }
+                ContentBlock::ToolResult(result) => {
+                    let name = tool_names.get(result.tool_use_id.as_str()).copied().unwrap_or("");
+                    out.push(json!({ "role": "tool", "tool_name": name, "content": result.content }));
+                }
+            }
+        }
+
+        if !text.is_empty() || !tool_calls.is_empty() {
+            let mut msg = json!({ "role": message.role, "content": text });
+            if !tool_calls.is_empty() {
+                msg["tool_calls"] = Value::Array(tool_calls);
+            }
+            out.push(msg);
+        }
+    }
+    out
+}
+
+/// Incremental parser for Ollama's newline-delimited JSON stream.
+#[derive(Default)]
+struct StreamParser {
+    buffer: Vec<u8>,
+    text: String,
+    tool_calls: Vec<ToolCall>,
+    stop_reason: Option<String>,
+}
+
+impl StreamParser {
+    fn feed(&mut self, bytes: &[u8], on_chunk: &mut (dyn FnMut(StreamChunk) + Send)) -> Result<(), LlmError> {
+        self.buffer.extend_from_slice(bytes);
+
+        while let Some(idx) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line = String::from_utf8_lossy(&self.buffer[..idx]).trim().to_string();
+            self.buffer.drain(..=idx);
+            if line.is_empty() {
+                continue;
+            }
+
+            let parsed: ChatResponseLine = match serde_json::from_str(&line) {
+                Ok(v) => v,
+                Err(_) => continue,
+            };
+            if let Some(error) = parsed.error {
+                return Err(LlmError::Stream(error));
+            }
+
+            if let Some(message) = parsed.message {
+                if !message.content.is_empty() {
+                    on_chunk(StreamChunk::Text(message.content.clone()));
+                    self.text.push_str(&message.content);
+                }
+                for call in message.tool_calls {
+                    let call = ToolCall {
+                        // Ollama does not assign ids; the agent needs one to pair results
+                        id: format!("call_{}", Uuid::new_v4().simple()),
+                        name: call.function.name,
+                        input: if call.function.arguments.is_object() { call.function.arguments } else { json!({}) },
+                    };
+                    on_chunk(StreamChunk::ToolUse(call.clone()));
+                    self.tool_calls.push(call);
+                }
+            }
+
+            if parsed.done {
+                // Match the Anthropic stop reasons the agent logs
+                self.stop_reason = Some(if self.tool_calls.is_empty() {
+                    parsed.done_reason.unwrap_or_else(|| "end_turn".to_string())
+                } else {
+                    "tool_use".to_string()
+                });
+                on_chunk(StreamChunk::Done { stop_reason: self.stop_reason.clone() });
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> LlmCompletion {
+        let mut content = Vec::new();
+        if !self.text.is_empty() {
+            content.push(ContentBlock::Text { text: self.text });
+        }
+        content.extend(self.tool_calls.into_iter().map(ContentBlock::ToolUse));
+        LlmCompletion {
+            content,
+            stop_reason: self.stop_reason,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::llm::ToolResult;
+
+    #[test]
+    fn test_stream_parser_collects_text_and_tool_calls() {
+        let lines = concat!(
+            "{\"message\":{\"role\":\"assistant\",\"content\":\"Checking \"},\"done\":false}\n",
+            "{\"message\":{\"role\":\"assistant\",\"content\":\"Zürich\"},\"done\":false}\n",
+            "{\"message\":{\"role\":\"assistant\",\"content\":\"\",\"tool_calls\":[{\"function\":{\"name\":\"monthly_trend\",\"arguments\":{\"year\":2026}}}]},\"done\":false}\n",
+            "{\"message\":{\"role\":\"assistant\",\"content\":\"\"},\"done\":true,\"done_reason\":\"stop\"}\n",
+        )
+        .as_bytes();
+
+        let mut parser = StreamParser::default();
+        let mut chunks = Vec::new();
+        for piece in lines.chunks(5) {
+            parser.feed(piece, &mut |c| chunks.push(c)).unwrap();
+        }
+        let completion = parser.finish();
+
+        assert_eq!(completion.stop_reason.as_deref(), Some("tool_use"));
+        assert!(matches!(&completion.content[0], ContentBlock::Text { text } if text == "Checking Zürich"));
+        assert!(matches!(&completion.content[1], ContentBlock::ToolUse(call) if call.name == "monthly_trend" && call.input["year"] == 2026));
+        assert!(matches!(chunks.last(), Some(StreamChunk::Done { .. })));
+    }
+
+    #[test]
+    fn test_tool_results_are_named_after_their_call() {
+        let history = vec![
+            Message {
+                role: "assistant".to_string(),
+                content: vec![ContentBlock::ToolUse(ToolCall {
+                    id: "call_1".to_string(),
+                    name: "spending_by_category".to_string(),
+                    input: json!({}),
+                })],
+            },
+            Message {
+                role: "user".to_string(),
+                content: vec![ContentBlock::ToolResult(ToolResult {
+                    tool_use_id: "call_1".to_string(),
+                    content: "Dining: 42".to_string(),
+                    is_error: None,
+                })],
+            },
+        ];
+
+        let messages = to_ollama_messages("system", &history);
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[1]["tool_calls"][0]["function"]["name"], "spending_by_category");
+        assert_eq!(messages[2]["role"], "tool");
+        assert_eq!(messages[2]["tool_name"], "spending_by_category");
+    }
+}
@@ -0,0 +1,270 @@
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+use futures_util::StreamExt;
+use serde_json::{json, Value};
+
+use crate::ai::llm::{
+    http_client, CompletionRequest, ContentBlock, LlmCompletion, LlmError, LlmProvider, Message, StreamChunk,
+    ToolCall, DEFAULT_TIMEOUT,
+};
+
+/// Any server implementing the OpenAI chat completions API, e.g. the
+/// llama.cpp server, LM Studio or vLLM. `base_url` includes the version
+/// prefix, e.g. `http://localhost:8080/v1`.
+#[derive(Debug, Clone)]
+pub struct OpenAiProvider {
+    client: reqwest::Client,
+    base_url: String,
+    model: String,
+    api_key: Option<String>,
+}
+
+impl OpenAiProvider {
+    pub fn new(base_url: &str, model: &str, api_key: Option<&str>) -> Self {
+        Self {
+            client: http_client(DEFAULT_TIMEOUT),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            model: model.to_string(),
+            api_key: api_key.map(|k| k.to_string()),
+        }
+    }
+
+    /// Replace the default request timeout.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client = http_client(timeout);
+        self
+    }
+
+    fn request_body(&self, request: &CompletionRequest<'_>) -> Value {
+        let mut body = json!({
+            "model": self.model,
+            "messages": to_openai_messages(request.system, request.messages),
+            "stream": true,
+        });
+        if !request.tools.is_empty() {
+            body["tools"] = request
+                .tools
+                .iter()
+                .map(|t| json!({
+                    "type": "function",
+                    "function": { "name": t.name, "description": t.description, "parameters": t.input_schema },
+                }))
+                .collect();
+        }
+        if request.json_output {
+            body["response_format"] = json!({ "type": "json_object" });
+        }
+        body
+    }
+
+    async fn stream(
+        &self,
+        request: CompletionRequest<'_>,
+        on_chunk: &mut (dyn FnMut(StreamChunk) + Send),
+    ) -> Result<LlmCompletion, LlmError> {
+        let mut http = self
+            .client
+            .post(format!("{}/chat/completions", self.base_url))
+            .json(&self.request_body(&request));
+        if let Some(key) = &self.api_key {
+            http = http.bearer_auth(key);
+        }
+        let response = http.send().await.map_err(LlmError::Http)?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(LlmError::Api { status, body });
+        }
+
+        let mut parser = StreamParser::default();
+        let mut stream = response.bytes_stream();
+        while let Some(item) = stream.next().await {
+            let bytes = item.map_err(LlmError::Http)?;
+            parser.feed(&bytes, on_chunk)?;
+        }
+        parser.feed(b"\n", on_chunk)?;
+
+        Ok(parser.finish(on_chunk))
+    }
+}
+
+impl LlmProvider for OpenAiProvider {
+    fn complete_stream<'a>(
+        &'a self,
+        request: CompletionRequest<'a>,
+        on_chunk: &'a mut (dyn FnMut(StreamChunk) + Send),
+    ) -> BoxFuture<'a, Result<LlmCompletion, LlmError>> {
+        Box::pin(self.stream(request, on_chunk))
+    }
+}
+
+/// Convert Anthropic-shaped history into OpenAI chat messages. Tool results
+/// become `tool` messages carrying the id of the call they answer.
+fn to_openai_messages(system: &str, messages: &[Message]) -> Vec<Value> {
+    let mut out = vec![json!({ "role": "system", "content": system })];
+
+    for message in messages {
+        let mut text = String::new();
+        let mut tool_calls = Vec::new();
+        for block in &message.content {
+            match block {
+                ContentBlock::Text { text: t } => text.push_str(t),
+                ContentBlock::ToolUse(call) => tool_calls.push(json!({
+                    "id": call.id,
+                    "type": "function",
+                    "function": { "name": call.name, "arguments": call.input.to_string() },
+                })),
+                ContentBlock::ToolResult(result) => out.push(json!({
+                    "role": "tool",
+                    "tool_call_id": result.tool_use_id,
+                    "content": result.content,
+                })),
+            }
+        }
+
+        if !text.is_empty() || !tool_calls.is_empty() {
+            let mut msg = json!({ "role": message.role, "content": text });
+            if !tool_calls.is_empty() {
+                msg["tool_calls"] = Value::Array(tool_calls);
+            }
+            out.push(msg);
+        }
+    }
+    out
+}
+
+#[derive(Default)]
+struct PartialToolCall {
+    id: String,
+    name: String,
+    arguments: String,
+}
+
+/// Incremental parser for the chat completions server-sent event stream.
+/// Tool call arguments arrive in fragments keyed by index, so calls are only
+/// reported once the stream finishes.
+#[derive(Default)]
+struct StreamParser {
+    buffer: Vec<u8>,
+    text: String,
+    tool_calls: Vec<PartialToolCall>,
+    stop_reason: Option<String>,
+}
+
+impl StreamParser {
+    fn feed(&mut self, bytes: &[u8], on_chunk: &mut (dyn FnMut(StreamChunk) + Send)) -> Result<(), LlmError> {
+        self.buffer.extend_from_slice(bytes);
+
+        while let Some(idx) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line = String::from_utf8_lossy(&self.buffer[..idx]).trim().to_string();
+            self.buffer.drain(..=idx);
+
+            let Some(data) = line.strip_prefix("data:").map(|d| d.trim()) else { continue };
+            if data == "[DONE]" {
+                continue;
+            }
+            let value: Value = match serde_json::from_str(data) {
+                Ok(v) => v,
+                Err(_) => continue,
+            };
+            if let Some(error) = value.get("error") {
+                return Err(LlmError::Stream(error.to_string()));
+            }
+
+            let Some(choice) = value.pointer("/choices/0") else { continue };
+            if let Some(delta) = choice.get("delta") {
+                if let Some(text) = delta.get("content").and_then(|v| v.as_str()) {
+                    if !text.is_empty() {
+                        on_chunk(StreamChunk::Text(text.to_string()));
+                        self.text.push_str(text);
+                    }
+                }
+                for call in delta.get("tool_calls").and_then(|v| v.as_array()).into_iter().flatten() {
+                    let index = call.get("index").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+                    while self.tool_calls.len() <= index {
+                        self.tool_calls.push(PartialToolCall::default());
+                    }
+                    let partial = &mut self.tool_calls[index];
+                    if let Some(id) = call.get("id").and_then(|v| v.as_str()) {
+                        partial.id = id.to_string();
+                    }
+                    if let Some(name) = call.pointer("/function/name").and_then(|v| v.as_str()) {
+                        partial.name.push_str(name);
+                    }
+                    if let Some(args) = call.pointer("/function/arguments").and_then(|v| v.as_str()) {
+                        partial.arguments.push_str(args);
+                    }
+                }
+            }
+            if let Some(reason) = choice.get("finish_reason").and_then(|v| v.as_str()) {
+                // Match the Anthropic stop reasons the agent logs
+                self.stop_reason = Some(match reason {
+                    "tool_calls" => "tool_use".to_string(),
+                    "stop" => "end_turn".to_string(),
+                    other => other.to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self, on_chunk: &mut (dyn FnMut(StreamChunk) + Send)) -> LlmCompletion {
+        let mut content = Vec::new();
+        if !self.text.is_empty() {
+            content.push(ContentBlock::Text { text: self.text });
+        }
+        for (i, partial) in self.tool_calls.into_iter().enumerate() {
+            let call = ToolCall {
+                id: if partial.id.is_empty() { format!("call_{}", i) } else { partial.id },
+                name: partial.name,
+                input: serde_json::from_str(&partial.arguments).unwrap_or_else(|_| json!({})),
+            };
+            on_chunk(StreamChunk::ToolUse(call.clone()));
+            content.push(ContentBlock::ToolUse(call));
+        }
+        on_chunk(StreamChunk::Done { stop_reason: self.stop_reason.clone() });
+
+        LlmCompletion {
+            content,
+            stop_reason: self.stop_reason,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_parser_joins_fragmented_tool_arguments() {
+        let events = concat!(
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"Let me look.\"}}]}\n\n",
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"call_a\",\"type\":\"function\",\"function\":{\"name\":\"budget_status\",\"arguments\":\"\"}}]}}]}\n\n",
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"{\\\"month\\\":\"}}]}}]}\n\n",
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\" 2}\"}}]}}]}\n\n",
+            "data: {\"choices\":[{\"index\":0,\"delta\":{},\"finish_reason\":\"tool_calls\"}]}\n\n",
+            "data: [DONE]\n\n",
+        )
+        .as_bytes();
+
+        let mut parser = StreamParser::default();
+        let mut chunks = Vec::new();
+        for piece in events.chunks(9) {
+            parser.feed(piece, &mut |c| chunks.push(c)).unwrap();
+        }
+        let completion = parser.finish(&mut |c| chunks.push(c));
+
+        assert_eq!(completion.stop_reason.as_deref(), Some("tool_use"));
+        match &completion.content[1] {
+            ContentBlock::ToolUse(call) => {
+                assert_eq!(call.id, "call_a");
+                assert_eq!(call.name, "budget_status");
+                assert_eq!(call.input, json!({ "month": 2 }));
+            }
+            other => panic!("expected tool use, got {:?}", other),
+        }
+        assert!(matches!(&chunks[0], StreamChunk::Text(t) if t == "Let me look."));
+    }
+}
@@ -0,0 +1,129 @@
+use chrono::{Datelike, NaiveDate};
+
+/// Differences below this are rounding, not a missing booking.
+const TOLERANCE: f64 = 0.005;
+
+/// A balance the bank reported at the end of `date`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReportedBalance {
+    pub date: NaiveDate,
+    pub balance: f64,
+}
+
+/// Two reported balances whose difference is not explained by the bookings
+/// between them, usually because a statement was not imported.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BalanceGap {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub reported_change: f64,
+    pub booked_change: f64,
+}
+
+impl BalanceGap {
+    /// Net amount of the bookings that are missing.
+    pub fn missing(&self) -> f64 {
+        self.reported_change - self.booked_change
+    }
+}
+
+/// Compare each pair of consecutive reported balances with the sum of the
+/// bookings `(date, amount)` after the first and up to the second.
+pub fn reconcile(reported: &[ReportedBalance], bookings: &[(NaiveDate, f64)]) -> Vec<BalanceGap> {
+    let mut reported = reported.to_vec();
+    reported.sort_by_key(|r| r.date);
+
+    reported
+        .windows(2)
+        .filter_map(|pair| {
+            let (start, end) = (pair[0], pair[1]);
+            let booked_change: f64 = bookings
+                .iter()
+                .filter(|(date, _)| *date > start.date && *date <= end.date)
+                .map(|(_, amount)| amount)
+                .sum();
+            let reported_change = end.balance - start.balance;
+            ((reported_change - booked_change).abs() >= TOLERANCE).then_some(BalanceGap {
+                from: start.date,
+                to: end.date,
+                reported_change,
+                booked_change,
+            })
+        })
+        .collect()
+}
+
+/// Balance at the end of `date`, walking the bookings forwards or backwards
+/// from `anchor`. Without an anchor the balance is counted from zero.
+pub fn balance_at(anchor: Option<ReportedBalance>, bookings: &[(NaiveDate, f64)], date: NaiveDate) -> f64 {
+    let Some(anchor) = anchor else {
+        return bookings.iter().filter(|(d, _)| *d <= date).map(|(_, a)| a).sum();
+    };
+    let between: f64 = bookings
+        .iter()
+        .filter(|(d, _)| (*d > anchor.date && *d <= date) || (*d > date && *d <= anchor.date))
+        .map(|(_, a)| a)
+        .sum();
+    if date >= anchor.date {
+        anchor.balance + between
+    } else {
+        anchor.balance - between
+    }
+}
+
+/// Last day of every month from `first` to `last`, inclusive.
+pub fn month_ends(first: NaiveDate, last: NaiveDate) -> Vec<NaiveDate> {
+    let mut ends = Vec::new();
+    let (mut year, mut month) = (first.year(), first.month());
+    while (year, month) <= (last.year(), last.month()) {
+        let (next_year, next_month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+        let end = NaiveDate::from_ymd_opt(next_year, next_month, 1)
+            .and_then(|d| d.pred_opt())
+            .expect("valid month");
+        ends.push(end);
+        (year, month) = (next_year, next_month);
+    }
+    ends
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn r(date: &str, balance: f64) -> ReportedBalance {
+        ReportedBalance { date: d(date), balance }
+    }
+
+    #[test]
+    fn test_reconcile_flags_unexplained_changes() {
+        let bookings = vec![(d("2026-01-05"), -100.0), (d("2026-01-20"), 2500.0), (d("2026-02-10"), -40.0)];
+        // January adds up; February's statement is missing a 60.00 debit
+        let reported = vec![r("2026-02-28", 3300.0), r("2025-12-31", 1000.0), r("2026-01-31", 3400.0)];
+        let gaps = reconcile(&reported, &bookings);
+        assert_eq!(gaps.len(), 1);
+        assert_eq!((gaps[0].from, gaps[0].to), (d("2026-01-31"), d("2026-02-28")));
+        assert!((gaps[0].missing() - -60.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_balance_at_walks_from_anchor() {
+        let bookings = vec![(d("2026-01-05"), -100.0), (d("2026-01-20"), 2500.0), (d("2026-02-10"), -40.0)];
+        let anchor = Some(r("2026-01-31", 3400.0));
+        assert_eq!(balance_at(anchor, &bookings, d("2026-02-28")), 3360.0);
+        assert_eq!(balance_at(anchor, &bookings, d("2026-01-10")), 900.0);
+        assert_eq!(balance_at(anchor, &bookings, d("2025-12-31")), 1000.0);
+        assert_eq!(balance_at(None, &bookings, d("2026-01-31")), 2400.0);
+    }
+
+    #[test]
+    fn test_month_ends_span_years() {
+        assert_eq!(
+            month_ends(d("2025-11-15"), d("2026-02-03")),
+            vec![d("2025-11-30"), d("2025-12-31"), d("2026-01-31"), d("2026-02-28")]
+        );
+    }
+}
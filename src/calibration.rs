@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+
+/// Calibrated confidence below which a classification goes to review.
+pub const REVIEW_THRESHOLD: f64 = 0.80;
+
+/// How many reviews it takes before the observed accuracy of a group counts as
+/// much as what the classifier (or the wider group) claims for it.
+const PRIOR_REVIEWS: f64 = 10.0;
+
+/// Changes in `transaction_history` made by a person looking at the result.
+pub const HUMAN_CHANGES: [&str; 3] = ["review", "recategorise", "chat: recategorise"];
+
+/// A person confirming or correcting one classification.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Judgement {
+    pub source: String,
+    pub category: String,
+    pub confidence: f64,
+    /// The category was kept.
+    pub correct: bool,
+}
+
+/// How far to trust the confidence of one source, or of one category within a
+/// source when `category` is set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalibrationEntry {
+    pub source: String,
+    pub category: Option<String>,
+    pub reviews: usize,
+    pub correct: usize,
+    pub mean_confidence: f64,
+    /// Reported confidence is multiplied by this.
+    pub factor: f64,
+}
+
+impl CalibrationEntry {
+    pub fn calibrate(&self, confidence: f64) -> f64 {
+        (confidence * self.factor).clamp(0.0, 1.0)
+    }
+}
+
+fn fit_group(source: &str, category: Option<&str>, judgements: &[&Judgement], prior_factor: f64) -> CalibrationEntry {
+    let n = judgements.len() as f64;
+    let correct = judgements.iter().filter(|j| j.correct).count();
+    let mean_confidence = judgements.iter().map(|j| j.confidence).sum::<f64>() / n;
+    // Observed accuracy, pulled towards what the prior factor predicts
+    let expected = (mean_confidence * prior_factor).min(1.0);
+    let accuracy = (correct as f64 + PRIOR_REVIEWS * expected) / (n + PRIOR_REVIEWS);
+    CalibrationEntry {
+        source: source.to_string(),
+        category: category.map(str::to_string),
+        reviews: judgements.len(),
+        correct,
+        mean_confidence,
+        factor: if mean_confidence > 0.0 { accuracy / mean_confidence } else { prior_factor },
+    }
+}
+
+/// `result`'s confidence scaled by the entry for its source and category,
+/// falling back to the source's entry, as `CALIBRATED_CONFIDENCE` does for
+/// stored transactions. Manual classifications are taken as they are.
+pub fn calibrated_confidence(entries: &[CalibrationEntry], source: &str, category: &str, confidence: f64) -> f64 {
+    if source == "manual" {
+        return confidence;
+    }
+    let find = |category: Option<&str>| entries.iter().find(|e| e.source == source && e.category.as_deref() == category);
+    match find(Some(category)).or_else(|| find(None)) {
+        Some(entry) => entry.calibrate(confidence),
+        None => confidence,
+    }
+}
+
+/// Fit a factor per source, and per category within each source shrunk
+/// towards the source's factor, so a handful of reviews only nudges it.
+/// Manual classifications need no calibration and are left out.
+pub fn fit(judgements: &[Judgement]) -> Vec<CalibrationEntry> {
+    let mut by_source: BTreeMap<&str, Vec<&Judgement>> = BTreeMap::new();
+    for j in judgements.iter().filter(|j| j.source != "manual") {
+        by_source.entry(j.source.as_str()).or_default().push(j);
+    }
+
+    let mut entries = Vec::new();
+    for (source, judgements) in by_source {
+        let source_entry = fit_group(source, None, &judgements, 1.0);
+        let factor = source_entry.factor;
+        entries.push(source_entry);
+        let mut by_category: BTreeMap<&str, Vec<&Judgement>> = BTreeMap::new();
+        for j in &judgements {
+            by_category.entry(j.category.as_str()).or_default().push(j);
+        }
+        for (category, judgements) in by_category {
+            entries.push(fit_group(source, Some(category), &judgements, factor));
+        }
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn judgement(source: &str, category: &str, confidence: f64, correct: bool) -> Judgement {
+        Judgement { source: source.to_string(), category: category.to_string(), confidence, correct }
+    }
+
+    #[test]
+    fn test_overconfident_source_is_scaled_down() {
+        // The LLM claims 0.9 but half its Shopping answers were wrong; Transport is fine
+        let mut judgements = Vec::new();
+        judgements.extend((0..10).map(|i| judgement("llm", "Shopping", 0.9, i % 2 == 0)));
+        judgements.extend((0..10).map(|_| judgement("llm", "Transport", 0.9, true)));
+        judgements.push(judgement("manual", "Dining", 1.0, false));
+
+        let entries = fit(&judgements);
+        assert_eq!(entries.len(), 3, "manual is not calibrated");
+        let find = |category: Option<&str>| entries.iter().find(|e| e.category.as_deref() == category).unwrap();
+
+        let llm = find(None);
+        assert_eq!((llm.reviews, llm.correct), (20, 15));
+        // (15 + 10 * 0.9) / 30 = 0.8
+        assert!((llm.calibrate(0.9) - 0.8).abs() < 1e-9);
+
+        let shopping = find(Some("Shopping"));
+        let transport = find(Some("Transport"));
+        assert!(shopping.calibrate(0.9) < REVIEW_THRESHOLD, "{:?}", shopping);
+        assert!(transport.calibrate(0.9) >= REVIEW_THRESHOLD, "{:?}", transport);
+        assert!(transport.calibrate(0.9) <= 1.0);
+
+        assert_eq!(calibrated_confidence(&entries, "llm", "Shopping", 0.9), shopping.calibrate(0.9));
+        assert_eq!(calibrated_confidence(&entries, "llm", "Dining", 0.9), llm.calibrate(0.9), "falls back to the source");
+        assert_eq!(calibrated_confidence(&entries, "rules", "Fees", 0.9), 0.9);
+        assert_eq!(calibrated_confidence(&entries, "manual", "Dining", 1.0), 1.0);
+    }
+
+    #[test]
+    fn test_few_reviews_barely_move_confidence() {
+        let entries = fit(&[judgement("rules", "Fees", 0.95, false)]);
+        let rules = entries.iter().find(|e| e.category.is_none()).unwrap();
+        // (0 + 10 * 0.95) / 11
+        assert!((rules.calibrate(0.95) - 9.5 / 11.0).abs() < 1e-9);
+    }
+}
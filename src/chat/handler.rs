@@ -1,13 +1,14 @@
-use axum::extract::State;
+use axum::extract::{Path, State};
 use axum::http::StatusCode;
 use axum::response::sse::{Event, Sse};
-use axum::routing::post;
+use axum::routing::{get, post};
 use axum::{Json, Router};
 use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
 use tracing::{info, error};
 
 use crate::ai::agent::{Agent, AgentEvent};
-use crate::chat::sessions::SessionStore;
+use crate::chat::sessions::{Conversation, ConversationInfo, SessionStore};
 
 #[derive(Clone)]
 pub struct ChatState {
@@ -58,6 +59,11 @@ pub struct ResetRequest {
     pub conversation_id: String,
 }
 
+#[derive(Deserialize)]
+pub struct RenameRequest {
+    pub title: String,
+}
+
 pub async fn chat(
     State(state): State<ChatState>,
     Json(req): Json<ChatRequest>,
@@ -68,64 +74,66 @@ pub async fn chat(
     let sessions = state.sessions.clone();
 
     let stream = async_stream::stream! {
-        let (conversation_id, mut history) = sessions.get_or_create(conv_id.as_deref()).await;
+        let (conversation_id, mut history) = match sessions.get_or_create(conv_id.as_deref()).await {
+            Ok(session) => session,
+            Err(err) => {
+                error!(error = %err, "failed to load conversation");
+                let err_event = Event::default()
+                    .event("error")
+                    .json_data(SseError { message: format!("failed to load conversation: {}", err) });
+                if let Ok(evt) = err_event {
+                    yield Ok(evt);
+                }
+                return;
+            }
+        };
         info!(conversation_id = %conversation_id, message = %msg, history_len = history.len(), "chat request");
 
-        match agent.chat(&mut history, &msg).await {
-            Ok((reply, events)) => {
-                info!(conversation_id = %conversation_id, tools = ?reply.tools_used, events = events.len(), "chat complete");
-                sessions.save_history(&conversation_id, history).await;
-
-                // Emit agent events in order (tool_use + chart_artifact)
-                for event in events {
-                    match event {
-                        AgentEvent::ToolRunning { tool } => {
-                            let sse = Event::default()
-                                .event("tool_use")
-                                .json_data(SseToolUse { tool, status: "running".to_string() });
-                            if let Ok(evt) = sse {
-                                yield Ok(evt);
-                            }
-                        }
-                        AgentEvent::ToolCompleted { tool } => {
-                            let sse = Event::default()
-                                .event("tool_use")
-                                .json_data(SseToolUse { tool, status: "completed".to_string() });
-                            if let Ok(evt) = sse {
-                                yield Ok(evt);
-                            }
-                        }
-                        AgentEvent::ChartArtifact(chart) => {
-                            let artifact = SseChartArtifact {
-                                chart_type: chart.chart_type,
-                                title: chart.title,
-                                data: serde_json::to_value(&chart.data).unwrap_or(serde_json::Value::Null),
-                                height: chart.height,
-                            };
-                            let sse = Event::default()
-                                .event("chart_artifact")
-                                .json_data(artifact);
-                            if let Ok(evt) = sse {
-                                yield Ok(evt);
-                            }
-                        }
-                    }
-                }
+        // Run and save the turn on its own task, so it is kept even if the
+        // client disconnects; the stream only forwards events and the outcome
+        let (tx, mut rx) = mpsc::unbounded_channel::<AgentEvent>();
+        let turn_conversation = conversation_id.clone();
+        let task = tokio::spawn(async move {
+            let reply = agent.chat(&turn_conversation, &mut history, &msg, &tx).await.map_err(|err| err.to_string())?;
+            info!(conversation_id = %turn_conversation, tools = ?reply.tools_used, charts = reply.charts.len(), text_len = reply.text.len(), "chat complete");
+            if let Err(err) = sessions.save_history(&turn_conversation, &history, &reply.charts).await {
+                error!(conversation_id = %turn_conversation, error = %err, "failed to save conversation");
+            }
+            Ok::<_, String>(reply)
+        });
+
+        while let Some(event) = rx.recv().await {
+            let sse = match event {
+                AgentEvent::TextDelta(text) => Event::default()
+                    .event("chunk")
+                    .json_data(SseChunk { text }),
+                AgentEvent::ToolRunning { tool } => Event::default()
+                    .event("tool_use")
+                    .json_data(SseToolUse { tool, status: "running".to_string() }),
+                AgentEvent::ToolCompleted { tool } => Event::default()
+                    .event("tool_use")
+                    .json_data(SseToolUse { tool, status: "completed".to_string() }),
+                AgentEvent::ChartArtifact(chart) => Event::default()
+                    .event("chart_artifact")
+                    .json_data(SseChartArtifact {
+                        chart_type: chart.chart_type,
+                        title: chart.title,
+                        data: serde_json::to_value(&chart.data).unwrap_or(serde_json::Value::Null),
+                        height: chart.height,
+                    }),
+            };
+            if let Ok(evt) = sse {
+                yield Ok(evt);
+            }
+        }
 
-                // Stream text response
-                if !reply.text.is_empty() {
-                    for chunk in reply.text.split_whitespace() {
-                        let sse = Event::default()
-                            .event("chunk")
-                            .json_data(SseChunk {
-                                text: format!("{} ", chunk),
-                            });
-                        if let Ok(evt) = sse {
-                            yield Ok(evt);
-                        }
-                    }
-                }
+        let result = match task.await {
+            Ok(result) => result,
+            Err(err) => Err(format!("agent task failed: {}", err)),
+        };
 
+        match result {
+            Ok(reply) => {
                 let reason = if reply.incomplete {
                     Some("max_iterations".to_string())
                 } else {
@@ -141,8 +149,7 @@ pub async fn chat(
                     yield Ok(evt);
                 }
             }
-            Err(err) => {
-                let msg = format!("{:?}", err);
+            Err(msg) => {
                 error!(conversation_id = %conversation_id, error = %msg, "agent error");
                 let err_event = Event::default()
                     .event("error")
@@ -161,13 +168,80 @@ pub async fn reset(
     State(state): State<ChatState>,
     Json(req): Json<ResetRequest>,
 ) -> StatusCode {
-    state.sessions.delete(&req.conversation_id).await;
-    StatusCode::OK
+    match state.sessions.delete(&req.conversation_id).await {
+        Ok(_) => StatusCode::OK,
+        Err(err) => {
+            error!(error = %err, "failed to delete conversation");
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+pub async fn list_conversations(
+    State(state): State<ChatState>,
+) -> Result<Json<Vec<ConversationInfo>>, StatusCode> {
+    state.sessions.list().await.map(Json).map_err(|err| {
+        error!(error = %err, "failed to list conversations");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+pub async fn get_conversation(
+    State(state): State<ChatState>,
+    Path(id): Path<String>,
+) -> Result<Json<Conversation>, StatusCode> {
+    match state.sessions.get(&id).await {
+        Ok(Some(conversation)) => Ok(Json(conversation)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(err) => {
+            error!(conversation_id = %id, error = %err, "failed to load conversation");
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+pub async fn rename_conversation(
+    State(state): State<ChatState>,
+    Path(id): Path<String>,
+    Json(req): Json<RenameRequest>,
+) -> StatusCode {
+    if req.title.trim().is_empty() {
+        return StatusCode::BAD_REQUEST;
+    }
+    match state.sessions.rename(&id, &req.title).await {
+        Ok(true) => StatusCode::OK,
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(err) => {
+            error!(conversation_id = %id, error = %err, "failed to rename conversation");
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+pub async fn delete_conversation(
+    State(state): State<ChatState>,
+    Path(id): Path<String>,
+) -> StatusCode {
+    match state.sessions.delete(&id).await {
+        Ok(true) => StatusCode::NO_CONTENT,
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(err) => {
+            error!(conversation_id = %id, error = %err, "failed to delete conversation");
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
 }
 
 pub fn router(state: ChatState) -> Router {
     Router::new()
         .route("/api/chat", post(chat))
         .route("/api/chat/reset", post(reset))
+        .route("/api/conversations", get(list_conversations))
+        .route(
+            "/api/conversations/:id",
+            get(get_conversation)
+                .patch(rename_conversation)
+                .delete(delete_conversation),
+        )
         .with_state(state)
 }
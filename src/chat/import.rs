@@ -0,0 +1,221 @@
+use std::path::{Path, PathBuf};
+
+use axum::extract::{DefaultBodyLimit, Multipart, State};
+use axum::http::StatusCode;
+use axum::response::sse::{Event, Sse};
+use axum::routing::post;
+use axum::Router;
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tracing::{info, error};
+
+use crate::ai::agent::Agent;
+use crate::chat::handler::SseError;
+use crate::rules::SharedRules;
+use crate::classifier::Classifier;
+use crate::config::ProviderConfig;
+use crate::db::Database;
+use crate::{import_file_with_progress, ImportProgress, ImportStats};
+
+/// Largest CSV accepted by the upload endpoint.
+const MAX_UPLOAD_BYTES: usize = 20 * 1024 * 1024;
+
+#[derive(Clone)]
+pub struct ImportState {
+    pub agent: Agent,
+    pub database_url: String,
+    pub classifier: ProviderConfig,
+    pub rules: SharedRules,
+    pub base_currency: String,
+}
+
+#[derive(Serialize)]
+pub struct SseImportDone {
+    pub filename: String,
+    pub stats: ImportStats,
+    pub total_transactions: i64,
+}
+
+/// Accepts a multipart upload with a `file` field holding a UBS CSV export and
+/// streams one `progress` event per classified transaction, then `done` with the
+/// import statistics. Uploading a file whose import was interrupted resumes it,
+/// as long as the file is unchanged.
+pub async fn import(
+    State(state): State<ImportState>,
+    mut multipart: Multipart,
+) -> Result<Sse<impl futures_util::stream::Stream<Item = Result<Event, std::fmt::Error>>>, (StatusCode, String)> {
+    let mut upload: Option<(String, Vec<u8>)> = None;
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?
+    {
+        if field.name() != Some("file") {
+            continue;
+        }
+        let filename = field
+            .file_name()
+            .and_then(|name| Path::new(name).file_name())
+            .and_then(|name| name.to_str())
+            .map(|name| name.to_string())
+            .ok_or_else(|| (StatusCode::BAD_REQUEST, "upload is missing a filename".to_string()))?;
+        let bytes = field
+            .bytes()
+            .await
+            .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+        upload = Some((filename, bytes.to_vec()));
+    }
+
+    let Some((filename, bytes)) = upload else {
+        return Err((StatusCode::BAD_REQUEST, "expected a multipart field named 'file'".to_string()));
+    };
+    if !filename.to_ascii_lowercase().ends_with(".csv") {
+        return Err((StatusCode::BAD_REQUEST, "only .csv files can be imported".to_string()));
+    }
+
+    // Keep the original filename so the import batch matches a CLI import of the same file
+    let dir = tempfile::tempdir().map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let csv_path = dir.path().join(&filename);
+    std::fs::write(&csv_path, &bytes).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    info!(filename = %filename, bytes = bytes.len(), "import upload received");
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<ImportProgress>();
+    let db_path = PathBuf::from(&state.database_url);
+    let classifier_config = state.classifier.clone();
+    let rules = state.rules.current();
+    let base_currency = state.base_currency.clone();
+    let handle = tokio::task::spawn_blocking(move || -> Result<ImportStats, String> {
+        let _dir = dir;
+        let db = Database::open(&db_path).map_err(|e| e.to_string())?;
+        let categories = db.list_categories().map_err(|e| e.to_string())?;
+        let classifier = Classifier::from_config(&classifier_config)?;
+        // The upload is the only copy of the file, so there is nothing to
+        // resume from later; pick up an interrupted import of it right away
+        let stats = import_file_with_progress(&db, &classifier, &rules, &csv_path, &categories, true, |progress| {
+            // The client may have disconnected; keep importing regardless
+            let _ = tx.send(progress);
+        })
+        .map_err(|e| e.to_string())?;
+        db.refresh_base_rates(&base_currency).map_err(|e| e.to_string())?;
+        Ok(stats)
+    });
+
+    let agent = state.agent.clone();
+    let stream = async_stream::stream! {
+        while let Some(progress) = rx.recv().await {
+            if let Ok(evt) = Event::default().event("progress").json_data(progress) {
+                yield Ok(evt);
+            }
+        }
+
+        let result = match handle.await {
+            Ok(result) => result,
+            Err(err) => Err(format!("import task failed: {}", err)),
+        };
+
+        match result {
+            Ok(stats) => {
+                info!(filename = %filename, stats = ?stats, "import complete");
+                let total_transactions = match agent.refresh_data_summary().await {
+                    Ok(summary) => summary.total_transactions,
+                    Err(err) => {
+                        error!(error = %err, "failed to refresh data summary after import");
+                        0
+                    }
+                };
+                let done = SseImportDone { filename, stats, total_transactions };
+                if let Ok(evt) = Event::default().event("done").json_data(done) {
+                    yield Ok(evt);
+                }
+            }
+            Err(message) => {
+                error!(filename = %filename, error = %message, "import failed");
+                if let Ok(evt) = Event::default().event("error").json_data(SseError { message }) {
+                    yield Ok(evt);
+                }
+            }
+        }
+    };
+
+    Ok(Sse::new(stream))
+}
+
+pub fn router(state: ImportState) -> Router {
+    Router::new()
+        .route("/api/import", post(import))
+        .layer(DefaultBodyLimit::max(MAX_UPLOAD_BYTES))
+        .with_state(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use axum::body::Body;
+    use axum::extract::FromRequest;
+    use axum::http::Request;
+    use axum::response::IntoResponse;
+    use crate::ai::ollama::OllamaProvider;
+    use crate::rules::RuleSet;
+    use crate::tools::ToolRegistry;
+
+    #[tokio::test]
+    async fn test_upload_resumes_interrupted_import() -> Result<(), Box<dyn std::error::Error>> {
+        let fixture = Path::new("tests/fixtures/account_statement_tiny.csv");
+        if !fixture.exists() { return Ok(()); }
+        let dir = tempfile::tempdir()?;
+        let db_path = dir.path().join("upload.db");
+        let config = crate::config::Config::defaults();
+
+        // An earlier upload of the file stopped after its first row
+        let db = Database::open(&db_path)?;
+        let cats = db.list_categories()?;
+        let (_, transactions) = crate::csv_parser::parse_csv(fixture)?;
+        let hash = crate::coverage::file_hash(fixture)?;
+        let log_id = db.start_import("account_statement_tiny.csv", Some(&hash))?;
+        let classified = crate::classifier::ClassificationResult {
+            merchant: "Steuerverwaltung".to_string(),
+            category: "Fees".to_string(),
+            confidence: 0.95,
+            source: "rules".to_string(),
+        };
+        db.checkpoint_import(log_id, 1, || -> rusqlite::Result<usize> {
+            db.insert_transaction(&transactions[0], &classified, Some("account_statement_tiny.csv"))?;
+            Ok(1)
+        })?;
+
+        // Rules cover the remaining row, so the LLM is not needed
+        let rules_path = dir.path().join("rules.toml");
+        std::fs::write(&rules_path, "[[rules]]\ndescription = 'SBB MOBILE'\ncategory = 'Transport'\n")?;
+        let pool = crate::db::connect_pool(db_path.to_str().unwrap()).await?;
+        let llm = Arc::new(OllamaProvider::new("http://127.0.0.1:1", "dummy-model"));
+        let state = ImportState {
+            agent: Agent::new(llm, ToolRegistry::new(), String::new(), pool),
+            database_url: db_path.to_str().unwrap().to_string(),
+            classifier: config.classifier.clone(),
+            rules: SharedRules::new(RuleSet::load(&[rules_path], &cats)?),
+            base_currency: "CHF".to_string(),
+        };
+
+        let boundary = "upload-boundary";
+        let mut body = format!(
+            "--{b}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"account_statement_tiny.csv\"\r\nContent-Type: text/csv\r\n\r\n",
+            b = boundary
+        ).into_bytes();
+        body.extend(std::fs::read(fixture)?);
+        body.extend(format!("\r\n--{}--\r\n", boundary).into_bytes());
+        let request = Request::post("/api/import")
+            .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+            .body(Body::from(body))?;
+        let multipart = Multipart::from_request(request, &()).await.map_err(|e| e.body_text())?;
+
+        let response = import(State(state), multipart).await.map_err(|(_, e)| e)?.into_response();
+        let events = String::from_utf8(axum::body::to_bytes(response.into_body(), usize::MAX).await?.to_vec())?;
+        assert!(!events.contains("event: error"), "{}", events);
+        assert_eq!(events.matches("event: progress").count(), 1, "only the row after the checkpoint");
+        assert!(events.contains(r#""new_insertions":2"#) && events.contains(r#""resumed_rows":1"#), "{}", events);
+        assert!(db.interrupted_import("account_statement_tiny.csv")?.is_none());
+        Ok(())
+    }
+}
+
@@ -1,5 +1,7 @@
 pub mod sessions;
 pub mod handler;
+pub mod import;
 
 pub use sessions::SessionStore;
-pub use handler::{ChatState, router};
\ No newline at end of file
+pub use handler::{ChatState, router};
+pub use import::{ImportState, router as import_router};
\ No newline at end of file
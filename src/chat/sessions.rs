@@ -1,80 +1,320 @@
-use std::collections::HashMap;
-use std::sync::Arc;
-use std::time::{Duration, SystemTime};
-use tokio::sync::RwLock;
+use chrono::Utc;
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
 use uuid::Uuid;
 
-use crate::ai::llm::Message;
+use crate::ai::llm::{ContentBlock, Message};
+use crate::tools::ChartSpec;
 
-pub struct SessionEntry {
-    pub history: Vec<Message>,
-    pub created_at: SystemTime,
-    pub last_accessed: SystemTime,
-}
+/// Longest title derived from the first user message.
+const TITLE_MAX_CHARS: usize = 60;
 
+/// Conversation history persisted in the `conversations`, `messages` and
+/// `chart_artifacts` tables, so chats survive a server restart.
 #[derive(Clone)]
 pub struct SessionStore {
-    inner: Arc<RwLock<HashMap<String, SessionEntry>>>,
+    pool: SqlitePool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConversationInfo {
+    pub id: String,
+    pub title: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub message_count: i64,
+}
+
+/// A chart emitted during a conversation. `message_seq` is the position of the
+/// user message whose turn produced it.
+#[derive(Debug, Clone, Serialize)]
+pub struct StoredChart {
+    pub message_seq: i64,
+    pub chart: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Conversation {
+    #[serde(flatten)]
+    pub info: ConversationInfo,
+    pub messages: Vec<Message>,
+    pub charts: Vec<StoredChart>,
 }
 
 impl SessionStore {
-    pub fn new() -> Self {
-        Self {
-            inner: Arc::new(RwLock::new(HashMap::new())),
-        }
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
     }
 
-    pub async fn get_or_create(&self, conversation_id: Option<&str>) -> (String, Vec<Message>) {
+    pub async fn get_or_create(&self, conversation_id: Option<&str>) -> Result<(String, Vec<Message>), sqlx::Error> {
         let id = conversation_id
             .map(|s| s.to_string())
             .unwrap_or_else(|| Uuid::new_v4().to_string());
 
-        let mut store = self.inner.write().await;
-        self.evict_expired(&mut store);
+        let now = Utc::now().to_rfc3339();
+        sqlx::query(
+            "INSERT OR IGNORE INTO conversations (id, title, created_at, updated_at) VALUES (?, '', ?, ?)",
+        )
+        .bind(&id)
+        .bind(&now)
+        .bind(&now)
+        .execute(&self.pool)
+        .await?;
 
-        let (history, is_new) = if let Some(entry) = store.get_mut(&id) {
-            entry.last_accessed = SystemTime::now();
-            (entry.history.clone(), false)
-        } else {
-            (Vec::new(), true)
-        };
+        let history = self.load_messages(&id).await?;
+        Ok((id, history))
+    }
+
+    /// Persist the messages appended to `history` since it was loaded, along with
+    /// the charts emitted during this turn.
+    pub async fn save_history(
+        &self,
+        conversation_id: &str,
+        history: &[Message],
+        charts: &[ChartSpec],
+    ) -> Result<(), sqlx::Error> {
+        let now = Utc::now().to_rfc3339();
+        let mut tx = self.pool.begin().await?;
+
+        let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM messages WHERE conversation_id = ?")
+            .bind(conversation_id)
+            .fetch_one(&mut *tx)
+            .await?;
 
-        if is_new {
-            store.insert(
-                id.clone(),
-                SessionEntry {
-                    history: Vec::new(),
-                    created_at: SystemTime::now(),
-                    last_accessed: SystemTime::now(),
-                },
-            );
+        for (seq, message) in history.iter().enumerate().skip(stored as usize) {
+            let content = serde_json::to_string(&message.content).unwrap_or_else(|_| "[]".to_string());
+            sqlx::query(
+                "INSERT INTO messages (conversation_id, seq, role, content, created_at) VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(conversation_id)
+            .bind(seq as i64)
+            .bind(&message.role)
+            .bind(content)
+            .bind(&now)
+            .execute(&mut *tx)
+            .await?;
         }
 
-        (id, history)
+        // Charts belong to the user message that started this turn
+        let turn_seq = history
+            .iter()
+            .enumerate()
+            .skip(stored as usize)
+            .find(|(_, m)| m.role == "user" && first_text(m).is_some())
+            .map(|(seq, _)| seq as i64)
+            .unwrap_or(stored);
+        for chart in charts {
+            let chart = serde_json::to_string(chart).unwrap_or_else(|_| "null".to_string());
+            sqlx::query(
+                "INSERT INTO chart_artifacts (conversation_id, message_seq, chart, created_at) VALUES (?, ?, ?, ?)",
+            )
+            .bind(conversation_id)
+            .bind(turn_seq)
+            .bind(chart)
+            .bind(&now)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let title = history
+            .iter()
+            .find(|m| m.role == "user")
+            .and_then(first_text)
+            .map(derive_title)
+            .unwrap_or_default();
+        sqlx::query(
+            "UPDATE conversations
+             SET updated_at = ?, title = CASE WHEN title = '' THEN ? ELSE title END
+             WHERE id = ?",
+        )
+        .bind(&now)
+        .bind(title)
+        .bind(conversation_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await
+    }
+
+    pub async fn list(&self) -> Result<Vec<ConversationInfo>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT c.id, c.title, c.created_at, c.updated_at, COUNT(m.id) as message_count
+             FROM conversations c
+             LEFT JOIN messages m ON m.conversation_id = c.id
+             GROUP BY c.id
+             HAVING message_count > 0
+             ORDER BY c.updated_at DESC",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(conversation_info).collect()
     }
 
-    pub async fn save_history(&self, conversation_id: &str, history: Vec<Message>) {
-        let mut store = self.inner.write().await;
-        if let Some(entry) = store.get_mut(conversation_id) {
-            entry.history = history;
-            entry.last_accessed = SystemTime::now();
+    pub async fn get(&self, conversation_id: &str) -> Result<Option<Conversation>, sqlx::Error> {
+        let row = sqlx::query(
+            "SELECT c.id, c.title, c.created_at, c.updated_at,
+                    (SELECT COUNT(*) FROM messages m WHERE m.conversation_id = c.id) as message_count
+             FROM conversations c WHERE c.id = ?",
+        )
+        .bind(conversation_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        let Some(row) = row else { return Ok(None) };
+
+        let charts = sqlx::query("SELECT message_seq, chart FROM chart_artifacts WHERE conversation_id = ? ORDER BY id ASC")
+            .bind(conversation_id)
+            .fetch_all(&self.pool)
+            .await?
+            .iter()
+            .map(|r| {
+                let chart: String = r.try_get("chart")?;
+                Ok(StoredChart {
+                    message_seq: r.try_get("message_seq")?,
+                    chart: serde_json::from_str(&chart).unwrap_or(serde_json::Value::Null),
+                })
+            })
+            .collect::<Result<Vec<_>, sqlx::Error>>()?;
+
+        Ok(Some(Conversation {
+            info: conversation_info(&row)?,
+            messages: self.load_messages(conversation_id).await?,
+            charts,
+        }))
+    }
+
+    pub async fn rename(&self, conversation_id: &str, title: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("UPDATE conversations SET title = ? WHERE id = ?")
+            .bind(title.trim())
+            .bind(conversation_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn delete(&self, conversation_id: &str) -> Result<bool, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM chart_artifacts WHERE conversation_id = ?")
+            .bind(conversation_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM messages WHERE conversation_id = ?")
+            .bind(conversation_id)
+            .execute(&mut *tx)
+            .await?;
+        let result = sqlx::query("DELETE FROM conversations WHERE id = ?")
+            .bind(conversation_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn load_messages(&self, conversation_id: &str) -> Result<Vec<Message>, sqlx::Error> {
+        let rows = sqlx::query("SELECT role, content FROM messages WHERE conversation_id = ? ORDER BY seq ASC")
+            .bind(conversation_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut messages = Vec::with_capacity(rows.len());
+        for row in rows {
+            let content: String = row.try_get("content")?;
+            messages.push(Message {
+                role: row.try_get("role")?,
+                content: serde_json::from_str(&content).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            });
         }
+        Ok(messages)
+    }
+}
+
+fn conversation_info(row: &sqlx::sqlite::SqliteRow) -> Result<ConversationInfo, sqlx::Error> {
+    Ok(ConversationInfo {
+        id: row.try_get("id")?,
+        title: row.try_get("title")?,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+        message_count: row.try_get("message_count")?,
+    })
+}
+
+fn first_text(message: &Message) -> Option<&str> {
+    message.content.iter().find_map(|block| match block {
+        ContentBlock::Text { text } => Some(text.as_str()),
+        _ => None,
+    })
+}
+
+fn derive_title(text: &str) -> String {
+    let text = text.trim();
+    if text.chars().count() <= TITLE_MAX_CHARS {
+        return text.to_string();
     }
+    let cut: String = text.chars().take(TITLE_MAX_CHARS).collect();
+    format!("{}...", cut.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::{ChartData, Dataset};
 
-    pub async fn delete(&self, conversation_id: &str) {
-        let mut store = self.inner.write().await;
-        store.remove(conversation_id);
+    fn text(role: &str, text: &str) -> Message {
+        Message {
+            role: role.to_string(),
+            content: vec![ContentBlock::Text { text: text.to_string() }],
+        }
     }
 
-    fn evict_expired(&self, store: &mut HashMap<String, SessionEntry>) {
-        let now = SystemTime::now();
-        let ttl = Duration::from_secs(2 * 60 * 60);
+    #[tokio::test]
+    async fn test_history_survives_reopening_the_pool() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let db_path = dir.path().join("sessions.db");
+        crate::db::Database::open(&db_path)?;
+
+        let chart = ChartSpec {
+            chart_type: "pie".to_string(),
+            title: "Spending".to_string(),
+            data: ChartData {
+                labels: vec!["Dining".to_string()],
+                datasets: vec![Dataset { name: "CHF".to_string(), values: vec![42.0] }],
+            },
+            height: None,
+        };
+
+        let id = {
+            let store = SessionStore::new(crate::db::connect_pool(db_path.to_str().unwrap()).await?);
+            let (id, mut history) = store.get_or_create(None).await?;
+            assert!(history.is_empty());
+            history.push(text("user", "How much did I spend on dining?"));
+            history.push(text("assistant", "CHF 42."));
+            store.save_history(&id, &history, &[chart]).await?;
+
+            history.push(text("user", "And groceries?"));
+            store.save_history(&id, &history, &[]).await?;
+            id
+        };
+
+        // A fresh store over a new pool sees the same conversation
+        let store = SessionStore::new(crate::db::connect_pool(db_path.to_str().unwrap()).await?);
+        let (same_id, history) = store.get_or_create(Some(&id)).await?;
+        assert_eq!(same_id, id);
+        assert_eq!(history.len(), 3);
+
+        let listed = store.list().await?;
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].title, "How much did I spend on dining?");
+
+        assert!(store.rename(&id, "Dining").await?);
+        let conversation = store.get(&id).await?.expect("conversation exists");
+        assert_eq!(conversation.info.title, "Dining");
+        assert_eq!(conversation.charts.len(), 1);
+        assert_eq!(conversation.charts[0].message_seq, 0);
+        assert_eq!(conversation.charts[0].chart["type"], "pie");
 
-        store.retain(|_, entry| {
-            match now.duration_since(entry.last_accessed) {
-                Ok(elapsed) => elapsed < ttl,
-                Err(_) => true,
-            }
-        });
+        assert!(store.delete(&id).await?);
+        assert!(store.get(&id).await?.is_none());
+        assert!(store.list().await?.is_empty());
+        Ok(())
     }
 }
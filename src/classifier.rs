@@ -1,8 +1,14 @@
-use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use futures_util::StreamExt;
+use serde::Deserialize;
+
+use crate::ai::llm::{build_provider, CompletionRequest, ContentBlock, LlmProvider, Message};
+use crate::config::ProviderConfig;
 use crate::db::{FewShotExample, CategoryInfo};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ClassificationResult {
     pub merchant: String,
     pub category: String,
@@ -10,104 +16,198 @@ pub struct ClassificationResult {
     pub source: String,
 }
 
+/// Classifies transactions with whichever LLM backend it was built with. The
+/// import pipeline is synchronous, so the classifier owns a small runtime to
+/// drive the async provider.
 pub struct Classifier {
-    client: reqwest::blocking::Client,
-    base_url: String,
-    model: String,
-}
-
-#[derive(Serialize)]
-struct ChatRequest {
-    model: String,
-    messages: Vec<Message>,
-    format: &'static str,
-    stream: bool,
-    think: bool,
+    provider: Arc<dyn LlmProvider>,
+    runtime: tokio::runtime::Runtime,
+    max_retries: u32,
+    /// Most requests `classify_many` keeps in flight.
+    concurrency: usize,
+    /// Transactions `classify_many` puts in one prompt.
+    batch_size: usize,
 }
 
-#[derive(Serialize)]
-struct Message {
-    role: &'static str,
-    content: String,
-}
-
-#[derive(Deserialize)]
-struct ChatResponse {
-    message: ResponseMessage,
+/// One transaction for `classify_many`.
+#[derive(Debug, Clone, Copy)]
+pub struct ClassifyRequest<'a> {
+    pub description: &'a str,
+    pub amount: Option<f64>,
+    pub details: &'a str,
 }
 
 #[derive(Deserialize)]
-struct ResponseMessage {
-    content: String,
+struct LlmOutput {
+    merchant: Option<String>,
+    category: Option<String>,
+    confidence: Option<f64>,
 }
 
 #[derive(Deserialize)]
-struct LlmOutput {
+struct BatchItem {
+    /// 1-based position in the prompt.
+    index: Option<usize>,
     merchant: Option<String>,
     category: Option<String>,
     confidence: Option<f64>,
 }
 
+/// Appended to the system prompt when several transactions share a request.
+const BATCH_INSTRUCTIONS: &str = r#"
+
+The message holds several transactions, each headed by its number in square brackets. Instead of a single object, respond with a JSON object with one field, "transactions": an array with one object per transaction, in the same order. Each object has the fields above plus "index", the transaction's number."#;
+
 impl Classifier {
-    pub fn new(base_url: &str, model: &str) -> Self {
-        let client = reqwest::blocking::Client::builder()
-            .timeout(std::time::Duration::from_secs(300))
+    pub fn new(provider: Arc<dyn LlmProvider>) -> Self {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
             .build()
-            .expect("failed to build reqwest client");
-        Self {
-            client,
-            base_url: base_url.trim_end_matches('/').to_string(),
-            model: model.to_string(),
-        }
+            .expect("failed to build classifier runtime");
+        Self { provider, runtime, max_retries: 3, concurrency: 1, batch_size: 1 }
+    }
+
+    pub fn from_config(config: &ProviderConfig) -> Result<Self, String> {
+        let mut classifier = Self::new(build_provider(config)?);
+        classifier.max_retries = config.max_retries;
+        classifier.concurrency = config.concurrency;
+        classifier.batch_size = config.batch_size;
+        Ok(classifier)
+    }
+
+    /// Keep up to `concurrency` requests in flight in `classify_many`.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Put up to `batch_size` transactions in each prompt in `classify_many`.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
     }
 
     pub fn classify(&self, description: &str, amount: Option<f64>, details: &str, examples: &[FewShotExample], categories: &[CategoryInfo]) -> ClassificationResult {
         let system_prompt = Self::build_system_prompt(examples, categories);
-        let user_prompt = Self::build_user_prompt(description, amount, details);
-
-        let request = ChatRequest {
-            model: self.model.clone(),
-            messages: vec![
-                Message { role: "system", content: system_prompt },
-                Message { role: "user", content: user_prompt },
-            ],
-            format: "json",
-            stream: false,
-            think: false,
+        self.runtime.block_on(self.request(&system_prompt, ClassifyRequest { description, amount, details }))
+    }
+
+    /// Classify several transactions concurrently, `batch_size` to a prompt.
+    /// Results come back in the order of `requests`, each with its share of
+    /// the time its request took.
+    pub fn classify_many(&self, requests: &[ClassifyRequest<'_>], examples: &[FewShotExample], categories: &[CategoryInfo]) -> Vec<(ClassificationResult, Duration)> {
+        let system_prompt = Self::build_system_prompt(examples, categories);
+        let batch_prompt = format!("{}{}", system_prompt, BATCH_INSTRUCTIONS);
+        let (system_prompt, batch_prompt) = (system_prompt.as_str(), batch_prompt.as_str());
+        let batches: Vec<Vec<(ClassificationResult, Duration)>> = self.runtime.block_on(
+            futures_util::stream::iter(requests.chunks(self.batch_size.max(1)))
+                .map(|batch| self.request_batch(system_prompt, batch_prompt, batch, categories))
+                .buffered(self.concurrency.max(1))
+                .collect(),
+        );
+        batches.into_iter().flatten().collect()
+    }
+
+    /// Classify `batch` in one request. Items missing from the answer, or with
+    /// a category not in `categories`, are asked about again one at a time.
+    async fn request_batch(
+        &self,
+        system_prompt: &str,
+        batch_prompt: &str,
+        batch: &[ClassifyRequest<'_>],
+        categories: &[CategoryInfo],
+    ) -> Vec<(ClassificationResult, Duration)> {
+        let start = Instant::now();
+        if let [transaction] = batch {
+            let result = self.request(system_prompt, *transaction).await;
+            return vec![(result, start.elapsed())];
+        }
+
+        let user_prompt = batch
+            .iter()
+            .enumerate()
+            .map(|(i, t)| format!("[{}]\n{}", i + 1, Self::build_user_prompt(t.description, t.amount, t.details)))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let answers = match self.complete(batch_prompt, user_prompt).await {
+            Some(content) => Self::parse_batch_output(&content, batch, categories),
+            None => {
+                // The backend is failing; asking item by item would fail the same way
+                let share = start.elapsed() / batch.len() as u32;
+                return batch.iter().map(|t| (Self::fallback(t.description), share)).collect();
+            }
         };
 
-        let url = format!("{}/api/chat", self.base_url);
+        let share = start.elapsed() / batch.len() as u32;
+        let mut results = Vec::with_capacity(batch.len());
+        for (transaction, answer) in batch.iter().zip(answers) {
+            match answer {
+                Some(result) => results.push((result, share)),
+                None => {
+                    let retry = Instant::now();
+                    let result = self.request(system_prompt, *transaction).await;
+                    results.push((result, share + retry.elapsed()));
+                }
+            }
+        }
+        results
+    }
+
+    async fn request(&self, system_prompt: &str, transaction: ClassifyRequest<'_>) -> ClassificationResult {
+        let description = transaction.description;
+        let user_prompt = Self::build_user_prompt(description, transaction.amount, transaction.details);
+        match self.complete(system_prompt, user_prompt).await {
+            Some(content) => Self::parse_llm_output(&content, description),
+            None => Self::fallback(description),
+        }
+    }
+
+    /// The text of the reply, or `None` once the request has failed for good.
+    async fn complete(&self, system_prompt: &str, user_prompt: String) -> Option<String> {
+        let messages = vec![Message {
+            role: "user".to_string(),
+            content: vec![ContentBlock::Text { text: user_prompt }],
+        }];
+        let request = CompletionRequest {
+            system: system_prompt,
+            messages: &messages,
+            tools: &[],
+            json_output: true,
+        };
 
         // Retry logic with exponential backoff
-        const MAX_RETRIES: u32 = 3;
         let mut retry_count = 0;
 
         loop {
-            let response = match self.client.post(&url).json(&request).send() {
-                Ok(resp) => resp,
-                Err(e) => {
+            let completion = match self.provider.complete(request).await {
+                Ok(completion) => completion,
+                Err(e) if e.is_transient() => {
                     retry_count += 1;
-                    if retry_count > MAX_RETRIES {
-                        eprintln!("Ollama request failed after {} retries: {:?}", MAX_RETRIES, e);
-                        return Self::fallback(description);
+                    if retry_count > self.max_retries {
+                        eprintln!("LLM request failed after {} retries: {}", self.max_retries, e);
+                        return None;
                     }
                     let backoff_ms = 500 * 2u64.pow(retry_count - 1); // 500ms, 1s, 2s
-                    eprintln!("Ollama request failed (attempt {}/{}): {:?}. Retrying in {}ms...",
-                             retry_count, MAX_RETRIES, e, backoff_ms);
-                    std::thread::sleep(std::time::Duration::from_millis(backoff_ms));
+                    eprintln!("LLM request failed (attempt {}/{}): {}. Retrying in {}ms...",
+                             retry_count, self.max_retries, e, backoff_ms);
+                    tokio::time::sleep(Duration::from_millis(backoff_ms)).await;
                     continue;
                 }
-            };
-
-            let chat_resp: ChatResponse = match response.json() {
-                Ok(r) => r,
                 Err(e) => {
-                    eprintln!("Failed to parse Ollama response: {}", e);
-                    return Self::fallback(description);
+                    eprintln!("LLM request failed: {}", e);
+                    return None;
                 }
             };
 
-            return Self::parse_llm_output(&chat_resp.message.content, description);
+            let content: String = completion
+                .content
+                .iter()
+                .filter_map(|block| match block {
+                    ContentBlock::Text { text } => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect();
+            return Some(content);
         }
     }
 
@@ -164,7 +264,12 @@ Examples of UBS merchant strings and their classifications:
     }
 
     fn parse_llm_output(content: &str, description: &str) -> ClassificationResult {
-        let parsed: LlmOutput = match serde_json::from_str(content) {
+        // Backends without a JSON mode may wrap the object in prose or a code fence
+        let json = match (content.find('{'), content.rfind('}')) {
+            (Some(start), Some(end)) if start < end => &content[start..=end],
+            _ => content,
+        };
+        let parsed: LlmOutput = match serde_json::from_str(json) {
             Ok(v) => v,
             Err(e) => {
                 eprintln!("Failed to parse LLM JSON output: {} — raw: {}", e, content);
@@ -180,6 +285,61 @@ Examples of UBS merchant strings and their classifications:
         }
     }
 
+    /// One answer per item of `batch`, `None` where the reply has no usable
+    /// classification for it.
+    fn parse_batch_output(content: &str, batch: &[ClassifyRequest<'_>], categories: &[CategoryInfo]) -> Vec<Option<ClassificationResult>> {
+        let mut answers = vec![None; batch.len()];
+        let json = match (content.find(['{', '[']), content.rfind(['}', ']'])) {
+            (Some(start), Some(end)) if start < end => &content[start..=end],
+            _ => content,
+        };
+        let items = match serde_json::from_str::<serde_json::Value>(json) {
+            Ok(serde_json::Value::Array(items)) => items,
+            Ok(serde_json::Value::Object(mut object)) => match object.remove("transactions") {
+                Some(serde_json::Value::Array(items)) => items,
+                _ => Vec::new(),
+            },
+            Ok(_) => Vec::new(),
+            Err(e) => {
+                eprintln!("Failed to parse LLM JSON output: {} — raw: {}", e, content);
+                Vec::new()
+            }
+        };
+
+        // Positions only say which transaction an item answers when every
+        // transaction has exactly one item and none of them is numbered
+        let items: Vec<Option<BatchItem>> = items.into_iter().map(|item| serde_json::from_value(item).ok()).collect();
+        let by_position = items.len() == batch.len() && items.iter().flatten().all(|item| item.index.is_none());
+        for (position, item) in items.into_iter().enumerate() {
+            let Some(item) = item else { continue };
+            let slot = match item.index {
+                Some(index) => index.wrapping_sub(1),
+                None if by_position => position,
+                None => continue,
+            };
+            let (Some(merchant), Some(category), Some(confidence)) = (item.merchant, item.category, item.confidence) else {
+                continue;
+            };
+            if slot >= batch.len() || answers[slot].is_some() || !categories.iter().any(|c| c.name == category) {
+                continue;
+            }
+            answers[slot] = Some(ClassificationResult {
+                merchant,
+                category,
+                confidence: confidence.clamp(0.0, 1.0),
+                source: "llm".to_string(),
+            });
+        }
+        answers
+    }
+
+    /// Whether `result` carries no answer: the fallback after the LLM failed,
+    /// or a reply without a category. These are not cached, so the merchant is
+    /// asked about again next time.
+    pub fn is_fallback(result: &ClassificationResult) -> bool {
+        result.source == "llm" && result.category == "Uncategorised" && result.confidence == 0.0
+    }
+
     fn fallback(description: &str) -> ClassificationResult {
         ClassificationResult {
             merchant: description.to_string(),
@@ -189,3 +349,184 @@ Examples of UBS merchant strings and their classifications:
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use futures_util::future::BoxFuture;
+
+    use crate::ai::llm::{CompletionRequest, LlmCompletion, LlmError, StreamChunk};
+
+    /// Answers with the transaction line as merchant, taking longer for earlier
+    /// requests, and records how many requests were in flight at once.
+    #[derive(Default)]
+    struct SlowEcho {
+        in_flight: AtomicUsize,
+        peak: AtomicUsize,
+        started: AtomicUsize,
+    }
+
+    impl LlmProvider for SlowEcho {
+        fn complete_stream<'a>(
+            &'a self,
+            request: CompletionRequest<'a>,
+            _on_chunk: &'a mut (dyn FnMut(StreamChunk) + Send),
+        ) -> BoxFuture<'a, Result<LlmCompletion, LlmError>> {
+            Box::pin(async move {
+                let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                self.peak.fetch_max(now, Ordering::SeqCst);
+                let order = self.started.fetch_add(1, Ordering::SeqCst) as u64;
+                tokio::time::sleep(Duration::from_millis(40u64.saturating_sub(order * 10))).await;
+                self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+                let ContentBlock::Text { text } = &request.messages[0].content[0] else { unreachable!() };
+                let line = text.lines().next().unwrap_or_default().trim_start_matches("Transaction: ");
+                Ok(LlmCompletion {
+                    content: vec![ContentBlock::Text {
+                        text: format!(r#"{{"merchant": "{}", "category": "Shopping", "confidence": 0.9}}"#, line),
+                    }],
+                    stop_reason: None,
+                })
+            })
+        }
+    }
+
+    #[test]
+    fn test_classify_many_keeps_order_and_limit() {
+        let provider = Arc::new(SlowEcho::default());
+        let classifier = Classifier::new(provider.clone()).with_concurrency(3);
+        let descriptions = ["A", "B", "C", "D", "E", "F", "G"];
+        let requests: Vec<ClassifyRequest> = descriptions
+            .iter()
+            .map(|d| ClassifyRequest { description: d, amount: None, details: "" })
+            .collect();
+
+        let results = classifier.classify_many(&requests, &[], &[]);
+        let merchants: Vec<&str> = results.iter().map(|(r, _)| r.merchant.as_str()).collect();
+        assert_eq!(merchants, descriptions);
+        assert_eq!(provider.peak.load(Ordering::SeqCst), 3);
+    }
+
+    /// Answers batches in reverse order with a made-up category for "BAD" and
+    /// nothing for "SKIP"; answers single requests with Other.
+    #[derive(Default)]
+    struct Batcher {
+        batches: AtomicUsize,
+        singles: AtomicUsize,
+    }
+
+    impl LlmProvider for Batcher {
+        fn complete_stream<'a>(
+            &'a self,
+            request: CompletionRequest<'a>,
+            _on_chunk: &'a mut (dyn FnMut(StreamChunk) + Send),
+        ) -> BoxFuture<'a, Result<LlmCompletion, LlmError>> {
+            Box::pin(async move {
+                let ContentBlock::Text { text } = &request.messages[0].content[0] else { unreachable!() };
+                let descriptions: Vec<&str> = text
+                    .lines()
+                    .filter_map(|line| line.strip_prefix("Transaction: "))
+                    .collect();
+                let text = if request.system.ends_with(BATCH_INSTRUCTIONS) {
+                    self.batches.fetch_add(1, Ordering::SeqCst);
+                    let items: Vec<String> = descriptions
+                        .iter()
+                        .enumerate()
+                        .rev()
+                        .filter(|(_, d)| **d != "SKIP")
+                        .map(|(i, d)| {
+                            let category = if *d == "BAD" { "Snacks" } else { "Shopping" };
+                            format!(r#"{{"index": {}, "merchant": "{}", "category": "{}", "confidence": 0.9}}"#, i + 1, d, category)
+                        })
+                        .collect();
+                    format!("```json\n{{\"transactions\": [{}]}}\n```", items.join(", "))
+                } else {
+                    self.singles.fetch_add(1, Ordering::SeqCst);
+                    format!(r#"{{"merchant": "{}", "category": "Other", "confidence": 0.5}}"#, descriptions[0])
+                };
+                Ok(LlmCompletion { content: vec![ContentBlock::Text { text }], stop_reason: None })
+            })
+        }
+    }
+
+    #[test]
+    fn test_batches_fall_back_to_single_requests() {
+        let provider = Arc::new(Batcher::default());
+        let classifier = Classifier::new(provider.clone()).with_batch_size(4);
+        let categories: Vec<CategoryInfo> = ["Shopping", "Other"]
+            .iter()
+            .map(|name| CategoryInfo { name: name.to_string(), description: String::new() })
+            .collect();
+        let requests: Vec<ClassifyRequest> = ["A", "BAD", "C", "SKIP", "E"]
+            .iter()
+            .map(|d| ClassifyRequest { description: d, amount: Some(-12.5), details: "" })
+            .collect();
+
+        let results = classifier.classify_many(&requests, &[], &categories);
+        let answers: Vec<(&str, &str)> = results.iter().map(|(r, _)| (r.merchant.as_str(), r.category.as_str())).collect();
+        assert_eq!(answers, vec![("A", "Shopping"), ("BAD", "Other"), ("C", "Shopping"), ("SKIP", "Other"), ("E", "Other")]);
+        // One batch of four, then BAD and SKIP on their own; E is a batch of one
+        assert_eq!(provider.batches.load(Ordering::SeqCst), 1);
+        assert_eq!(provider.singles.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_unnumbered_batch_items_need_one_per_transaction() {
+        let categories = vec![CategoryInfo { name: "Shopping".to_string(), description: String::new() }];
+        let batch: Vec<ClassifyRequest> = ["A", "B", "C"]
+            .iter()
+            .map(|d| ClassifyRequest { description: d, amount: None, details: "" })
+            .collect();
+        let item = |merchant: &str| format!(r#"{{"merchant": "{}", "category": "Shopping", "confidence": 0.9}}"#, merchant);
+
+        let complete = format!("[{}, {}, {}]", item("A"), item("B"), item("C"));
+        let answers = Classifier::parse_batch_output(&complete, &batch, &categories);
+        let merchants: Vec<Option<&str>> = answers.iter().map(|a| a.as_ref().map(|r| r.merchant.as_str())).collect();
+        assert_eq!(merchants, vec![Some("A"), Some("B"), Some("C")]);
+
+        // B is missing, so C's answer can't be told apart from B's by position
+        let missing = format!("[{}, {}]", item("A"), item("C"));
+        let answers = Classifier::parse_batch_output(&missing, &batch, &categories);
+        assert!(answers.iter().all(Option::is_none));
+    }
+
+    /// Fails each request with the next status in `errors`, then answers.
+    struct Flaky {
+        errors: std::sync::Mutex<Vec<u16>>,
+        calls: AtomicUsize,
+    }
+
+    impl LlmProvider for Flaky {
+        fn complete_stream<'a>(
+            &'a self,
+            _request: CompletionRequest<'a>,
+            _on_chunk: &'a mut (dyn FnMut(StreamChunk) + Send),
+        ) -> BoxFuture<'a, Result<LlmCompletion, LlmError>> {
+            Box::pin(async move {
+                self.calls.fetch_add(1, Ordering::SeqCst);
+                if let Some(status) = self.errors.lock().unwrap().pop() {
+                    let status = reqwest::StatusCode::from_u16(status).unwrap();
+                    return Err(LlmError::Api { status, body: String::new() });
+                }
+                let text = r#"{"merchant": "Coop", "category": "Groceries", "confidence": 0.9}"#.to_string();
+                Ok(LlmCompletion { content: vec![ContentBlock::Text { text }], stop_reason: None })
+            })
+        }
+    }
+
+    #[test]
+    fn test_retries_rate_limits_and_server_errors_only() {
+        let classify = |errors: Vec<u16>| {
+            let provider = Arc::new(Flaky { errors: std::sync::Mutex::new(errors), calls: AtomicUsize::new(0) });
+            let result = Classifier::new(provider.clone()).classify("COOP", None, "", &[], &[]);
+            (result.category, provider.calls.load(Ordering::SeqCst))
+        };
+        assert_eq!(classify(vec![503, 429]), ("Groceries".to_string(), 3));
+
+        let (category, calls) = classify(vec![400]);
+        assert_eq!((category.as_str(), calls), ("Uncategorised", 1), "a bad request is not retried");
+        assert!(Classifier::is_fallback(&Classifier::fallback("COOP")));
+    }
+}
@@ -0,0 +1,404 @@
+use std::path::PathBuf;
+
+use chrono::NaiveDate;
+use clap::{Args, Parser, Subcommand};
+use clap_complete::Shell;
+
+use crate::config::Overrides;
+use crate::csv_parser::CsvFormat;
+use crate::review::ReviewFilters;
+use crate::splits::SplitRequest;
+
+#[derive(Debug, Parser)]
+#[command(name = "budget-analyser", version, about = "Import, classify and analyse UBS bank transactions")]
+pub struct Cli {
+    /// Config file (default: budget-analyser.toml in the working directory)
+    #[arg(long, global = true, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// SQLite database path
+    #[arg(long, global = true, value_name = "PATH")]
+    pub db: Option<String>,
+
+    /// Classifier model
+    #[arg(long, global = true, value_name = "NAME")]
+    pub model: Option<String>,
+
+    /// Classifier endpoint URL
+    #[arg(long, global = true, value_name = "URL")]
+    pub endpoint: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Import a CSV file, or every CSV file in a directory
+    Import {
+        path: PathBuf,
+        /// Continue interrupted imports of these files from their last checkpoint
+        #[arg(long)]
+        resume: bool,
+    },
+    /// Start the chat server
+    #[command(alias = "chat")]
+    Serve,
+    /// Interactively review low-confidence classifications
+    Review(FilterArgs),
+    /// Re-run the classifier over low-confidence transactions
+    Reclassify(FilterArgs),
+    /// Review every transaction in one category
+    Recategorise {
+        #[arg(long)]
+        category: String,
+    },
+    /// Score the classifier against ground-truth labels without changing the database
+    Evaluate {
+        /// Statement whose transactions are labelled
+        csv: PathBuf,
+        /// Labels keyed by transaction id
+        #[arg(long, default_value = "data/ground-truth.toml")]
+        labels: PathBuf,
+        /// Results below this confidence count as flagged for review
+        #[arg(long, default_value_t = crate::calibration::REVIEW_THRESHOLD, value_parser = parse_threshold)]
+        threshold: f64,
+        /// Ask the classifier for every merchant instead of using the merchant cache
+        #[arg(long)]
+        no_cache: bool,
+    },
+    /// Manage spending categories
+    #[command(subcommand)]
+    Categories(CategoriesCommand),
+    /// Manage monthly and annual budgets
+    #[command(subcommand)]
+    Budgets(BudgetsCommand),
+    /// Detect recurring payments
+    Recurring,
+    /// Split transactions across several categories
+    #[command(subcommand)]
+    Splits(SplitsCommand),
+    /// Show who or what changed a transaction's merchant and category, and when
+    History {
+        /// Transaction id, as shown by `splits find`
+        id: i64,
+    },
+    /// Check which periods have been imported for each account
+    #[command(subcommand)]
+    Imports(ImportsCommand),
+    /// List bank accounts and cards seen in imports
+    #[command(subcommand)]
+    Accounts(AccountsCommand),
+    /// Show account balances and check them against statements
+    #[command(subcommand)]
+    Balances(BalancesCommand),
+    /// Link transfers between your own accounts
+    #[command(subcommand)]
+    Transfers(TransfersCommand),
+    /// Show or refit how far classification confidence is trusted
+    #[command(subcommand)]
+    Calibration(CalibrationCommand),
+    /// Import exchange rates and check currency conversion
+    #[command(subcommand)]
+    Fx(FxCommand),
+    /// Check and try out the classification rules
+    #[command(subcommand)]
+    Rules(RulesCommand),
+    /// Inspect the effective configuration
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Print a shell completion script
+    Completions {
+        shell: Shell,
+    },
+}
+
+/// Filters shared by `review` and `reclassify`.
+#[derive(Debug, Args)]
+pub struct FilterArgs {
+    #[arg(long)]
+    pub category: Option<String>,
+    /// Earliest booking date (YYYY-MM-DD)
+    #[arg(long, value_parser = parse_date)]
+    pub since: Option<String>,
+    /// Latest booking date (YYYY-MM-DD)
+    #[arg(long, value_parser = parse_date)]
+    pub until: Option<String>,
+    /// Substring of the merchant name
+    #[arg(long)]
+    pub merchant: Option<String>,
+    /// Account or card number, IBAN, card holder or account name
+    #[arg(long)]
+    pub account: Option<String>,
+    /// Only include transactions below this calibrated confidence (0.0 to 1.0)
+    #[arg(long, default_value_t = crate::calibration::REVIEW_THRESHOLD, value_parser = parse_threshold)]
+    pub threshold: f64,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CategoriesCommand {
+    /// List all categories
+    List,
+    /// Add a new category
+    Add {
+        name: String,
+        description: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum BudgetsCommand {
+    /// List all budgets
+    List,
+    /// Set a monthly (or, with --annual, annual) limit for a category
+    Set {
+        category: String,
+        #[arg(value_parser = parse_budget_amount)]
+        amount: f64,
+        #[arg(long)]
+        annual: bool,
+    },
+    /// Remove the budget for a category
+    Remove {
+        category: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SplitsCommand {
+    /// Find transactions by merchant or description, with their ids
+    Find {
+        search: String,
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Show a transaction and its split
+    Show {
+        id: i64,
+    },
+    /// Split a transaction, replacing any existing split
+    Set {
+        id: i64,
+        /// Parts as CATEGORY=AMOUNT (positive); one bare CATEGORY takes the remainder
+        #[arg(required = true, num_args = 2.., value_name = "CATEGORY=AMOUNT")]
+        parts: Vec<SplitRequest>,
+    },
+    /// Remove the split so the transaction counts under its own category again
+    Clear {
+        id: i64,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ImportsCommand {
+    /// Coverage per account, with gaps, overlaps and files imported twice under different names
+    Status {
+        /// Account or card number, IBAN, card holder or account name
+        #[arg(long)]
+        account: Option<String>,
+    },
+    /// Remove every transaction imported from a file, e.g. after importing the wrong one
+    Rollback {
+        /// File name the transactions were imported from, as shown by `imports status`
+        batch: String,
+        /// Also forget the LLM classifications this import added to the merchant cache
+        #[arg(long)]
+        revert_cache: bool,
+        /// Show what would be removed without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AccountsCommand {
+    /// List accounts and cards with their transaction counts
+    List,
+    /// Give an account a friendly name, e.g. "Joint card"
+    Rename {
+        id: i64,
+        name: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum BalancesCommand {
+    /// Latest balance of every account and the total net worth
+    List,
+    /// Find periods where reported balances and imported transactions disagree
+    Check,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TransfersCommand {
+    /// List linked transfer pairs
+    List,
+    /// Pair up unlinked transfers now (imports do this automatically)
+    Match {
+        /// Most days between the two bookings
+        #[arg(long, default_value_t = crate::transfers::TRANSFER_WINDOW_DAYS)]
+        window: i64,
+    },
+    /// Undo a wrong pairing; both sides get their categories back and are not paired again
+    Unlink {
+        /// Id of either side, as shown by `transfers list`
+        id: i64,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CalibrationCommand {
+    /// Show the calibration fitted from past reviews
+    Show,
+    /// Refit it from all reviews so far (review does this when it finishes)
+    Fit,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum FxCommand {
+    /// Import rates from an ECB reference rates CSV or a date,base,quote,rate CSV
+    Import {
+        path: PathBuf,
+    },
+    /// List the stored rates and any transactions that cannot be converted
+    List,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum RulesCommand {
+    /// Validate the configured rules files
+    Check,
+    /// Show which rule, if any, classifies a transaction
+    Test {
+        /// Booking text (credit cards) or counterparty (account statements)
+        description: String,
+        /// Remaining description columns, e.g. payment reason and IBAN
+        #[arg(long, default_value = "")]
+        details: String,
+        /// Negative for debits, positive for credits
+        #[arg(long, allow_hyphen_values = true)]
+        amount: Option<f64>,
+        /// UBS card sector
+        #[arg(long)]
+        sector: Option<String>,
+        /// account-statement, credit-card or synthetic (default: credit-card with --sector, otherwise account-statement)
+        #[arg(long)]
+        format: Option<CsvFormat>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// Print the effective settings as TOML
+    Show,
+}
+
+impl Cli {
+    pub fn overrides(&self) -> Overrides {
+        Overrides {
+            config: self.config.clone(),
+            database: self.db.clone(),
+            model: self.model.clone(),
+            endpoint: self.endpoint.clone(),
+        }
+    }
+}
+
+impl FilterArgs {
+    pub fn filters(&self) -> ReviewFilters<'_> {
+        ReviewFilters {
+            category: self.category.as_deref(),
+            since: self.since.as_deref(),
+            until: self.until.as_deref(),
+            merchant: self.merchant.as_deref(),
+            account: self.account.as_deref(),
+            threshold: self.threshold,
+        }
+    }
+}
+
+fn parse_date(value: &str) -> Result<String, String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .map_err(|_| format!("'{}' is not a date in YYYY-MM-DD format", value))
+}
+
+fn parse_threshold(value: &str) -> Result<f64, String> {
+    let threshold: f64 = value.parse().map_err(|_| format!("'{}' is not a number", value))?;
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(format!("{} is outside 0.0 to 1.0", threshold));
+    }
+    Ok(threshold)
+}
+
+fn parse_budget_amount(value: &str) -> Result<f64, String> {
+    let amount: f64 = value.parse().map_err(|_| format!("'{}' is not a number", value))?;
+    crate::db::validate_budget_amount(amount)?;
+    Ok(amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_command_definition_is_valid() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_global_flags_and_validation() {
+        let cli = Cli::try_parse_from(["budget-analyser", "reclassify", "--since", "2026-01-01", "--db", "x.db", "--model", "qwen3:14b"])
+            .unwrap();
+        assert_eq!(cli.db.as_deref(), Some("x.db"));
+        assert_eq!(cli.model.as_deref(), Some("qwen3:14b"));
+        match cli.command {
+            Command::Reclassify(args) => {
+                assert_eq!(args.since.as_deref(), Some("2026-01-01"));
+                assert_eq!(args.threshold, 0.80);
+            }
+            other => panic!("expected reclassify, got {:?}", other),
+        }
+
+        let cli = Cli::try_parse_from(["budget-analyser", "evaluate", "feb.csv", "--no-cache"]).unwrap();
+        match cli.command {
+            Command::Evaluate { labels, threshold, no_cache, .. } => {
+                assert_eq!(labels, PathBuf::from("data/ground-truth.toml"));
+                assert_eq!(threshold, 0.80);
+                assert!(no_cache);
+            }
+            other => panic!("expected evaluate, got {:?}", other),
+        }
+
+        assert!(Cli::try_parse_from(["budget-analyser", "review", "--since", "January"]).is_err());
+        assert!(Cli::try_parse_from(["budget-analyser", "review", "--threshold", "1.5"]).is_err());
+        assert!(Cli::try_parse_from(["budget-analyser", "review", "--bogus"]).is_err());
+        assert!(Cli::try_parse_from(["budget-analyser", "data/statement.csv"]).is_err());
+
+        let cli = Cli::try_parse_from(["budget-analyser", "splits", "set", "42", "Groceries=80.50", "Household"]).unwrap();
+        match cli.command {
+            Command::Splits(SplitsCommand::Set { id, parts }) => {
+                assert_eq!(id, 42);
+                assert_eq!(parts[0], SplitRequest { category: "Groceries".to_string(), amount: Some(80.5) });
+                assert_eq!(parts[1].amount, None);
+            }
+            other => panic!("expected splits set, got {:?}", other),
+        }
+        assert!(Cli::try_parse_from(["budget-analyser", "splits", "set", "42", "Groceries=80.50"]).is_err());
+
+        assert!(Cli::try_parse_from(["budget-analyser", "budgets", "set", "Dining", "400"]).is_ok());
+        for amount in ["0", "-5", "NaN", "inf"] {
+            assert!(Cli::try_parse_from(["budget-analyser", "budgets", "set", "Dining", amount]).is_err(), "{} accepted", amount);
+        }
+
+        let cli = Cli::try_parse_from(["budget-analyser", "imports", "rollback", "statement.csv", "--dry-run"]).unwrap();
+        match cli.command {
+            Command::Imports(ImportsCommand::Rollback { batch, revert_cache, dry_run }) => {
+                assert_eq!(batch, "statement.csv");
+                assert!(dry_run && !revert_cache);
+            }
+            other => panic!("expected imports rollback, got {:?}", other),
+        }
+    }
+}
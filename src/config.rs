@@ -1,26 +1,404 @@
 use std::env;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
+use serde::{Deserialize, Serialize};
+
+/// Config file read from the working directory when `--config` is not given.
+pub const DEFAULT_CONFIG_FILE: &str = "budget-analyser.toml";
+
+/// Classifier requests in flight at once. Ollama serves up to four requests
+/// per model in parallel when memory allows (`OLLAMA_NUM_PARALLEL`).
+const DEFAULT_CONCURRENCY: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProviderKind {
+    Anthropic,
+    Ollama,
+    /// Any server speaking the OpenAI chat completions API (llama.cpp server, LM Studio, vLLM).
+    OpenAi,
+}
+
+impl FromStr for ProviderKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "anthropic" | "claude" => Ok(ProviderKind::Anthropic),
+            "ollama" => Ok(ProviderKind::Ollama),
+            "openai" | "openai-compatible" => Ok(ProviderKind::OpenAi),
+            other => Err(format!("unknown LLM provider '{}' (expected anthropic, ollama or openai)", other)),
+        }
+    }
+}
+
+impl fmt::Display for ProviderKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProviderKind::Anthropic => write!(f, "anthropic"),
+            ProviderKind::Ollama => write!(f, "ollama"),
+            ProviderKind::OpenAi => write!(f, "openai"),
+        }
+    }
+}
+
+impl ProviderKind {
+    pub fn default_model(&self) -> &'static str {
+        match self {
+            ProviderKind::Anthropic => "claude-sonnet-4-5-20250929",
+            ProviderKind::Ollama => "qwen3:8b",
+            ProviderKind::OpenAi => "local-model",
+        }
+    }
+
+    /// Environment variable holding the API key, for providers that need one.
+    pub fn api_key_var(&self) -> Option<&'static str> {
+        match self {
+            ProviderKind::Anthropic => Some("ANTHROPIC_API_KEY"),
+            ProviderKind::OpenAi => Some("OPENAI_API_KEY"),
+            ProviderKind::Ollama => None,
+        }
+    }
+}
+
+/// Which LLM backend to use for one role (chat agent or classifier).
+#[derive(Debug, Clone)]
+pub struct ProviderConfig {
+    pub kind: ProviderKind,
+    pub model: String,
+    /// Base URL; each provider has its own default where one makes sense.
+    pub endpoint: Option<String>,
+    /// Read from the environment only, so secrets stay out of the config file.
+    pub api_key: Option<String>,
+    pub timeout_secs: u64,
+    pub max_retries: u32,
+    /// Requests the classifier sends at once during an import.
+    pub concurrency: usize,
+    /// Transactions the classifier puts in one prompt; 1 sends each on its own.
+    pub batch_size: usize,
+}
+
+impl ProviderConfig {
+    fn new(kind: ProviderKind) -> Self {
+        Self {
+            kind,
+            model: kind.default_model().to_string(),
+            endpoint: None,
+            api_key: None,
+            timeout_secs: crate::ai::llm::DEFAULT_TIMEOUT.as_secs(),
+            max_retries: 3,
+            concurrency: DEFAULT_CONCURRENCY,
+            batch_size: 1,
+        }
+    }
+
+    fn apply_file(&mut self, section: &ProviderSection) -> Result<(), String> {
+        if let Some(provider) = &section.provider {
+            self.switch_kind(provider.parse()?);
+        }
+        if let Some(model) = &section.model {
+            self.model = model.clone();
+        }
+        if let Some(endpoint) = &section.endpoint {
+            self.endpoint = Some(endpoint.clone());
+        }
+        if let Some(timeout) = section.timeout_secs {
+            self.timeout_secs = timeout;
+        }
+        if let Some(retries) = section.max_retries {
+            self.max_retries = retries;
+        }
+        if let Some(concurrency) = section.concurrency {
+            if concurrency == 0 {
+                return Err("concurrency must be at least 1".to_string());
+            }
+            self.concurrency = concurrency;
+        }
+        if let Some(batch_size) = section.batch_size {
+            if batch_size == 0 {
+                return Err("batch_size must be at least 1".to_string());
+            }
+            self.batch_size = batch_size;
+        }
+        Ok(())
+    }
+
+    /// Apply `<PREFIX>_PROVIDER`, `<PREFIX>_MODEL` and `<PREFIX>_ENDPOINT`, then
+    /// pick up the API key for the final provider.
+    fn apply_env(&mut self, prefix: &str) -> Result<(), String> {
+        if let Ok(provider) = env::var(format!("{}_PROVIDER", prefix)) {
+            self.switch_kind(provider.parse()?);
+        }
+        if let Ok(model) = env::var(format!("{}_MODEL", prefix)) {
+            self.model = model;
+        }
+        if let Ok(endpoint) = env::var(format!("{}_ENDPOINT", prefix)) {
+            self.endpoint = Some(endpoint);
+        }
+        self.api_key = self.kind.api_key_var().and_then(|var| env::var(var).ok());
+        Ok(())
+    }
+
+    /// Change provider, resetting the model if it was still the old provider's default.
+    fn switch_kind(&mut self, kind: ProviderKind) {
+        if self.model == self.kind.default_model() {
+            self.model = kind.default_model().to_string();
+        }
+        if kind != self.kind {
+            self.endpoint = None;
+        }
+        self.kind = kind;
+    }
+}
+
+/// Effective settings: built-in defaults, then the config file, then
+/// environment variables, then command-line overrides.
 #[derive(Debug, Clone)]
 pub struct Config {
-    pub anthropic_api_key: String,
+    /// The config file that was read, if any.
+    pub source: Option<PathBuf>,
     pub bind_address: String,
     pub database_url: String,
+    /// Currency that reports convert amounts into.
+    pub base_currency: String,
+    pub chat: ProviderConfig,
+    pub classifier: ProviderConfig,
+    pub rules_files: Vec<PathBuf>,
+}
+
+/// Settings given on the command line, which win over everything else.
+#[derive(Debug, Default)]
+pub struct Overrides {
+    pub config: Option<PathBuf>,
+    pub database: Option<String>,
+    pub model: Option<String>,
+    pub endpoint: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    database: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    base_currency: Option<String>,
+    #[serde(default)]
+    server: ServerSection,
+    #[serde(default)]
+    chat: ProviderSection,
+    #[serde(default)]
+    classifier: ProviderSection,
+    #[serde(default)]
+    rules: RulesSection,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ServerSection {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bind_address: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProviderSection {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    provider: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    endpoint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timeout_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_retries: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    concurrency: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    batch_size: Option<usize>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesSection {
+    #[serde(default)]
+    files: Vec<PathBuf>,
 }
 
 impl Config {
-    pub fn from_env() -> Result<Self, String> {
+    pub fn defaults() -> Self {
+        Self {
+            source: None,
+            bind_address: "127.0.0.1:3000".to_string(),
+            database_url: "data/budget.db".to_string(),
+            base_currency: "CHF".to_string(),
+            chat: ProviderConfig::new(ProviderKind::Anthropic),
+            classifier: ProviderConfig::new(ProviderKind::Ollama),
+            rules_files: Vec::new(),
+        }
+    }
+
+    /// Load the effective configuration. An explicit `--config` path must exist;
+    /// otherwise `budget-analyser.toml` is read from the working directory if present.
+    pub fn load(overrides: &Overrides) -> Result<Self, String> {
         // Load .env file if present (silently ignored if missing)
         let _ = dotenvy::dotenv();
 
-        let anthropic_api_key = env::var("ANTHROPIC_API_KEY")
-            .map_err(|_| "Missing ANTHROPIC_API_KEY — set it in .env or as an environment variable".to_string())?;
-        let bind_address = env::var("BIND_ADDRESS").unwrap_or_else(|_| "127.0.0.1:3000".to_string());
-        let database_url = env::var("DATABASE_URL").unwrap_or_else(|_| "data/budget.db".to_string());
+        let mut config = Self::defaults();
+
+        let path = match &overrides.config {
+            Some(path) => Some(path.clone()),
+            None => env::var("BUDGET_CONFIG")
+                .ok()
+                .map(PathBuf::from)
+                .or_else(|| Some(PathBuf::from(DEFAULT_CONFIG_FILE)).filter(|p| p.exists())),
+        };
+        if let Some(path) = path {
+            config.apply_file(&path)?;
+        }
+
+        config.apply_env()?;
+
+        if let Some(database) = &overrides.database {
+            config.database_url = database.clone();
+        }
+        if let Some(model) = &overrides.model {
+            config.classifier.model = model.clone();
+        }
+        if let Some(endpoint) = &overrides.endpoint {
+            config.classifier.endpoint = Some(endpoint.clone());
+        }
+
+        Ok(config)
+    }
+
+    fn apply_file(&mut self, path: &Path) -> Result<(), String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read config file {}: {}", path.display(), e))?;
+        let file: FileConfig = toml::from_str(&text)
+            .map_err(|e| format!("invalid config file {}: {}", path.display(), e))?;
+
+        if let Some(database) = file.database {
+            self.database_url = database;
+        }
+        if let Some(currency) = file.base_currency {
+            self.base_currency = parse_currency(&currency)?;
+        }
+        if let Some(bind_address) = file.server.bind_address {
+            self.bind_address = bind_address;
+        }
+        self.chat.apply_file(&file.chat)?;
+        self.classifier.apply_file(&file.classifier)?;
+
+        // Rule files are relative to the config file, not the working directory
+        let base = path.parent().unwrap_or(Path::new(""));
+        self.rules_files = file.rules.files.iter().map(|f| base.join(f)).collect();
+
+        self.source = Some(path.to_path_buf());
+        Ok(())
+    }
+
+    fn apply_env(&mut self) -> Result<(), String> {
+        if let Ok(bind_address) = env::var("BIND_ADDRESS") {
+            self.bind_address = bind_address;
+        }
+        if let Ok(database_url) = env::var("DATABASE_URL") {
+            self.database_url = database_url;
+        }
+        if let Ok(currency) = env::var("BASE_CURRENCY") {
+            self.base_currency = parse_currency(&currency)?;
+        }
+        self.chat.apply_env("CHAT")?;
+        self.classifier.apply_env("CLASSIFIER")?;
+        Ok(())
+    }
+
+    /// The effective settings in config-file syntax, for `config show`.
+    pub fn to_toml(&self) -> String {
+        let section = |p: &ProviderConfig| ProviderSection {
+            provider: Some(p.kind.to_string()),
+            model: Some(p.model.clone()),
+            endpoint: p.endpoint.clone(),
+            timeout_secs: Some(p.timeout_secs),
+            max_retries: Some(p.max_retries),
+            concurrency: Some(p.concurrency),
+            batch_size: Some(p.batch_size),
+        };
+        let file = FileConfig {
+            database: Some(self.database_url.clone()),
+            base_currency: Some(self.base_currency.clone()),
+            server: ServerSection { bind_address: Some(self.bind_address.clone()) },
+            chat: section(&self.chat),
+            classifier: section(&self.classifier),
+            rules: RulesSection { files: self.rules_files.clone() },
+        };
+        toml::to_string_pretty(&file).unwrap_or_default()
+    }
+}
+
+/// An ISO 4217 code such as `CHF`, normalised to upper case.
+fn parse_currency(value: &str) -> Result<String, String> {
+    let code = value.trim().to_ascii_uppercase();
+    if code.len() != 3 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(format!("'{}' is not a three-letter currency code", value));
+    }
+    Ok(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_settings_and_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("budget-analyser.toml");
+        std::fs::write(&path, r#"
+database = "data/other.db"
+base_currency = "eur"
+
+[classifier]
+provider = "openai"
+endpoint = "http://localhost:8080/v1"
+timeout_secs = 60
+concurrency = 8
+batch_size = 10
+
+[chat]
+model = "claude-haiku-4-5"
+
+[rules]
+files = ["rules/cc.toml"]
+"#)?;
+
+        let mut config = Config::defaults();
+        config.apply_file(&path)?;
+        assert_eq!(config.database_url, "data/other.db");
+        assert_eq!(config.base_currency, "EUR");
+        assert_eq!(config.classifier.kind, ProviderKind::OpenAi);
+        assert_eq!(config.classifier.model, "local-model");
+        assert_eq!(config.classifier.timeout_secs, 60);
+        assert_eq!(config.classifier.concurrency, 8);
+        assert_eq!(config.classifier.batch_size, 10);
+        assert_eq!(config.chat.batch_size, 1);
+        assert_eq!(config.chat.kind, ProviderKind::Anthropic);
+        assert_eq!(config.chat.model, "claude-haiku-4-5");
+        assert_eq!(config.rules_files, vec![dir.path().join("rules/cc.toml")]);
+
+        // `config show` output is itself a valid config file
+        let shown: FileConfig = toml::from_str(&config.to_toml())?;
+        assert_eq!(shown.classifier.endpoint.as_deref(), Some("http://localhost:8080/v1"));
+        Ok(())
+    }
 
-        Ok(Self {
-            anthropic_api_key,
-            bind_address,
-            database_url,
-        })
+    #[test]
+    fn test_unknown_keys_are_rejected() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("bad.toml");
+        std::fs::write(&path, "[classifier]\nmodle = \"typo\"\n")?;
+        let err = Config::defaults().apply_file(&path).unwrap_err();
+        assert!(err.contains("modle"), "{}", err);
+        Ok(())
     }
 }
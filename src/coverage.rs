@@ -0,0 +1,197 @@
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::Path;
+
+use chrono::NaiveDate;
+use sha2::{Digest, Sha256};
+
+/// Gaps shorter than this between periods taken from the first and last
+/// booking are quiet days, not a missing file.
+pub const INFERRED_GAP_DAYS: i64 = 7;
+
+/// One imported file as recorded in `import_log`.
+#[derive(Debug, Clone)]
+pub struct ImportRecord {
+    pub id: i64,
+    pub filename: String,
+    pub file_hash: Option<String>,
+    pub row_count: i64,
+    pub imported_at: String,
+    pub period: Option<(NaiveDate, NaiveDate)>,
+    /// True when the period comes from the statement header rather than from
+    /// the first and last booking.
+    pub period_exact: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CoverageIssue {
+    /// No import covers these days.
+    Gap { from: NaiveDate, to: NaiveDate },
+    /// Two different files cover the same days.
+    Overlap { first: String, second: String, from: NaiveDate, to: NaiveDate },
+    /// The same file content was imported under several names.
+    Renamed { names: Vec<String> },
+}
+
+impl std::fmt::Display for CoverageIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CoverageIssue::Gap { from, to } => write!(f, "gap: nothing imported from {} to {}", from, to),
+            CoverageIssue::Overlap { first, second, from, to } => {
+                write!(f, "overlap: {} and {} both cover {} to {}", first, second, from, to)
+            }
+            CoverageIssue::Renamed { names } => write!(f, "same file imported as {}", names.join(", ")),
+        }
+    }
+}
+
+/// SHA-256 of a file's contents, as lowercase hex.
+pub fn file_hash(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Merge the import periods of one account into covered date ranges and list
+/// the gaps and overlaps between them. Re-imports of identical content are
+/// neither; see `renamed_files` for those.
+pub fn analyse_periods(imports: &[ImportRecord]) -> (Vec<(NaiveDate, NaiveDate)>, Vec<CoverageIssue>) {
+    let mut dated: Vec<&ImportRecord> = imports.iter().filter(|i| i.period.is_some()).collect();
+    dated.sort_by_key(|i| (i.period, i.id));
+    let mut seen_hashes = Vec::new();
+    dated.retain(|i| match &i.file_hash {
+        Some(hash) if seen_hashes.contains(&hash) => false,
+        Some(hash) => {
+            seen_hashes.push(hash);
+            true
+        }
+        None => true,
+    });
+
+    let mut covered: Vec<(NaiveDate, NaiveDate)> = Vec::new();
+    let mut issues = Vec::new();
+    let mut latest: Option<&ImportRecord> = None;
+    for import in dated {
+        let (start, end) = import.period.expect("filtered above");
+        if let (Some(prev), Some(last)) = (latest, covered.last_mut()) {
+            let (_, prev_end) = prev.period.expect("filtered above");
+            if start <= prev_end {
+                issues.push(CoverageIssue::Overlap {
+                    first: prev.filename.clone(),
+                    second: import.filename.clone(),
+                    from: start,
+                    to: end.min(prev_end),
+                });
+            }
+            let allowed = if import.period_exact && prev.period_exact { 1 } else { INFERRED_GAP_DAYS };
+            if (start - last.1).num_days() > allowed {
+                issues.push(CoverageIssue::Gap {
+                    from: last.1.succ_opt().unwrap_or(last.1),
+                    to: start.pred_opt().unwrap_or(start),
+                });
+                covered.push((start, end));
+            } else {
+                last.1 = last.1.max(end);
+            }
+        } else {
+            covered.push((start, end));
+        }
+        if latest.is_none_or(|l| l.period.map(|p| p.1) < Some(end)) {
+            latest = Some(import);
+        }
+    }
+    (covered, issues)
+}
+
+/// Files whose content was imported under more than one name.
+pub fn renamed_files(imports: &[ImportRecord]) -> Vec<CoverageIssue> {
+    let mut by_hash: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for import in imports {
+        if let Some(hash) = &import.file_hash {
+            let names = by_hash.entry(hash).or_default();
+            if !names.contains(&import.filename) {
+                names.push(import.filename.clone());
+            }
+        }
+    }
+    by_hash
+        .into_values()
+        .filter(|names| names.len() > 1)
+        .map(|names| CoverageIssue::Renamed { names })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn import(id: i64, name: &str, hash: &str, from: &str, to: &str, exact: bool) -> ImportRecord {
+        ImportRecord {
+            id,
+            filename: name.to_string(),
+            file_hash: Some(hash.to_string()),
+            row_count: 10,
+            imported_at: String::new(),
+            period: Some((d(from), d(to))),
+            period_exact: exact,
+        }
+    }
+
+    #[test]
+    fn test_statements_report_gaps_and_overlaps() {
+        let imports = vec![
+            import(1, "jan.csv", "a", "2026-01-01", "2026-01-31", true),
+            import(2, "mar.csv", "c", "2026-03-01", "2026-03-31", true),
+            import(3, "mar-mid.csv", "d", "2026-03-15", "2026-04-15", true),
+            // Same content again: not an overlap
+            import(4, "jan-copy.csv", "a", "2026-01-01", "2026-01-31", true),
+        ];
+        let (covered, issues) = analyse_periods(&imports);
+        assert_eq!(covered, vec![(d("2026-01-01"), d("2026-01-31")), (d("2026-03-01"), d("2026-04-15"))]);
+        assert_eq!(issues, vec![
+            CoverageIssue::Gap { from: d("2026-02-01"), to: d("2026-02-28") },
+            CoverageIssue::Overlap {
+                first: "mar.csv".to_string(),
+                second: "mar-mid.csv".to_string(),
+                from: d("2026-03-15"),
+                to: d("2026-03-31"),
+            },
+        ]);
+        assert_eq!(renamed_files(&imports), vec![CoverageIssue::Renamed {
+            names: vec!["jan.csv".to_string(), "jan-copy.csv".to_string()],
+        }]);
+    }
+
+    #[test]
+    fn test_inferred_periods_tolerate_quiet_days() {
+        let imports = vec![
+            import(1, "card-jan.csv", "a", "2026-01-03", "2026-01-28", false),
+            import(2, "card-feb.csv", "b", "2026-02-02", "2026-02-26", false),
+            import(3, "card-apr.csv", "c", "2026-04-01", "2026-04-29", false),
+        ];
+        let (covered, issues) = analyse_periods(&imports);
+        assert_eq!(covered.len(), 2);
+        assert_eq!(issues, vec![CoverageIssue::Gap { from: d("2026-02-27"), to: d("2026-03-31") }]);
+    }
+
+    #[test]
+    fn test_file_hash_is_sha256() -> std::io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("empty.csv");
+        std::fs::write(&path, "")?;
+        assert_eq!(file_hash(&path)?, "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+        Ok(())
+    }
+}
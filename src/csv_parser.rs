@@ -13,12 +13,55 @@ pub struct Transaction {
     pub currency: String,
     pub debit: Option<f64>,
     pub credit: Option<f64>,
-    pub _balance: f64,
+    /// Balance after this booking, as reported by the bank.
+    pub balance: Option<f64>,
     pub transaction_id: String,
     pub description: String,
     pub details: String,
     pub sector: Option<String>,
     pub _footnotes: String,
+    /// Amount and currency charged abroad, with the card issuer's rate to `currency`
+    /// (credit card exports only, and only when the currency differs).
+    pub original_amount: Option<f64>,
+    pub original_currency: Option<String>,
+    pub fx_rate: Option<f64>,
+    /// The account or card it was booked on, when the export says.
+    pub account: Option<AccountRef>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AccountKind {
+    Account,
+    Card,
+}
+
+impl AccountKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccountKind::Account => "account",
+            AccountKind::Card => "card",
+        }
+    }
+}
+
+/// Balances an account statement reports for its period: `opening` at the start
+/// of the first day, `closing` at the end of the last.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatementBalances {
+    pub account: AccountRef,
+    pub opening: Option<(NaiveDate, f64)>,
+    pub closing: Option<(NaiveDate, f64)>,
+}
+
+/// Account identity as printed in an export: the statement preamble for bank
+/// accounts, the card columns for credit card invoices.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountRef {
+    pub kind: AccountKind,
+    /// Account number, or the card number for cards.
+    pub number: String,
+    pub iban: Option<String>,
+    pub holder: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -43,6 +86,29 @@ pub enum CsvFormat {
     CreditCard,
 }
 
+impl std::str::FromStr for CsvFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "synthetic" => Ok(CsvFormat::Synthetic),
+            "account-statement" => Ok(CsvFormat::AccountStatement),
+            "credit-card" => Ok(CsvFormat::CreditCard),
+            other => Err(format!("unknown format '{}' (expected account-statement, credit-card or synthetic)", other)),
+        }
+    }
+}
+
+impl std::fmt::Display for CsvFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CsvFormat::Synthetic => write!(f, "synthetic"),
+            CsvFormat::AccountStatement => write!(f, "account-statement"),
+            CsvFormat::CreditCard => write!(f, "credit-card"),
+        }
+    }
+}
+
 fn detect_format(path: &Path) -> Result<CsvFormat, String> {
     let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
     let mut reader = BufReader::new(file);
@@ -106,13 +172,17 @@ fn parse_synthetic(path: &Path) -> Result<Vec<Transaction>, String> {
             currency: raw.currency,
             debit: parse_amount(&raw.debit),
             credit: parse_amount(&raw.credit),
-            _balance: raw.balance.parse::<f64>()
-                .map_err(|e| format!("Failed to parse balance on row {}: {}", i + 1, e))?,
+            balance: Some(raw.balance.parse::<f64>()
+                .map_err(|e| format!("Failed to parse balance on row {}: {}", i + 1, e))?),
             transaction_id: raw.transaction_id,
             description: raw.description,
             details: raw.details.unwrap_or_default(),
             sector: None,
             _footnotes: raw.footnotes.unwrap_or_default(),
+            original_amount: None,
+            original_currency: None,
+            fx_rate: None,
+            account: None,
         };
 
         transactions.push(tx);
@@ -141,6 +211,8 @@ struct AccountRecord {
     debit: Option<String>,
     #[serde(rename = "Credit")]
     credit: Option<String>,
+    #[serde(rename = "Balance")]
+    balance: Option<String>,
     #[serde(rename = "Transaction no.")]
     transaction_no: String,
 }
@@ -150,29 +222,106 @@ fn parse_iso_date(s: &str) -> Result<NaiveDate, String> {
         .map_err(|e| format!("Failed to parse ISO date '{}': {}", s, e))
 }
 
-fn parse_account_statement(path: &Path) -> Result<Vec<Transaction>, String> {
-    let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
-    let transcoded = DecodeReaderBytesBuilder::new()
-        .encoding(Some(encoding_rs::WINDOWS_1252))
-        .build(file);
-    let mut reader = BufReader::new(transcoded);
-    let mut skip_lines = 0;
-    let header_line;
-
-    // Scan for header row
-    loop {
-        let mut line = String::new();
-        let bytes = reader.read_line(&mut line).map_err(|e| format!("Failed to read line: {}", e))?;
-        if bytes == 0 {
-            return Err("Header row starting with 'Trade date;' not found".to_string());
-        }
-        if line.starts_with("Trade date;") {
-            header_line = line;
-            break;
+/// The `Key:;value;` lines above an account statement's header row.
+struct Preamble {
+    entries: Vec<(String, String)>,
+    skip_lines: usize,
+    header_line: String,
+}
+
+impl Preamble {
+    fn read(path: &Path) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+        let transcoded = DecodeReaderBytesBuilder::new()
+            .encoding(Some(encoding_rs::WINDOWS_1252))
+            .build(file);
+        let mut reader = BufReader::new(transcoded);
+        let mut skip_lines = 0;
+        let mut entries = Vec::new();
+
+        // Scan for header row, keeping the `Key:;value;` lines above it
+        loop {
+            let mut line = String::new();
+            let bytes = reader.read_line(&mut line).map_err(|e| format!("Failed to read line: {}", e))?;
+            if bytes == 0 {
+                return Err("Header row starting with 'Trade date;' not found".to_string());
+            }
+            if line.starts_with("Trade date;") {
+                return Ok(Preamble { entries, skip_lines, header_line: line });
+            }
+            if let Some((key, rest)) = line.split_once(":;") {
+                let value = rest.split(';').next().unwrap_or("").trim();
+                entries.push((key.trim().to_string(), value.to_string()));
+            }
+            skip_lines += 1;
         }
-        skip_lines += 1;
     }
 
+    fn value(&self, key: &str) -> Option<String> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.clone())
+            .filter(|v| !v.is_empty())
+    }
+
+    fn account(&self) -> Option<AccountRef> {
+        self.value("Account number").map(|number| AccountRef {
+            kind: AccountKind::Account,
+            number,
+            iban: self.value("IBAN"),
+            holder: None,
+        })
+    }
+
+    /// A dated balance, e.g. `From` with `Opening balance`. Thousands
+    /// separators (`7'908.77`) are allowed.
+    fn balance(&self, date_key: &str, balance_key: &str) -> Result<Option<(NaiveDate, f64)>, String> {
+        let (Some(date), Some(balance)) = (self.value(date_key), self.value(balance_key)) else {
+            return Ok(None);
+        };
+        let amount = balance
+            .replace(['\'', ' '], "")
+            .parse::<f64>()
+            .map_err(|e| format!("Failed to parse {} '{}': {}", balance_key, balance, e))?;
+        Ok(Some((parse_iso_date(&date)?, amount)))
+    }
+}
+
+/// Opening and closing balances from an account statement's preamble. `None`
+/// for other formats and for statements that do not name their account.
+pub fn parse_statement_balances(path: &Path) -> Result<Option<StatementBalances>, String> {
+    if detect_format(path)? != CsvFormat::AccountStatement {
+        return Ok(None);
+    }
+    let preamble = Preamble::read(path)?;
+    let Some(account) = preamble.account() else {
+        return Ok(None);
+    };
+    Ok(Some(StatementBalances {
+        account,
+        opening: preamble.balance("From", "Opening balance")?,
+        closing: preamble.balance("Until", "Closing balance")?,
+    }))
+}
+
+/// The `From`/`Until` period an account statement covers, if it says.
+pub fn parse_statement_period(path: &Path) -> Result<Option<(NaiveDate, NaiveDate)>, String> {
+    if detect_format(path)? != CsvFormat::AccountStatement {
+        return Ok(None);
+    }
+    let preamble = Preamble::read(path)?;
+    match (preamble.value("From"), preamble.value("Until")) {
+        (Some(from), Some(until)) => Ok(Some((parse_iso_date(&from)?, parse_iso_date(&until)?))),
+        _ => Ok(None),
+    }
+}
+
+fn parse_account_statement(path: &Path) -> Result<Vec<Transaction>, String> {
+    let preamble = Preamble::read(path)?;
+    let account = preamble.account();
+    let Preamble { skip_lines, header_line, .. } = preamble;
+
     // Re-open file and skip lines to use csv crate
     let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
     let transcoded = DecodeReaderBytesBuilder::new()
@@ -227,12 +376,16 @@ fn parse_account_statement(path: &Path) -> Result<Vec<Transaction>, String> {
             currency: raw.currency,
             debit: parse_amount(&raw.debit).map(|v| v.abs()),
             credit: parse_amount(&raw.credit).map(|v| v.abs()),
-            _balance: 0.0,
+            balance: parse_amount(&raw.balance),
             transaction_id: raw.transaction_no,
             description: raw.description1,
             details: details_parts.join("; "),
             sector: None,
             _footnotes: String::new(),
+            original_amount: None,
+            original_currency: None,
+            fx_rate: None,
+            account: account.clone(),
         };
 
         transactions.push(tx);
@@ -288,6 +441,16 @@ fn parse_credit_card(path: &Path) -> Result<Vec<Transaction>, String> {
         };
 
         let tx_id = generate_cc_transaction_id(&raw);
+        let account = card_account(&raw);
+        let original_currency = raw.original_currency
+            .as_deref()
+            .map(str::trim)
+            .filter(|c| !c.is_empty() && *c != raw.currency.trim())
+            .map(str::to_string);
+        let (original_amount, fx_rate) = match original_currency {
+            Some(_) => (parse_amount(&raw.amount).map(|v| v.abs()), parse_amount(&raw.rate)),
+            None => (None, None),
+        };
 
         let tx = Transaction {
             trade_date,
@@ -296,12 +459,16 @@ fn parse_credit_card(path: &Path) -> Result<Vec<Transaction>, String> {
             currency: raw.currency,
             debit: parse_amount(&raw.debit).map(|v| v.abs()),
             credit: parse_amount(&raw.credit).map(|v| v.abs()),
-            _balance: 0.0,
+            balance: None,
             transaction_id: tx_id,
             description: raw.booking_text,
             details: raw.sector.clone().unwrap_or_default(),
             sector: raw.sector,
             _footnotes: String::new(),
+            original_amount,
+            original_currency,
+            fx_rate,
+            account,
         };
 
         transactions.push(tx);
@@ -312,6 +479,10 @@ fn parse_credit_card(path: &Path) -> Result<Vec<Transaction>, String> {
 
 #[derive(Debug, Deserialize)]
 struct CreditCardRecord {
+    #[serde(rename = "Card number")]
+    card_number: Option<String>,
+    #[serde(rename = "Account/Cardholder")]
+    cardholder: Option<String>,
     #[serde(rename = "Purchase date")]
     purchase_date: String,
     #[serde(rename = "Booked")]
@@ -320,6 +491,12 @@ struct CreditCardRecord {
     booking_text: String,
     #[serde(rename = "Sector")]
     sector: Option<String>,
+    #[serde(rename = "Amount")]
+    amount: Option<String>,
+    #[serde(rename = "Original currency")]
+    original_currency: Option<String>,
+    #[serde(rename = "Rate")]
+    rate: Option<String>,
     #[serde(rename = "Currency")]
     currency: String,
     #[serde(rename = "Debit")]
@@ -328,6 +505,16 @@ struct CreditCardRecord {
     credit: Option<String>,
 }
 
+fn card_account(raw: &CreditCardRecord) -> Option<AccountRef> {
+    let number = raw.card_number.as_deref().map(str::trim).filter(|n| !n.is_empty())?;
+    Some(AccountRef {
+        kind: AccountKind::Card,
+        number: number.to_string(),
+        iban: None,
+        holder: raw.cardholder.as_deref().map(str::trim).filter(|h| !h.is_empty()).map(str::to_string),
+    })
+}
+
 fn generate_cc_transaction_id(raw: &CreditCardRecord) -> String {
     // FNV-1a 64-bit — stable across Rust versions (unlike DefaultHasher)
     const FNV_OFFSET: u64 = 0xcbf29ce484222325;
@@ -0,0 +1,129 @@
+use rusqlite::{params, Result};
+use chrono::Utc;
+
+use super::Database;
+use crate::csv_parser::AccountRef;
+
+/// SQL condition matching transactions booked on an account whose number, IBAN
+/// (with or without spaces), card holder or name contains the bound parameter.
+pub const ACCOUNT_FILTER: &str = "account_id IN (SELECT id FROM accounts WHERE LOWER(
+    number || ' ' || COALESCE(iban, '') || ' ' || REPLACE(COALESCE(iban, ''), ' ', '') || ' ' ||
+    holder || ' ' || COALESCE(name, '')) LIKE '%' || LOWER(?) || '%')";
+
+#[derive(Debug, Clone)]
+pub struct Account {
+    pub id: i64,
+    pub kind: String,
+    pub number: String,
+    pub iban: Option<String>,
+    pub holder: Option<String>,
+    pub name: Option<String>,
+    pub transactions: i64,
+}
+
+impl Account {
+    /// The user's name for the account, or how the bank identifies it.
+    pub fn label(&self) -> String {
+        if let Some(name) = &self.name {
+            return name.clone();
+        }
+        match (&self.holder, &self.iban) {
+            (Some(holder), _) => format!("{} {} ({})", self.kind, self.number, holder),
+            (None, Some(iban)) => format!("{} {}", self.kind, iban),
+            (None, None) => format!("{} {}", self.kind, self.number),
+        }
+    }
+}
+
+impl Database {
+    /// Id of the account, creating it on first sight and filling in an IBAN that
+    /// an earlier export did not have. Each holder of a card gets their own row.
+    pub fn upsert_account(&self, account: &AccountRef) -> Result<i64> {
+        let holder = account.holder.as_deref().unwrap_or("");
+        self.conn.execute(
+            "INSERT INTO accounts (kind, number, holder, iban, created_at) VALUES (?, ?, ?, ?, ?)
+             ON CONFLICT (kind, number, holder) DO UPDATE SET iban = COALESCE(excluded.iban, iban)",
+            params![account.kind.as_str(), account.number, holder, account.iban, Utc::now().to_rfc3339()],
+        )?;
+        self.conn.query_row(
+            "SELECT id FROM accounts WHERE kind = ? AND number = ? AND holder = ?",
+            params![account.kind.as_str(), account.number, holder],
+            |row| row.get(0),
+        )
+    }
+
+    pub fn list_accounts(&self) -> Result<Vec<Account>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT a.id, a.kind, a.number, a.iban, NULLIF(a.holder, ''), a.name,
+                    (SELECT COUNT(*) FROM transactions t WHERE t.account_id = a.id)
+             FROM accounts a
+             ORDER BY a.kind, a.number, a.holder"
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(Account {
+                id: row.get(0)?,
+                kind: row.get(1)?,
+                number: row.get(2)?,
+                iban: row.get(3)?,
+                holder: row.get(4)?,
+                name: row.get(5)?,
+                transactions: row.get(6)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Ids of the accounts matching a filter as in `ACCOUNT_FILTER`.
+    pub fn matching_account_ids(&self, filter: &str) -> Result<Vec<i64>> {
+        let query = format!("SELECT account_id FROM (SELECT id AS account_id FROM accounts) WHERE {}", ACCOUNT_FILTER);
+        let mut stmt = self.conn.prepare(&query)?;
+        let ids = stmt.query_map(params![filter], |row| row.get(0))?;
+        ids.collect()
+    }
+
+    /// Give an account a friendly name. Returns false if there is no such account.
+    pub fn rename_account(&self, id: i64, name: &str) -> Result<bool> {
+        let name = Some(name.trim()).filter(|n| !n.is_empty());
+        let changed = self.conn.execute("UPDATE accounts SET name = ? WHERE id = ?", params![name, id])?;
+        Ok(changed > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classifier::ClassificationResult;
+    use crate::csv_parser::{self, AccountKind};
+    use std::path::Path;
+
+    #[test]
+    fn test_statement_rows_are_tagged_with_their_account() -> Result<(), Box<dyn std::error::Error>> {
+        let csv_path = Path::new("tests/fixtures/account_statement_tiny.csv");
+        if !csv_path.exists() { return Ok(()); }
+        let db = Database::open(Path::new(":memory:"))?;
+        let (_, transactions) = csv_parser::parse_csv(csv_path)?;
+        let result = ClassificationResult { merchant: "X".to_string(), category: "Other".to_string(), confidence: 0.9, source: "llm".to_string() };
+        for tx in &transactions {
+            db.insert_transaction(tx, &result, Some("account_statement_tiny.csv"))?;
+        }
+
+        // Every row is booked on the account from the statement preamble
+        let accounts = db.list_accounts()?;
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].number, "0292 00123456.78");
+        assert_eq!(accounts[0].iban.as_deref(), Some("CH76 0029 2292 1234 5678 9"));
+        assert_eq!(accounts[0].transactions, transactions.len() as i64);
+
+        // A later export without the IBAN finds the same account and keeps it
+        let same = db.upsert_account(&AccountRef {
+            kind: AccountKind::Account,
+            number: "0292 00123456.78".to_string(),
+            iban: None,
+            holder: None,
+        })?;
+        assert_eq!(same, accounts[0].id);
+        assert_eq!(db.list_accounts()?[0].iban.as_deref(), Some("CH76 0029 2292 1234 5678 9"));
+        Ok(())
+    }
+}
+
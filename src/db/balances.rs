@@ -0,0 +1,187 @@
+use std::collections::BTreeSet;
+
+use rusqlite::{params, Result};
+use chrono::NaiveDate;
+
+use super::accounts::Account;
+use super::Database;
+use crate::balances::{balance_at, month_ends, reconcile, BalanceGap, ReportedBalance};
+use crate::csv_parser::StatementBalances;
+
+/// Month-end balances of one account.
+#[derive(Debug, Clone)]
+pub struct AccountHistory {
+    pub account: Account,
+    pub currency: String,
+    /// False when the bank never reported a balance and it is counted from zero.
+    pub anchored: bool,
+    pub balances: Vec<f64>,
+    pub gaps: Vec<BalanceGap>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BalanceHistory {
+    pub months: Vec<NaiveDate>,
+    pub accounts: Vec<AccountHistory>,
+    /// Sum over all accounts, in the base currency.
+    pub net_worth: Vec<f64>,
+    /// Currencies without a rate to the base currency, counted unconverted.
+    pub unconverted: BTreeSet<String>,
+}
+
+impl Database {
+    /// Keep a statement's opening and closing balances for reconciliation.
+    /// Returns the id of the statement's account.
+    pub fn store_statement_balances(&self, balances: &StatementBalances, import_batch: &str) -> Result<i64> {
+        let account_id = self.upsert_account(&balances.account)?;
+        for (kind, entry) in [("opening", balances.opening), ("closing", balances.closing)] {
+            if let Some((date, balance)) = entry {
+                self.conn.execute(
+                    "INSERT OR REPLACE INTO statement_balances (account_id, date, kind, balance, import_batch)
+                     VALUES (?, ?, ?, ?, ?)",
+                    params![account_id, date.to_string(), kind, balance, import_batch],
+                )?;
+            }
+        }
+        Ok(account_id)
+    }
+
+    /// Reported balances as end-of-day figures; an opening balance is the
+    /// balance at the end of the day before.
+    pub fn reported_balances(&self, account_id: i64) -> Result<Vec<ReportedBalance>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT date, kind, balance FROM statement_balances WHERE account_id = ? ORDER BY date, kind DESC"
+        )?;
+        let rows = stmt.query_map(params![account_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, f64>(2)?))
+        })?;
+
+        let mut reported = Vec::new();
+        for row in rows {
+            let (date, kind, balance) = row?;
+            let Ok(date) = NaiveDate::parse_from_str(&date, "%Y-%m-%d") else { continue };
+            let date = if kind == "opening" { date.pred_opt().unwrap_or(date) } else { date };
+            reported.push(ReportedBalance { date, balance });
+        }
+        Ok(reported)
+    }
+
+    fn account_bookings(&self, account_id: i64) -> Result<Vec<(NaiveDate, f64)>> {
+        let mut stmt = self.conn.prepare("SELECT date, amount FROM transactions WHERE account_id = ?")?;
+        let rows = stmt.query_map(params![account_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?)))?;
+        let mut bookings = Vec::new();
+        for row in rows {
+            let (date, amount) = row?;
+            if let Ok(date) = NaiveDate::parse_from_str(&date, "%Y-%m-%d") {
+                bookings.push((date, amount));
+            }
+        }
+        Ok(bookings)
+    }
+
+    /// Periods where the reported balances of an account do not match its
+    /// imported transactions.
+    pub fn reconcile_account(&self, account_id: i64) -> Result<Vec<BalanceGap>> {
+        Ok(reconcile(&self.reported_balances(account_id)?, &self.account_bookings(account_id)?))
+    }
+
+    /// Month-end balances per account, anchored on the latest reported balance,
+    /// and their total converted to `base`. `account` restricts to matching
+    /// accounts as in the report tools' account filter.
+    pub fn balance_history(&self, base: &str, account: Option<&str>) -> Result<BalanceHistory> {
+        let mut accounts = self.list_accounts()?;
+        if let Some(filter) = account {
+            let ids = self.matching_account_ids(filter)?;
+            accounts.retain(|a| ids.contains(&a.id));
+        }
+
+        let mut loaded = Vec::new();
+        for account in accounts {
+            let bookings = self.account_bookings(account.id)?;
+            let reported = self.reported_balances(account.id)?;
+            if bookings.is_empty() && reported.is_empty() {
+                continue;
+            }
+            let currency: String = self.conn.query_row(
+                "SELECT COALESCE((SELECT currency FROM transactions WHERE account_id = ?
+                                  GROUP BY currency ORDER BY COUNT(*) DESC LIMIT 1), ?)",
+                params![account.id, base],
+                |row| row.get(0),
+            )?;
+            loaded.push((account, currency, bookings, reported));
+        }
+
+        let dates = loaded.iter().flat_map(|(_, _, bookings, reported)| {
+            bookings.iter().map(|(d, _)| *d).chain(reported.iter().map(|r| r.date))
+        });
+        let (Some(first), Some(last)) = (dates.clone().min(), dates.max()) else {
+            return Ok(BalanceHistory::default());
+        };
+        let months = month_ends(first, last);
+
+        let fx = self.load_fx_table()?;
+        let mut history = BalanceHistory {
+            net_worth: vec![0.0; months.len()],
+            months,
+            ..Default::default()
+        };
+        for (account, currency, bookings, reported) in loaded {
+            let anchor = reported.iter().max_by_key(|r| r.date).copied();
+            let balances: Vec<f64> = history.months.iter().map(|m| balance_at(anchor, &bookings, *m)).collect();
+            for (i, month) in history.months.iter().enumerate() {
+                let rate = if currency == base { Some(1.0) } else { fx.rate(&currency, base, *month) };
+                if rate.is_none() {
+                    history.unconverted.insert(currency.clone());
+                }
+                history.net_worth[i] += balances[i] * rate.unwrap_or(1.0);
+            }
+            history.accounts.push(AccountHistory {
+                account,
+                currency,
+                anchored: anchor.is_some(),
+                balances,
+                gaps: reconcile(&reported, &bookings),
+            });
+        }
+        Ok(history)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classifier::ClassificationResult;
+    use crate::csv_parser;
+    use std::path::Path;
+
+    #[test]
+    fn test_statement_balances_reconcile_against_bookings() -> Result<(), Box<dyn std::error::Error>> {
+        let csv_path = Path::new("tests/fixtures/account_statement_tiny.csv");
+        if !csv_path.exists() { return Ok(()); }
+        let db = Database::open(Path::new(":memory:"))?;
+        let (_, transactions) = csv_parser::parse_csv(csv_path)?;
+        let result = ClassificationResult { merchant: "X".to_string(), category: "Other".to_string(), confidence: 0.9, source: "llm".to_string() };
+        for tx in &transactions {
+            db.insert_transaction(tx, &result, Some("account_statement_tiny.csv"))?;
+        }
+        let balances = csv_parser::parse_statement_balances(csv_path)?.expect("the preamble has balances");
+        let account_id = db.store_statement_balances(&balances, "account_statement_tiny.csv")?;
+
+        // Each booking keeps the balance the bank reported after it
+        let balance: f64 = db.conn.query_row(
+            "SELECT balance FROM transactions WHERE raw_description LIKE 'Steuerverwaltung%'",
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!(balance, 1483.82);
+
+        // The excerpt only has two of the statement's bookings, so its opening
+        // and closing balances do not reconcile
+        let gaps = db.reconcile_account(account_id)?;
+        assert_eq!(gaps.len(), 1);
+        assert!((gaps[0].reported_change - (1483.82 - 7908.77)).abs() < 0.005);
+        assert!((gaps[0].booked_change - -889.25).abs() < 0.005);
+        Ok(())
+    }
+}
+
@@ -0,0 +1,129 @@
+use rusqlite::{params, Result};
+use chrono::Utc;
+
+use super::Database;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Budget {
+    pub category: String,
+    pub monthly_limit: Option<f64>,
+    pub annual_limit: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BudgetPeriod {
+    Monthly,
+    Annual,
+}
+
+impl BudgetPeriod {
+    fn column(&self) -> &'static str {
+        match self {
+            BudgetPeriod::Monthly => "monthly_limit",
+            BudgetPeriod::Annual => "annual_limit",
+        }
+    }
+}
+
+impl Budget {
+    /// Limit for a single month. Falls back to a twelfth of the annual limit
+    /// when only an annual budget is set.
+    pub fn monthly_equivalent(&self) -> Option<f64> {
+        self.monthly_limit.or(self.annual_limit.map(|a| a / 12.0))
+    }
+
+    /// Limit for a whole year. Falls back to twelve times the monthly limit
+    /// when only a monthly budget is set.
+    pub fn annual_equivalent(&self) -> Option<f64> {
+        self.annual_limit.or(self.monthly_limit.map(|m| m * 12.0))
+    }
+}
+
+/// A limit has to be a positive, finite amount.
+pub fn validate_budget_amount(amount: f64) -> std::result::Result<(), String> {
+    if !amount.is_finite() || amount <= 0.0 {
+        return Err(format!("budget amount must be a positive number, got {}", amount));
+    }
+    Ok(())
+}
+
+impl Database {
+    /// Set (or replace) the monthly or annual limit for a category, leaving the
+    /// other period untouched.
+    pub fn set_budget(&self, category: &str, period: BudgetPeriod, amount: f64) -> Result<()> {
+        validate_budget_amount(amount).map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.into()))?;
+        let now = Utc::now().to_rfc3339();
+        let column = period.column();
+        self.conn.execute(
+            &format!(
+                "INSERT INTO budgets (category, {col}, created_at, updated_at) VALUES (?, ?, ?, ?)
+                 ON CONFLICT(category) DO UPDATE SET {col} = excluded.{col}, updated_at = excluded.updated_at",
+                col = column
+            ),
+            params![category, amount, now, now],
+        )?;
+        Ok(())
+    }
+
+    pub fn remove_budget(&self, category: &str) -> Result<bool> {
+        let rows = self.conn.execute("DELETE FROM budgets WHERE category = ?", params![category])?;
+        Ok(rows > 0)
+    }
+
+    pub fn list_budgets(&self) -> Result<Vec<Budget>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT category, monthly_limit, annual_limit FROM budgets ORDER BY category ASC"
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(Budget {
+                category: row.get(0)?,
+                monthly_limit: row.get(1)?,
+                annual_limit: row.get(2)?,
+            })
+        })?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row?);
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dining(monthly_limit: Option<f64>, annual_limit: Option<f64>) -> Vec<Budget> {
+        vec![Budget { category: "Dining".to_string(), monthly_limit, annual_limit }]
+    }
+
+    #[test]
+    fn test_set_replace_and_remove_budget() -> Result<()> {
+        let db = Database::open(std::path::Path::new(":memory:"))?;
+        db.set_budget("Dining", BudgetPeriod::Monthly, 400.0)?;
+        assert_eq!(db.list_budgets()?, dining(Some(400.0), None));
+
+        // Setting a period again replaces its limit and keeps the other one
+        db.set_budget("Dining", BudgetPeriod::Annual, 4000.0)?;
+        db.set_budget("Dining", BudgetPeriod::Monthly, 350.0)?;
+        assert_eq!(db.list_budgets()?, dining(Some(350.0), Some(4000.0)));
+
+        assert!(db.remove_budget("Dining")?);
+        assert!(db.list_budgets()?.is_empty());
+        assert!(!db.remove_budget("Dining")?, "nothing left to remove");
+        Ok(())
+    }
+
+    #[test]
+    fn test_rejects_amounts_that_are_not_positive() -> Result<()> {
+        let db = Database::open(std::path::Path::new(":memory:"))?;
+        db.set_budget("Dining", BudgetPeriod::Monthly, 400.0)?;
+        for amount in [0.0, -50.0, f64::NAN, f64::INFINITY] {
+            assert!(db.set_budget("Dining", BudgetPeriod::Monthly, amount).is_err(), "{} accepted", amount);
+            assert!(db.set_budget("Groceries", BudgetPeriod::Annual, amount).is_err(), "{} accepted", amount);
+        }
+        assert_eq!(db.list_budgets()?, dining(Some(400.0), None));
+        Ok(())
+    }
+}
@@ -0,0 +1,96 @@
+use rusqlite::{params, params_from_iter, Result};
+use chrono::Utc;
+
+use super::Database;
+use crate::calibration::{fit, CalibrationEntry, Judgement, HUMAN_CHANGES};
+
+/// `confidence` scaled by the stored factor for the row's source and category,
+/// falling back to the source's factor. Manual classifications are taken as
+/// they are.
+pub(super) const CALIBRATED_CONFIDENCE: &str = "CASE WHEN source = 'manual' THEN confidence ELSE MIN(1.0, confidence * COALESCE(
+        (SELECT c.factor FROM confidence_calibration c WHERE c.source = transactions.source AND c.category = transactions.category),
+        (SELECT c.factor FROM confidence_calibration c WHERE c.source = transactions.source AND c.category = ''),
+        1.0)) END";
+
+impl Database {
+    /// Every confirmation or correction a person made, with the classification
+    /// they were shown.
+    pub fn review_judgements(&self) -> Result<Vec<Judgement>> {
+        let placeholders = vec!["?"; HUMAN_CHANGES.len()].join(", ");
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT before_source, before_category, before_confidence, before_category = after_category
+             FROM transaction_history WHERE changed_by IN ({})",
+            placeholders
+        ))?;
+        let rows = stmt.query_map(params_from_iter(HUMAN_CHANGES), |row| {
+            Ok(Judgement {
+                source: row.get(0)?,
+                category: row.get(1)?,
+                confidence: row.get(2)?,
+                correct: row.get(3)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Refit the calibration from all reviews so far, store it and recompute
+    /// every transaction's calibrated confidence.
+    pub fn fit_calibration(&self) -> Result<Vec<CalibrationEntry>> {
+        let entries = fit(&self.review_judgements()?);
+        let now = Utc::now().to_rfc3339();
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM confidence_calibration", [])?;
+        for e in &entries {
+            tx.execute(
+                "INSERT INTO confidence_calibration (source, category, reviews, correct, mean_confidence, factor, fitted_at)
+                 VALUES (?, ?, ?, ?, ?, ?, ?)",
+                params![
+                    e.source,
+                    e.category.as_deref().unwrap_or(""),
+                    e.reviews as i64,
+                    e.correct as i64,
+                    e.mean_confidence,
+                    e.factor,
+                    now
+                ],
+            )?;
+        }
+        tx.execute(&format!("UPDATE transactions SET calibrated_confidence = {}", CALIBRATED_CONFIDENCE), [])?;
+        tx.commit()?;
+        Ok(entries)
+    }
+
+    /// The stored calibration, sources first and then their categories.
+    pub fn calibration(&self) -> Result<Vec<CalibrationEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT source, NULLIF(category, ''), reviews, correct, mean_confidence, factor
+             FROM confidence_calibration ORDER BY source, category"
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(CalibrationEntry {
+                source: row.get(0)?,
+                category: row.get(1)?,
+                reviews: row.get::<_, i64>(2)? as usize,
+                correct: row.get::<_, i64>(3)? as usize,
+                mean_confidence: row.get(4)?,
+                factor: row.get(5)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Set the calibrated confidence of an import's transactions from the
+    /// stored calibration, and count those that need review at `threshold`.
+    pub fn calibrate_batch(&self, import_batch: &str, threshold: f64) -> Result<usize> {
+        self.conn.execute(
+            &format!("UPDATE transactions SET calibrated_confidence = {} WHERE import_batch = ?", CALIBRATED_CONFIDENCE),
+            params![import_batch],
+        )?;
+        self.conn.query_row(
+            "SELECT COUNT(*) FROM transactions WHERE import_batch = ?
+               AND (calibrated_confidence < ? OR category = 'Other' OR category = 'Uncategorised')",
+            params![import_batch, threshold],
+            |row| row.get::<_, i64>(0).map(|n| n as usize),
+        )
+    }
+}
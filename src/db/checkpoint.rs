@@ -0,0 +1,97 @@
+use rusqlite::{params, OptionalExtension, Result};
+use chrono::Utc;
+
+use super::{Database, ImportLogEntry};
+
+/// An import that stopped before it finished, as recorded in `import_log`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InterruptedImport {
+    pub id: i64,
+    pub filename: String,
+    pub file_hash: Option<String>,
+    /// Rows of the file handled before the last checkpoint.
+    pub rows_done: usize,
+    /// Transactions inserted before the last checkpoint.
+    pub row_count: usize,
+    pub started_at: String,
+}
+
+const INTERRUPTED_IMPORTS: &str = "SELECT id, filename, file_hash, rows_done, row_count, imported_at
+     FROM import_log WHERE status = 'in_progress' AND rolled_back_at IS NULL";
+
+fn interrupted(row: &rusqlite::Row) -> Result<InterruptedImport> {
+    Ok(InterruptedImport {
+        id: row.get(0)?,
+        filename: row.get(1)?,
+        file_hash: row.get(2)?,
+        rows_done: row.get::<_, i64>(3)? as usize,
+        row_count: row.get::<_, i64>(4)? as usize,
+        started_at: row.get(5)?,
+    })
+}
+
+impl Database {
+    /// Unfinished imports that were not rolled back, oldest first.
+    pub fn interrupted_imports(&self) -> Result<Vec<InterruptedImport>> {
+        let mut stmt = self.conn.prepare(&format!("{} ORDER BY id", INTERRUPTED_IMPORTS))?;
+        let rows = stmt.query_map([], interrupted)?;
+        rows.collect()
+    }
+
+    pub fn interrupted_import(&self, filename: &str) -> Result<Option<InterruptedImport>> {
+        self.conn
+            .query_row(&format!("{} AND filename = ? ORDER BY id DESC", INTERRUPTED_IMPORTS), params![filename], interrupted)
+            .optional()
+    }
+
+    /// Log an import of `filename` as in progress until `finish_import`.
+    pub fn start_import(&self, filename: &str, file_hash: Option<&str>) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO import_log (filename, row_count, imported_at, file_hash, status, rows_done)
+             VALUES (?, 0, ?, ?, 'in_progress', 0)",
+            params![filename, Utc::now().to_rfc3339(), file_hash],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Run `write` and move the import's checkpoint to `rows_done` in one
+    /// transaction, so an interrupted import keeps exactly the rows its
+    /// checkpoint counts. `write` returns the transactions inserted so far.
+    pub fn checkpoint_import<E: From<rusqlite::Error>>(
+        &self,
+        id: i64,
+        rows_done: usize,
+        write: impl FnOnce() -> std::result::Result<usize, E>,
+    ) -> std::result::Result<usize, E> {
+        let tx = self.conn.unchecked_transaction()?;
+        let row_count = write()?;
+        tx.execute(
+            "UPDATE import_log SET rows_done = ?, row_count = ? WHERE id = ?",
+            params![rows_done as i64, row_count as i64, id],
+        )?;
+        tx.commit()?;
+        Ok(row_count)
+    }
+
+    /// Record what the import covered and mark it complete.
+    pub fn finish_import(&self, id: i64, entry: &ImportLogEntry) -> Result<()> {
+        let (start, end) = match entry.period {
+            Some((start, end)) => (Some(start.to_string()), Some(end.to_string())),
+            None => (None, None),
+        };
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "UPDATE import_log SET row_count = ?, imported_at = ?, file_hash = ?, period_start = ?, period_end = ?,
+                    period_exact = ?, status = 'complete'
+             WHERE id = ?",
+            params![entry.row_count as i64, Utc::now().to_rfc3339(), entry.file_hash, start, end, entry.period_exact, id],
+        )?;
+        for account_id in &entry.account_ids {
+            tx.execute(
+                "INSERT OR IGNORE INTO import_log_accounts (import_id, account_id) VALUES (?, ?)",
+                params![id, account_id],
+            )?;
+        }
+        tx.commit()
+    }
+}
@@ -0,0 +1,92 @@
+use rusqlite::Result;
+use chrono::NaiveDate;
+
+use super::accounts::Account;
+use super::Database;
+use crate::coverage::{analyse_periods, renamed_files, CoverageIssue, ImportRecord};
+
+/// Imports of one account and what they cover.
+#[derive(Debug, Clone)]
+pub struct AccountCoverage {
+    /// `None` for files that do not name their account.
+    pub account: Option<Account>,
+    pub imports: Vec<ImportRecord>,
+    pub covered: Vec<(NaiveDate, NaiveDate)>,
+    pub issues: Vec<CoverageIssue>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ImportStatus {
+    pub accounts: Vec<AccountCoverage>,
+    pub renamed: Vec<CoverageIssue>,
+}
+
+impl Database {
+    /// Every finished import that was not rolled back, oldest first, with the
+    /// accounts it covered.
+    pub fn import_records(&self) -> Result<Vec<(ImportRecord, Vec<i64>)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT l.id, l.filename, l.file_hash, l.row_count, l.imported_at, l.period_start, l.period_end, l.period_exact,
+                    (SELECT GROUP_CONCAT(account_id) FROM import_log_accounts a WHERE a.import_id = l.id)
+             FROM import_log l
+             WHERE l.rolled_back_at IS NULL AND l.status = 'complete'
+             ORDER BY l.id"
+        )?;
+        let rows = stmt.query_map([], |row| {
+            let start: Option<String> = row.get(5)?;
+            let end: Option<String> = row.get(6)?;
+            let accounts: Option<String> = row.get(8)?;
+            let date = |s: Option<String>| s.and_then(|s| NaiveDate::parse_from_str(&s, "%Y-%m-%d").ok());
+            let record = ImportRecord {
+                id: row.get(0)?,
+                filename: row.get(1)?,
+                file_hash: row.get(2)?,
+                row_count: row.get(3)?,
+                imported_at: row.get(4)?,
+                period: date(start).zip(date(end)),
+                period_exact: row.get(7)?,
+            };
+            let ids = accounts
+                .unwrap_or_default()
+                .split(',')
+                .filter_map(|id| id.parse().ok())
+                .collect();
+            Ok((record, ids))
+        })?;
+        rows.collect()
+    }
+
+    /// Coverage per account with gaps and overlaps, plus files imported under
+    /// several names. `account` restricts to matching accounts as in the report
+    /// tools' account filter.
+    pub fn import_status(&self, account: Option<&str>) -> Result<ImportStatus> {
+        let records = self.import_records()?;
+        let mut accounts: Vec<Option<Account>> = self.list_accounts()?.into_iter().map(Some).collect();
+        accounts.push(None);
+        if let Some(filter) = account {
+            let ids = self.matching_account_ids(filter)?;
+            accounts.retain(|a| a.as_ref().is_some_and(|a| ids.contains(&a.id)));
+        }
+
+        let mut status = ImportStatus {
+            renamed: renamed_files(&records.iter().map(|(r, _)| r.clone()).collect::<Vec<_>>()),
+            ..Default::default()
+        };
+        for account in accounts {
+            let imports: Vec<ImportRecord> = records
+                .iter()
+                .filter(|(_, ids)| match &account {
+                    Some(a) => ids.contains(&a.id),
+                    None => ids.is_empty(),
+                })
+                .map(|(r, _)| r.clone())
+                .collect();
+            if imports.is_empty() {
+                continue;
+            }
+            let (covered, issues) = analyse_periods(&imports);
+            status.accounts.push(AccountCoverage { account, imports, covered, issues });
+        }
+        Ok(status)
+    }
+}
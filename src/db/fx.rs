@@ -0,0 +1,93 @@
+use std::collections::BTreeMap;
+
+use rusqlite::{params, Result};
+use chrono::NaiveDate;
+
+use super::Database;
+use crate::fx::{FxRate, FxTable};
+
+/// Stored rates for one currency pair.
+#[derive(Debug, Clone)]
+pub struct FxSeries {
+    pub base: String,
+    pub quote: String,
+    pub count: i64,
+    pub first_date: String,
+    pub last_date: String,
+}
+
+impl Database {
+    /// Store rates, replacing any already stored for the same date and pair.
+    pub fn import_fx_rates(&self, rates: &[FxRate], source: &str) -> Result<usize> {
+        let tx = self.conn.unchecked_transaction()?;
+        for r in rates {
+            tx.execute(
+                "INSERT OR REPLACE INTO fx_rates (date, base, quote, rate, source) VALUES (?, ?, ?, ?, ?)",
+                params![r.date.to_string(), r.base, r.quote, r.rate, source],
+            )?;
+        }
+        tx.commit()?;
+        Ok(rates.len())
+    }
+
+    pub fn load_fx_table(&self) -> Result<FxTable> {
+        let mut stmt = self.conn.prepare("SELECT date, base, quote, rate FROM fx_rates")?;
+        let rows = stmt.query_map([], |row| {
+            let date: String = row.get(0)?;
+            Ok((date, row.get(1)?, row.get(2)?, row.get(3)?))
+        })?;
+
+        let mut rates = Vec::new();
+        for row in rows {
+            let (date, base, quote, rate): (String, String, String, f64) = row?;
+            if let Ok(date) = NaiveDate::parse_from_str(&date, "%Y-%m-%d") {
+                rates.push(FxRate { date, base, quote, rate });
+            }
+        }
+        Ok(FxTable::new(rates))
+    }
+
+    pub fn list_fx_series(&self) -> Result<Vec<FxSeries>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT base, quote, COUNT(*), MIN(date), MAX(date) FROM fx_rates GROUP BY base, quote ORDER BY base, quote"
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(FxSeries {
+                base: row.get(0)?,
+                quote: row.get(1)?,
+                count: row.get(2)?,
+                first_date: row.get(3)?,
+                last_date: row.get(4)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Recompute every transaction's rate into `base` from the stored FX rates.
+    /// Returns, per currency, how many transactions have no usable rate; those
+    /// keep a NULL `base_rate` and are reported unconverted.
+    pub fn refresh_base_rates(&self, base: &str) -> Result<BTreeMap<String, usize>> {
+        let table = self.load_fx_table()?;
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("UPDATE transactions SET base_rate = 1.0 WHERE currency = ?", params![base])?;
+
+        let foreign: Vec<(i64, String, String)> = {
+            let mut stmt = tx.prepare("SELECT id, date, currency FROM transactions WHERE currency != ?")?;
+            let rows = stmt.query_map(params![base], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+            rows.collect::<Result<_>>()?
+        };
+
+        let mut missing = BTreeMap::new();
+        for (id, date, currency) in foreign {
+            let rate = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| table.rate(&currency, base, date));
+            if rate.is_none() {
+                *missing.entry(currency).or_insert(0) += 1;
+            }
+            tx.execute("UPDATE transactions SET base_rate = ? WHERE id = ?", params![rate, id])?;
+        }
+        tx.commit()?;
+        Ok(missing)
+    }
+}
@@ -0,0 +1,91 @@
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use chrono::Utc;
+
+use super::Database;
+use crate::classifier::ClassificationResult;
+use crate::history::HistoryEntry;
+
+/// A transaction's current merchant, category, confidence and source.
+pub(super) fn classification(conn: &Connection, id: i64) -> Result<Option<ClassificationResult>> {
+    conn.query_row(
+        "SELECT merchant_name, category, confidence, source FROM transactions WHERE id = ?",
+        params![id],
+        |row| {
+            Ok(ClassificationResult {
+                merchant: row.get(0)?,
+                category: row.get(1)?,
+                confidence: row.get(2)?,
+                source: row.get(3)?,
+            })
+        },
+    )
+    .optional()
+}
+
+pub(super) fn record_change(
+    conn: &Connection,
+    id: i64,
+    before: &ClassificationResult,
+    after: &ClassificationResult,
+    changed_by: &str,
+    note: Option<&str>,
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO transaction_history (
+            tx_id, changed_at, changed_by,
+            before_merchant, before_category, before_confidence, before_source,
+            after_merchant, after_category, after_confidence, after_source, note
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            id,
+            Utc::now().to_rfc3339(),
+            changed_by,
+            before.merchant,
+            before.category,
+            before.confidence,
+            before.source,
+            after.merchant,
+            after.category,
+            after.confidence,
+            after.source,
+            note
+        ],
+    )?;
+    Ok(())
+}
+
+impl Database {
+    /// Recorded changes to a transaction, oldest first.
+    pub fn transaction_history(&self, id: i64) -> Result<Vec<HistoryEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT changed_at, changed_by,
+                    before_merchant, before_category, before_confidence, before_source,
+                    after_merchant, after_category, after_confidence, after_source, note
+             FROM transaction_history WHERE tx_id = ? ORDER BY id ASC"
+        )?;
+        let rows = stmt.query_map(params![id], |row| {
+            Ok(HistoryEntry {
+                changed_at: row.get(0)?,
+                changed_by: row.get(1)?,
+                before: ClassificationResult {
+                    merchant: row.get(2)?,
+                    category: row.get(3)?,
+                    confidence: row.get(4)?,
+                    source: row.get(5)?,
+                },
+                after: ClassificationResult {
+                    merchant: row.get(6)?,
+                    category: row.get(7)?,
+                    confidence: row.get(8)?,
+                    source: row.get(9)?,
+                },
+                note: row.get(10)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    pub fn current_classification(&self, id: i64) -> Result<Option<ClassificationResult>> {
+        classification(&self.conn, id)
+    }
+}
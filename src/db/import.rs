@@ -1,12 +1,12 @@
-use rusqlite::{params, Connection, Result};
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension, Result};
 use std::path::Path;
 use crate::categories::Category;
 use crate::classifier::ClassificationResult;
 use crate::csv_parser::Transaction;
-use chrono::Utc;
+use chrono::{NaiveDate, Utc};
 
 pub struct Database {
-    conn: Connection,
+    pub(super) conn: Connection,
 }
 
 #[derive(Debug, Clone)]
@@ -37,6 +37,76 @@ pub struct FewShotExample {
     pub correct_category: String,
 }
 
+/// What one imported file covered, for `log_import` and `finish_import`.
+#[derive(Debug, Clone, Default)]
+pub struct ImportLogEntry {
+    pub filename: String,
+    pub row_count: usize,
+    pub file_hash: Option<String>,
+    pub period: Option<(NaiveDate, NaiveDate)>,
+    /// The period is the statement's own rather than the first and last booking.
+    pub period_exact: bool,
+    pub account_ids: Vec<i64>,
+}
+
+const TRANSACTION_LINES_VIEW: &str = "SELECT t.id, t.date, t.raw_description,
+        t.amount * COALESCE(t.base_rate, 1.0) AS amount, t.amount AS native_amount, t.currency, t.base_rate,
+        t.original_amount, t.original_currency, t.fx_rate, t.merchant_name, t.category,
+        t.source, t.confidence, t.transaction_id, t.import_batch, t.transfer_of, t.account_id, NULL AS split_id
+    FROM transactions t
+    WHERE NOT EXISTS (SELECT 1 FROM transaction_splits s WHERE s.parent_id = t.id)
+    UNION ALL
+    SELECT t.id, t.date, t.raw_description,
+        s.amount * COALESCE(t.base_rate, 1.0), s.amount, t.currency, t.base_rate,
+        t.original_amount, t.original_currency, t.fx_rate, t.merchant_name, s.category,
+        t.source, t.confidence, t.transaction_id, t.import_batch, t.transfer_of, t.account_id, s.id
+    FROM transaction_splits s
+    JOIN transactions t ON t.id = s.parent_id";
+
+/// Add a column to an existing table unless it is already there, so databases
+/// created by older versions pick up new columns on open.
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = stmt.query_map([], |row| row.get::<_, String>(1))?.collect::<Result<Vec<_>>>()?;
+    if !columns.iter().any(|c| c == column) {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])?;
+    }
+    Ok(())
+}
+
+/// Set a transaction's classification and recompute its calibrated confidence,
+/// recording the change under `changed_by` if there was one.
+fn update_classification(conn: &Connection, id: i64, result: &ClassificationResult, changed_by: &str) -> Result<()> {
+    let before = super::history::classification(conn, id)?;
+    conn.execute(
+        "UPDATE transactions 
+         SET merchant_name = ?, category = ?, confidence = ?, source = ? 
+         WHERE id = ?",
+        params![result.merchant, result.category, result.confidence, result.source, id],
+    )?;
+    conn.execute(
+        &format!("UPDATE transactions SET calibrated_confidence = {} WHERE id = ?", super::calibration::CALIBRATED_CONFIDENCE),
+        params![id],
+    )?;
+    match before {
+        Some(before) if before != *result => super::history::record_change(conn, id, &before, result, changed_by, None),
+        _ => Ok(()),
+    }
+}
+
+/// Create a view, replacing an existing one whose definition has changed.
+fn ensure_view(conn: &Connection, name: &str, select: &str) -> Result<()> {
+    let create = format!("CREATE VIEW {} AS {}", name, select);
+    let existing: Option<String> = conn
+        .query_row("SELECT sql FROM sqlite_master WHERE type = 'view' AND name = ?", params![name], |row| row.get(0))
+        .optional()?;
+    if existing.as_deref() != Some(create.as_str()) {
+        conn.execute(&format!("DROP VIEW IF EXISTS {}", name), [])?;
+        conn.execute(&create, [])?;
+    }
+    Ok(())
+}
+
 impl Database {
     pub fn open(path: &Path) -> Result<Self> {
         let conn = Connection::open(path)?;
@@ -60,6 +130,135 @@ impl Database {
             [],
         )?;
 
+        // Columns added after the first release
+        add_column_if_missing(&conn, "transactions", "original_amount", "REAL")?;
+        add_column_if_missing(&conn, "transactions", "original_currency", "TEXT")?;
+        add_column_if_missing(&conn, "transactions", "fx_rate", "REAL")?;
+        add_column_if_missing(&conn, "transactions", "base_rate", "REAL")?;
+        // The other side of an internal transfer, see `match_transfers`
+        add_column_if_missing(&conn, "transactions", "transfer_of", "INTEGER")?;
+        add_column_if_missing(&conn, "transactions", "account_id", "INTEGER REFERENCES accounts(id)")?;
+        // Balance after the booking, where the export reports one
+        add_column_if_missing(&conn, "transactions", "balance", "REAL")?;
+        // `confidence` corrected by what reviews showed, see `fit_calibration`
+        add_column_if_missing(&conn, "transactions", "calibrated_confidence", "REAL")?;
+        // The export's details column, where transfers name the other account
+        add_column_if_missing(&conn, "transactions", "details", "TEXT")?;
+        // Pairs someone unlinked with `transfers unlink`, never to be paired again
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS rejected_transfers (
+                debit_id INTEGER NOT NULL REFERENCES transactions(id),
+                credit_id INTEGER NOT NULL REFERENCES transactions(id),
+                PRIMARY KEY (debit_id, credit_id)
+            )",
+            [],
+        )?;
+
+        // Bank accounts and cards, identified by the numbers in their exports.
+        // A card invoice can list several holders under one card number.
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS accounts (
+                id INTEGER PRIMARY KEY,
+                kind TEXT NOT NULL,
+                number TEXT NOT NULL,
+                holder TEXT NOT NULL DEFAULT '',
+                iban TEXT,
+                name TEXT,
+                created_at TEXT NOT NULL,
+                UNIQUE (kind, number, holder)
+            )",
+            [],
+        )?;
+
+        // Opening and closing balances from statement headers, see `reconcile_account`
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS statement_balances (
+                account_id INTEGER NOT NULL REFERENCES accounts(id),
+                date TEXT NOT NULL,
+                kind TEXT NOT NULL CHECK (kind IN ('opening', 'closing')),
+                balance REAL NOT NULL,
+                import_batch TEXT,
+                PRIMARY KEY (account_id, date, kind)
+            )",
+            [],
+        )?;
+
+        // Exchange rates imported from ECB/SNB files: one `base` buys `rate` of `quote`
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS fx_rates (
+                date TEXT NOT NULL,
+                base TEXT NOT NULL,
+                quote TEXT NOT NULL,
+                rate REAL NOT NULL,
+                source TEXT NOT NULL,
+                PRIMARY KEY (date, base, quote)
+            )",
+            [],
+        )?;
+
+        // Parts of a transaction split across several categories
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS transaction_splits (
+                id INTEGER PRIMARY KEY,
+                parent_id INTEGER NOT NULL REFERENCES transactions(id),
+                amount REAL NOT NULL,
+                category TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_transaction_splits_parent ON transaction_splits(parent_id)",
+            [],
+        )?;
+
+        // One row per spending line: unsplit transactions as they are, split ones
+        // once per part with the part's amount and category. `amount` is in the base
+        // currency (see `refresh_base_rates`); `native_amount` is in `currency`.
+        // Aggregations read this.
+        ensure_view(&conn, "transaction_lines", TRANSACTION_LINES_VIEW)?;
+
+        // Every change to a transaction's classification after import, see
+        // `transaction_history`. `before_*` of the first entry is what the import
+        // assigned.
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS transaction_history (
+                id INTEGER PRIMARY KEY,
+                tx_id INTEGER NOT NULL REFERENCES transactions(id),
+                changed_at TEXT NOT NULL,
+                changed_by TEXT NOT NULL,
+                before_merchant TEXT NOT NULL,
+                before_category TEXT NOT NULL,
+                before_confidence REAL NOT NULL,
+                before_source TEXT NOT NULL,
+                after_merchant TEXT NOT NULL,
+                after_category TEXT NOT NULL,
+                after_confidence REAL NOT NULL,
+                after_source TEXT NOT NULL,
+                note TEXT
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_transaction_history_tx ON transaction_history(tx_id)",
+            [],
+        )?;
+
+        // How far each source's (and category's) confidence held up in review
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS confidence_calibration (
+                source TEXT NOT NULL,
+                category TEXT NOT NULL DEFAULT '',
+                reviews INTEGER NOT NULL,
+                correct INTEGER NOT NULL,
+                mean_confidence REAL NOT NULL,
+                factor REAL NOT NULL,
+                fitted_at TEXT NOT NULL,
+                PRIMARY KEY (source, category)
+            )",
+            [],
+        )?;
+
         // merchant_cache table
         conn.execute(
             "CREATE TABLE IF NOT EXISTS merchant_cache (
@@ -73,6 +272,8 @@ impl Database {
             )",
             [],
         )?;
+        // The import that asked the LLM for this entry, so a rollback can drop it
+        add_column_if_missing(&conn, "merchant_cache", "import_batch", "TEXT")?;
 
         // import_log table
         conn.execute(
@@ -84,6 +285,33 @@ impl Database {
             )",
             [],
         )?;
+        // What each import covered, see `import_status`
+        add_column_if_missing(&conn, "import_log", "file_hash", "TEXT")?;
+        add_column_if_missing(&conn, "import_log", "period_start", "TEXT")?;
+        add_column_if_missing(&conn, "import_log", "period_end", "TEXT")?;
+        add_column_if_missing(&conn, "import_log", "period_exact", "INTEGER NOT NULL DEFAULT 0")?;
+        add_column_if_missing(&conn, "import_log", "rolled_back_at", "TEXT")?;
+        // Imports are logged when they start and checkpointed as they go, see `checkpoint_import`
+        add_column_if_missing(&conn, "import_log", "status", "TEXT NOT NULL DEFAULT 'complete'")?;
+        add_column_if_missing(&conn, "import_log", "rows_done", "INTEGER NOT NULL DEFAULT 0")?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS import_rollbacks (
+                id INTEGER PRIMARY KEY,
+                import_batch TEXT NOT NULL,
+                transactions_removed INTEGER NOT NULL,
+                cache_entries_removed INTEGER NOT NULL,
+                rolled_back_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS import_log_accounts (
+                import_id INTEGER NOT NULL REFERENCES import_log(id),
+                account_id INTEGER NOT NULL REFERENCES accounts(id),
+                PRIMARY KEY (import_id, account_id)
+            )",
+            [],
+        )?;
 
         // few_shot_examples table
         conn.execute(
@@ -108,6 +336,72 @@ impl Database {
             [],
         )?;
 
+        // budgets table
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS budgets (
+                category TEXT PRIMARY KEY REFERENCES categories(name),
+                monthly_limit REAL,
+                annual_limit REAL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        // recurring_payments table (rebuilt by each detection run)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS recurring_payments (
+                merchant_key TEXT PRIMARY KEY,
+                merchant_name TEXT NOT NULL,
+                category TEXT NOT NULL,
+                cadence TEXT NOT NULL,
+                typical_amount REAL NOT NULL,
+                previous_amount REAL,
+                occurrences INTEGER NOT NULL,
+                first_date TEXT NOT NULL,
+                last_date TEXT NOT NULL,
+                next_expected TEXT NOT NULL,
+                missed_count INTEGER NOT NULL,
+                updated_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        // Chat conversations, persisted by the web server's session store
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS conversations (
+                id TEXT PRIMARY KEY,
+                title TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS messages (
+                id INTEGER PRIMARY KEY,
+                conversation_id TEXT NOT NULL REFERENCES conversations(id),
+                seq INTEGER NOT NULL,
+                role TEXT NOT NULL,
+                content TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                UNIQUE (conversation_id, seq)
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS chart_artifacts (
+                id INTEGER PRIMARY KEY,
+                conversation_id TEXT NOT NULL REFERENCES conversations(id),
+                message_seq INTEGER NOT NULL,
+                chart TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
         // Seed categories if empty
         {
             let mut stmt = conn.prepare("SELECT COUNT(*) FROM categories")?;
@@ -126,6 +420,13 @@ impl Database {
         Ok(Database { conn })
     }
 
+    /// Open an existing database without creating or migrating anything, for
+    /// commands that must leave it untouched.
+    pub fn open_read_only(path: &Path) -> Result<Self> {
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX)?;
+        Ok(Database { conn })
+    }
+
     pub fn insert_transaction(&self, tx: &Transaction, classification: &ClassificationResult, import_batch: Option<&str>) -> Result<bool> {
         let amount = match (tx.debit, tx.credit) {
             (Some(d), _) => -d,
@@ -135,23 +436,33 @@ impl Database {
 
         let created_at = Utc::now().to_rfc3339();
         let date = tx.trade_date.to_string();
+        let account_id = match &tx.account {
+            Some(account) => Some(self.upsert_account(account)?),
+            None => None,
+        };
 
         let res = self.conn.execute(
             "INSERT OR IGNORE INTO transactions (
-                date, raw_description, amount, currency, merchant_name, 
-                category, source, confidence, transaction_id, import_batch, created_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                date, raw_description, amount, currency, original_amount, original_currency, fx_rate,
+                merchant_name, category, source, confidence, transaction_id, import_batch, account_id, balance, details, created_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
             params![
                 date,
                 tx.description,
                 amount,
                 tx.currency,
+                tx.original_amount,
+                tx.original_currency,
+                tx.fx_rate,
                 classification.merchant,
                 classification.category,
                 classification.source,
                 classification.confidence,
                 tx.transaction_id,
                 import_batch,
+                account_id,
+                tx.balance,
+                (!tx.details.is_empty()).then_some(tx.details.as_str()),
                 created_at
             ],
         );
@@ -164,7 +475,7 @@ impl Database {
 
     pub fn transaction_exists(&self, transaction_id: &str) -> Result<bool> {
         let mut stmt = self.conn.prepare("SELECT 1 FROM transactions WHERE transaction_id = ?")?;
-        Ok(stmt.exists(params![transaction_id])?)
+        stmt.exists(params![transaction_id])
     }
 
     pub fn cache_lookup(&self, raw_key: &str) -> Result<Option<ClassificationResult>> {
@@ -191,12 +502,14 @@ impl Database {
         }
     }
 
-    pub fn cache_insert(&self, raw_key: &str, result: &ClassificationResult) -> Result<()> {
+    /// Store a classification for `raw_key`. `import_batch` is the import that
+    /// produced it, if any, so `rollback_import` can revert it.
+    pub fn cache_insert(&self, raw_key: &str, result: &ClassificationResult, import_batch: Option<&str>) -> Result<()> {
         let now = Utc::now().to_rfc3339();
         self.conn.execute(
             "INSERT OR REPLACE INTO merchant_cache (
-                raw_key, merchant_name, category, confidence, source, created_at, updated_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?)",
+                raw_key, merchant_name, category, confidence, source, created_at, updated_at, import_batch
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
             params![
                 raw_key,
                 result.merchant,
@@ -204,19 +517,35 @@ impl Database {
                 result.confidence,
                 result.source,
                 now,
-                now
+                now,
+                import_batch
             ],
         )?;
         Ok(())
     }
 
-    pub fn log_import(&self, filename: &str, row_count: usize) -> Result<()> {
+    /// Record an import with the period and accounts it covered. Returns its id.
+    pub fn log_import(&self, entry: &ImportLogEntry) -> Result<i64> {
         let now = Utc::now().to_rfc3339();
-        self.conn.execute(
-            "INSERT INTO import_log (filename, row_count, imported_at) VALUES (?, ?, ?)",
-            params![filename, row_count as i64, now],
+        let (start, end) = match entry.period {
+            Some((start, end)) => (Some(start.to_string()), Some(end.to_string())),
+            None => (None, None),
+        };
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT INTO import_log (filename, row_count, imported_at, file_hash, period_start, period_end, period_exact)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+            params![entry.filename, entry.row_count as i64, now, entry.file_hash, start, end, entry.period_exact],
         )?;
-        Ok(())
+        let id = tx.last_insert_rowid();
+        for account_id in &entry.account_ids {
+            tx.execute(
+                "INSERT OR IGNORE INTO import_log_accounts (import_id, account_id) VALUES (?, ?)",
+                params![id, account_id],
+            )?;
+        }
+        tx.commit()?;
+        Ok(id)
     }
 
     pub fn get_flagged_transactions(
@@ -226,11 +555,12 @@ impl Database {
         since: Option<&str>,
         until: Option<&str>,
         merchant: Option<&str>,
+        account: Option<&str>,
     ) -> Result<Vec<StoredTransaction>> {
         let mut query = String::from(
             "SELECT id, date, raw_description, amount, currency, merchant_name, category, source, confidence, transaction_id 
              FROM transactions 
-             WHERE (confidence < ? OR category = 'Other' OR category = 'Uncategorised')"
+             WHERE (COALESCE(calibrated_confidence, confidence) < ? OR category = 'Other' OR category = 'Uncategorised')"
         );
         let mut params_vec: Vec<rusqlite::types::Value> = vec![rusqlite::types::Value::Real(threshold)];
 
@@ -251,6 +581,12 @@ impl Database {
             params_vec.push(rusqlite::types::Value::Text(format!("%{}%", m)));
         }
 
+        if let Some(a) = account {
+            query.push_str(" AND ");
+            query.push_str(super::accounts::ACCOUNT_FILTER);
+            params_vec.push(rusqlite::types::Value::Text(a.to_string()));
+        }
+
         query.push_str(" ORDER BY date ASC");
 
         let mut stmt = self.conn.prepare(&query)?;
@@ -276,6 +612,8 @@ impl Database {
         Ok(results)
     }
 
+    /// Overwrite a transaction's classification, recording the change in
+    /// `transaction_history` under `changed_by`.
     pub fn update_transaction(
         &self,
         id: i64,
@@ -283,14 +621,45 @@ impl Database {
         category: &str,
         confidence: f64,
         source: &str,
+        changed_by: &str,
     ) -> Result<()> {
-        self.conn.execute(
-            "UPDATE transactions 
-             SET merchant_name = ?, category = ?, confidence = ?, source = ? 
-             WHERE id = ?",
-            params![merchant_name, category, confidence, source, id],
-        )?;
-        Ok(())
+        let result = ClassificationResult {
+            merchant: merchant_name.to_string(),
+            category: category.to_string(),
+            confidence,
+            source: source.to_string(),
+        };
+        let tx = self.conn.unchecked_transaction()?;
+        update_classification(&tx, id, &result, changed_by)?;
+        tx.commit()
+    }
+
+    /// Record a human classification for a merchant group: pin it in the cache,
+    /// keep it as a few-shot example and update every transaction in the group.
+    /// `changed_by` names the command or tool in the transactions' history.
+    pub fn apply_manual_classification(
+        &self,
+        merchant_key: &str,
+        raw_description: &str,
+        merchant_name: &str,
+        category: &str,
+        transaction_ids: &[i64],
+        changed_by: &str,
+    ) -> Result<()> {
+        let result = ClassificationResult {
+            merchant: merchant_name.to_string(),
+            category: category.to_string(),
+            confidence: 1.0,
+            source: "manual".to_string(),
+        };
+        // All or nothing, so a failure cannot leave the group half recategorised
+        let tx = self.conn.unchecked_transaction()?;
+        self.cache_insert(merchant_key, &result, None)?;
+        self.insert_few_shot_example(merchant_key, raw_description, merchant_name, category)?;
+        for id in transaction_ids {
+            update_classification(&tx, *id, &result, changed_by)?;
+        }
+        tx.commit()
     }
 
     pub fn insert_few_shot_example(
@@ -1,8 +1,21 @@
+pub mod accounts;
+pub mod balances;
+pub mod budgets;
+pub mod calibration;
+pub mod checkpoint;
+pub mod coverage;
+pub mod fx;
+pub mod history;
 pub mod import;
+pub mod recurring;
+pub mod rollback;
+pub mod splits;
+pub mod transfers;
 
 use sqlx::{Row, SqlitePool};
 
-pub use import::{CategoryInfo, Database, FewShotExample, StoredTransaction};
+pub use budgets::{validate_budget_amount, Budget, BudgetPeriod};
+pub use import::{CategoryInfo, Database, FewShotExample, ImportLogEntry, StoredTransaction};
 
 #[derive(Debug, Clone)]
 pub struct CategoryCount {
@@ -16,12 +29,24 @@ pub struct DataSummary {
     pub max_date: Option<String>,
     pub total_transactions: i64,
     pub categories: Vec<CategoryCount>,
+    pub accounts: Vec<accounts::Account>,
 }
 
 pub async fn connect_pool(database_url: &str) -> Result<SqlitePool, sqlx::Error> {
     SqlitePool::connect(database_url).await
 }
 
+pub async fn load_categories(pool: &SqlitePool) -> Result<Vec<CategoryInfo>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (String, String)>("SELECT name, description FROM categories ORDER BY name ASC")
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(name, description)| CategoryInfo { name, description })
+        .collect())
+}
+
 pub async fn load_data_summary(pool: &SqlitePool) -> Result<DataSummary, sqlx::Error> {
     let row = sqlx::query(
         "SELECT MIN(date) as min_date, MAX(date) as max_date, COUNT(*) as total FROM transactions",
@@ -34,7 +59,7 @@ pub async fn load_data_summary(pool: &SqlitePool) -> Result<DataSummary, sqlx::E
     let total: i64 = row.get("total");
 
     let cat_rows = sqlx::query(
-        "SELECT category, COUNT(*) as count FROM transactions GROUP BY category ORDER BY count DESC",
+        "SELECT category, COUNT(*) as count FROM transaction_lines GROUP BY category ORDER BY count DESC",
     )
     .fetch_all(pool)
     .await?;
@@ -47,10 +72,32 @@ pub async fn load_data_summary(pool: &SqlitePool) -> Result<DataSummary, sqlx::E
         })
         .collect();
 
+    let account_rows = sqlx::query(
+        "SELECT a.id, a.kind, a.number, a.iban, NULLIF(a.holder, '') as holder, a.name, COUNT(t.id) as count
+         FROM accounts a LEFT JOIN transactions t ON t.account_id = a.id
+         GROUP BY a.id ORDER BY a.kind, a.number, a.holder",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let accounts = account_rows
+        .into_iter()
+        .map(|r| accounts::Account {
+            id: r.get("id"),
+            kind: r.get("kind"),
+            number: r.get("number"),
+            iban: r.get("iban"),
+            holder: r.get("holder"),
+            name: r.get("name"),
+            transactions: r.get("count"),
+        })
+        .collect();
+
     Ok(DataSummary {
         min_date,
         max_date,
         total_transactions: total,
         categories,
+        accounts,
     })
 }
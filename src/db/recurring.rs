@@ -0,0 +1,112 @@
+use rusqlite::{params, Result};
+use chrono::{NaiveDate, Utc};
+
+use super::Database;
+use crate::recurring::{detect_all, sort_by_annual_cost, ChargeRow, RecurringPayment};
+
+impl Database {
+    /// Spending transactions (excluding transfers) in date order, for recurring-payment detection.
+    pub fn get_recurring_candidates(&self) -> Result<Vec<ChargeRow>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT raw_description, merchant_name, category, date, -amount
+             FROM transactions
+             WHERE amount < 0 AND category != 'Transfers'
+             ORDER BY date ASC"
+        )?;
+        let rows = stmt.query_map([], |row| {
+            let date: String = row.get(3)?;
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, date, row.get(4)?))
+        })?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            let (raw_description, merchant_name, category, date, amount): (String, String, String, String, f64) = row?;
+            // Skip rows with unparseable dates rather than failing the whole scan
+            if let Ok(date) = NaiveDate::parse_from_str(&date, "%Y-%m-%d") {
+                results.push(ChargeRow { raw_description, merchant_name, category, date, amount });
+            }
+        }
+        Ok(results)
+    }
+
+    pub fn latest_transaction_date(&self) -> Result<Option<NaiveDate>> {
+        let latest: Option<String> = self.conn.query_row("SELECT MAX(date) FROM transactions", [], |row| row.get(0))?;
+        Ok(latest.and_then(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok()))
+    }
+
+    /// Run detection over every transaction, up to the latest one, and store
+    /// the result. Returns the number of recurring payments found.
+    pub fn refresh_recurring_payments(&self) -> Result<usize> {
+        let payments = match self.latest_transaction_date()? {
+            Some(as_of) => detect_all(&self.get_recurring_candidates()?, as_of),
+            None => Vec::new(),
+        };
+        self.save_recurring_payments(&payments)?;
+        Ok(payments.len())
+    }
+
+    /// The recurring payments found by the last detection run, most expensive first.
+    pub fn recurring_payments(&self) -> Result<Vec<RecurringPayment>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT merchant_key, merchant_name, category, cadence, typical_amount, previous_amount,
+                    occurrences, first_date, last_date, next_expected, missed_count
+             FROM recurring_payments"
+        )?;
+        let date = |d: String| NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok();
+        let rows = stmt.query_map([], |row| {
+            let cadence: String = row.get(3)?;
+            // Rows that no longer parse are skipped rather than failing the whole list
+            let (Ok(cadence), Some(first_date), Some(last_date), Some(next_expected)) =
+                (cadence.parse(), date(row.get(7)?), date(row.get(8)?), date(row.get(9)?))
+            else {
+                return Ok(None);
+            };
+            Ok(Some(RecurringPayment {
+                merchant_key: row.get(0)?,
+                merchant_name: row.get(1)?,
+                category: row.get(2)?,
+                cadence,
+                typical_amount: row.get(4)?,
+                previous_amount: row.get(5)?,
+                occurrences: row.get::<_, i64>(6)? as usize,
+                first_date,
+                last_date,
+                next_expected,
+                missed: row.get::<_, i64>(10)? as usize,
+            }))
+        })?;
+        let mut payments: Vec<RecurringPayment> = rows.filter_map(|row| row.transpose()).collect::<Result<_>>()?;
+        sort_by_annual_cost(&mut payments);
+        Ok(payments)
+    }
+
+    /// Replace the stored recurring payments with a fresh detection run.
+    pub fn save_recurring_payments(&self, payments: &[RecurringPayment]) -> Result<()> {
+        let now = Utc::now().to_rfc3339();
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM recurring_payments", [])?;
+        for p in payments {
+            tx.execute(
+                "INSERT INTO recurring_payments (
+                    merchant_key, merchant_name, category, cadence, typical_amount, previous_amount,
+                    occurrences, first_date, last_date, next_expected, missed_count, updated_at
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                params![
+                    p.merchant_key,
+                    p.merchant_name,
+                    p.category,
+                    p.cadence.to_string(),
+                    p.typical_amount,
+                    p.previous_amount,
+                    p.occurrences as i64,
+                    p.first_date.to_string(),
+                    p.last_date.to_string(),
+                    p.next_expected.to_string(),
+                    p.missed as i64,
+                    now
+                ],
+            )?;
+        }
+        tx.commit()
+    }
+}
@@ -0,0 +1,112 @@
+use rusqlite::{params, Connection, Result};
+use chrono::Utc;
+
+use super::transfers::unpair;
+use super::Database;
+
+/// What rolling back an import batch removes, or removed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RollbackSummary {
+    pub batch: String,
+    pub transactions: usize,
+    pub first_date: Option<String>,
+    pub last_date: Option<String>,
+    pub uncategorised: usize,
+    pub splits: usize,
+    /// Transactions in other imports that were paired with one of these as a
+    /// transfer; they are unlinked and get back the category they had before.
+    pub transfers_unlinked: usize,
+    pub statement_balances: usize,
+    /// LLM results this import added to the merchant cache.
+    pub cache_entries: usize,
+}
+
+fn summarise(conn: &Connection, batch: &str) -> Result<RollbackSummary> {
+    let count = |sql: &str| -> Result<usize> {
+        conn.query_row(sql, params![batch], |row| row.get::<_, i64>(0)).map(|n| n as usize)
+    };
+    let (transactions, first_date, last_date, uncategorised) = conn.query_row(
+        "SELECT COUNT(*), MIN(date), MAX(date), COUNT(CASE WHEN category = 'Uncategorised' THEN 1 END)
+         FROM transactions WHERE import_batch = ?",
+        params![batch],
+        |row| Ok((row.get::<_, i64>(0)? as usize, row.get(1)?, row.get(2)?, row.get::<_, i64>(3)? as usize)),
+    )?;
+    Ok(RollbackSummary {
+        batch: batch.to_string(),
+        transactions,
+        first_date,
+        last_date,
+        uncategorised,
+        splits: count(
+            "SELECT COUNT(*) FROM transaction_splits WHERE parent_id IN (SELECT id FROM transactions WHERE import_batch = ?1)",
+        )?,
+        transfers_unlinked: count(
+            "SELECT COUNT(*) FROM transactions WHERE import_batch IS NOT ?1
+               AND transfer_of IN (SELECT id FROM transactions WHERE import_batch = ?1)",
+        )?,
+        statement_balances: count("SELECT COUNT(*) FROM statement_balances WHERE import_batch = ?")?,
+        cache_entries: count("SELECT COUNT(*) FROM merchant_cache WHERE import_batch = ? AND source = 'llm'")?,
+    })
+}
+
+impl Database {
+    /// Preview `rollback_import` without changing anything.
+    pub fn plan_rollback(&self, batch: &str) -> Result<RollbackSummary> {
+        summarise(&self.conn, batch)
+    }
+
+    /// Remove every transaction imported from `batch` (the file name), with
+    /// its splits, history and statement balances, and optionally the merchant cache
+    /// entries the import created. The import stays in `import_log`, marked as
+    /// rolled back, and the rollback is recorded in `import_rollbacks`.
+    pub fn rollback_import(&self, batch: &str, revert_cache: bool) -> Result<RollbackSummary> {
+        let tx = self.conn.unchecked_transaction()?;
+        let mut summary = summarise(&tx, batch)?;
+
+        let counterparts: Vec<(i64, i64)> = {
+            let mut stmt = tx.prepare(
+                "SELECT id, transfer_of FROM transactions WHERE import_batch IS NOT ?1
+                   AND transfer_of IN (SELECT id FROM transactions WHERE import_batch = ?1)",
+            )?;
+            let rows = stmt.query_map(params![batch], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<Result<_>>()?
+        };
+        for (id, other) in counterparts {
+            unpair(&tx, id, "rollback", &format!("unpaired from #{} when {} was rolled back", other, batch))?;
+        }
+        tx.execute(
+            "DELETE FROM transaction_splits WHERE parent_id IN (SELECT id FROM transactions WHERE import_batch = ?)",
+            params![batch],
+        )?;
+        // Row ids are reused once deleted, so the history has to go with them
+        tx.execute(
+            "DELETE FROM transaction_history WHERE tx_id IN (SELECT id FROM transactions WHERE import_batch = ?)",
+            params![batch],
+        )?;
+        tx.execute(
+            "DELETE FROM rejected_transfers WHERE debit_id IN (SELECT id FROM transactions WHERE import_batch = ?1)
+                OR credit_id IN (SELECT id FROM transactions WHERE import_batch = ?1)",
+            params![batch],
+        )?;
+        tx.execute("DELETE FROM transactions WHERE import_batch = ?", params![batch])?;
+        tx.execute("DELETE FROM statement_balances WHERE import_batch = ?", params![batch])?;
+        if revert_cache {
+            tx.execute("DELETE FROM merchant_cache WHERE import_batch = ? AND source = 'llm'", params![batch])?;
+        } else {
+            summary.cache_entries = 0;
+        }
+
+        let now = Utc::now().to_rfc3339();
+        tx.execute(
+            "UPDATE import_log SET rolled_back_at = ? WHERE filename = ? AND rolled_back_at IS NULL",
+            params![now, batch],
+        )?;
+        tx.execute(
+            "INSERT INTO import_rollbacks (import_batch, transactions_removed, cache_entries_removed, rolled_back_at)
+             VALUES (?, ?, ?, ?)",
+            params![batch, summary.transactions as i64, summary.cache_entries as i64, now],
+        )?;
+        tx.commit()?;
+        Ok(summary)
+    }
+}
@@ -0,0 +1,84 @@
+use rusqlite::{params, OptionalExtension, Result, Row};
+use chrono::Utc;
+
+use super::history::{classification, record_change};
+use super::{Database, StoredTransaction};
+use crate::splits::Split;
+
+const TRANSACTION_COLUMNS: &str =
+    "id, date, raw_description, amount, currency, merchant_name, category, source, confidence, transaction_id";
+
+fn stored_transaction(row: &Row) -> Result<StoredTransaction> {
+    Ok(StoredTransaction {
+        id: row.get(0)?,
+        date: row.get(1)?,
+        raw_description: row.get(2)?,
+        amount: row.get(3)?,
+        currency: row.get(4)?,
+        merchant_name: row.get(5)?,
+        category: row.get(6)?,
+        _source: row.get(7)?,
+        confidence: row.get(8)?,
+        _transaction_id: row.get(9)?,
+    })
+}
+
+impl Database {
+    pub fn get_transaction(&self, id: i64) -> Result<Option<StoredTransaction>> {
+        self.conn
+            .query_row(
+                &format!("SELECT {} FROM transactions WHERE id = ?", TRANSACTION_COLUMNS),
+                params![id],
+                stored_transaction,
+            )
+            .optional()
+    }
+
+    /// Most recent transactions whose merchant or description contains `search`.
+    pub fn find_transactions(&self, search: &str, limit: usize) -> Result<Vec<StoredTransaction>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM transactions
+             WHERE LOWER(merchant_name) LIKE '%' || LOWER(?1) || '%' OR LOWER(raw_description) LIKE '%' || LOWER(?1) || '%'
+             ORDER BY date DESC, id DESC
+             LIMIT ?2",
+            TRANSACTION_COLUMNS
+        ))?;
+        let rows = stmt.query_map(params![search, limit as i64], stored_transaction)?;
+        rows.collect()
+    }
+
+    pub fn get_splits(&self, transaction_id: i64) -> Result<Vec<Split>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT category, amount FROM transaction_splits WHERE parent_id = ? ORDER BY id ASC"
+        )?;
+        let rows = stmt.query_map(params![transaction_id], |row| {
+            Ok(Split { category: row.get(0)?, amount: row.get(1)? })
+        })?;
+        rows.collect()
+    }
+
+    /// Replace the splits of a transaction. An empty list removes them, so the
+    /// transaction counts under its own category again. The change is recorded
+    /// in the transaction's history under `changed_by`.
+    pub fn set_splits(&self, transaction_id: i64, splits: &[Split], changed_by: &str) -> Result<()> {
+        let now = Utc::now().to_rfc3339();
+        let tx = self.conn.unchecked_transaction()?;
+        let had_splits = tx.execute("DELETE FROM transaction_splits WHERE parent_id = ?", params![transaction_id])? > 0;
+        for split in splits {
+            tx.execute(
+                "INSERT INTO transaction_splits (parent_id, amount, category, created_at) VALUES (?, ?, ?, ?)",
+                params![transaction_id, split.amount, split.category, now],
+            )?;
+        }
+        let note = if splits.is_empty() {
+            had_splits.then(|| "split removed".to_string())
+        } else {
+            let parts: Vec<String> = splits.iter().map(|s| format!("{} {:.2}", s.category, s.amount.abs())).collect();
+            Some(format!("split into {}", parts.join(", ")))
+        };
+        if let (Some(note), Some(current)) = (note, classification(&tx, transaction_id)?) {
+            record_change(&tx, transaction_id, &current, &current, changed_by, Some(&note))?;
+        }
+        tx.commit()
+    }
+}
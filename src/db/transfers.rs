@@ -0,0 +1,152 @@
+use std::collections::HashSet;
+
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use chrono::NaiveDate;
+
+use super::history::{classification, record_change};
+use super::Database;
+use crate::classifier::ClassificationResult;
+use crate::transfers::{has_transfer_signal, match_transfers, TransferCandidate};
+
+/// Both sides of a linked transfer.
+#[derive(Debug, Clone)]
+pub struct TransferPair {
+    pub date: String,
+    pub amount: f64,
+    pub currency: String,
+    pub from_id: i64,
+    pub from_description: String,
+    pub to_id: i64,
+    pub to_description: String,
+}
+
+/// Clear `id`'s transfer link and give it back the category it had before
+/// `match_transfers` paired it, unless someone has changed it since.
+pub(super) fn unpair(conn: &Connection, id: i64, changed_by: &str, note: &str) -> Result<()> {
+    let Some(before) = classification(conn, id)? else { return Ok(()) };
+    let paired_from: Option<String> = conn
+        .query_row(
+            "SELECT before_category FROM transaction_history WHERE tx_id = ? AND changed_by = 'transfers' ORDER BY id DESC LIMIT 1",
+            params![id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let category = match paired_from {
+        Some(category) if before.category == "Transfers" => category,
+        _ => before.category.clone(),
+    };
+    conn.execute("UPDATE transactions SET transfer_of = NULL, category = ? WHERE id = ?", params![category, id])?;
+    conn.execute(
+        &format!("UPDATE transactions SET calibrated_confidence = {} WHERE id = ?", super::calibration::CALIBRATED_CONFIDENCE),
+        params![id],
+    )?;
+    let after = ClassificationResult { category, ..before.clone() };
+    record_change(conn, id, &before, &after, changed_by, Some(note))
+}
+
+impl Database {
+    /// Link unmatched transactions that look like the two sides of a transfer
+    /// between own accounts, and move both into Transfers. Besides amount and
+    /// date, one side has to say it is a transfer (see `has_transfer_signal`).
+    /// Manual classifications and unlinked pairs are left alone. Transactions
+    /// without a known account count the file they were imported from as
+    /// their account. Returns the number of new pairs.
+    pub fn match_transfers(&self, window_days: i64) -> Result<usize> {
+        let accounts: Vec<(i64, Vec<String>)> = self
+            .list_accounts()?
+            .into_iter()
+            .map(|a| (a.id, std::iter::once(a.number).chain(a.iban).collect()))
+            .collect();
+        let candidates: Vec<TransferCandidate> = {
+            let mut stmt = self.conn.prepare(
+                "SELECT id, date, amount, currency, COALESCE('account ' || account_id, import_batch, ''), account_id, category,
+                        raw_description || ' ' || COALESCE(details, '')
+                 FROM transactions t
+                 WHERE transfer_of IS NULL AND source != 'manual'
+                   AND NOT EXISTS (SELECT 1 FROM transaction_splits s WHERE s.parent_id = t.id)"
+            )?;
+            let rows = stmt.query_map([], |row| {
+                let date: String = row.get(1)?;
+                Ok((row.get(0)?, date, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?, row.get(6)?, row.get(7)?))
+            })?;
+            let mut candidates = Vec::new();
+            for row in rows {
+                let (id, date, amount, currency, account, account_id, category, text): (i64, String, f64, String, String, Option<i64>, String, String) = row?;
+                let Ok(date) = NaiveDate::parse_from_str(&date, "%Y-%m-%d") else { continue };
+                let other_accounts: Vec<&str> = accounts
+                    .iter()
+                    .filter(|(a, _)| Some(*a) != account_id)
+                    .flat_map(|(_, numbers)| numbers.iter().map(String::as_str))
+                    .collect();
+                let signal = has_transfer_signal(&category, &text, &other_accounts);
+                candidates.push(TransferCandidate { id, date, amount, currency, account, signal });
+            }
+            candidates
+        };
+        let rejected: HashSet<(i64, i64)> = {
+            let mut stmt = self.conn.prepare("SELECT debit_id, credit_id FROM rejected_transfers")?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<Result<_>>()?
+        };
+
+        let pairs = match_transfers(&candidates, window_days, &rejected);
+        let tx = self.conn.unchecked_transaction()?;
+        for (debit, credit) in &pairs {
+            for (id, other) in [(debit, credit), (credit, debit)] {
+                let before = classification(&tx, *id)?;
+                tx.execute(
+                    "UPDATE transactions SET transfer_of = ?, category = 'Transfers' WHERE id = ?",
+                    params![other, id],
+                )?;
+                if let Some(before) = before {
+                    let after = ClassificationResult { category: "Transfers".to_string(), ..before.clone() };
+                    record_change(&tx, *id, &before, &after, "transfers", Some(&format!("paired with #{}", other)))?;
+                }
+            }
+        }
+        tx.commit()?;
+        Ok(pairs.len())
+    }
+
+    /// Undo a wrong pairing: unlink `id` from the other side of its transfer,
+    /// put both back in the categories they had before, and keep the pair from
+    /// being matched again. Returns the other side's id, or `None` when `id`
+    /// is not linked.
+    pub fn unlink_transfer(&self, id: i64) -> Result<Option<i64>> {
+        let linked: Option<(Option<i64>, f64)> = self
+            .conn
+            .query_row("SELECT transfer_of, amount FROM transactions WHERE id = ?", params![id], |row| Ok((row.get(0)?, row.get(1)?)))
+            .optional()?;
+        let Some((Some(other), amount)) = linked else { return Ok(None) };
+        let (debit, credit) = if amount < 0.0 { (id, other) } else { (other, id) };
+
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("INSERT OR IGNORE INTO rejected_transfers (debit_id, credit_id) VALUES (?, ?)", params![debit, credit])?;
+        unpair(&tx, id, "transfers unlink", &format!("unpaired from #{}", other))?;
+        unpair(&tx, other, "transfers unlink", &format!("unpaired from #{}", id))?;
+        tx.commit()?;
+        Ok(Some(other))
+    }
+
+    pub fn list_transfers(&self) -> Result<Vec<TransferPair>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT d.date, -d.amount, d.currency, d.id, d.raw_description, c.id, c.raw_description
+             FROM transactions d
+             JOIN transactions c ON c.id = d.transfer_of
+             WHERE d.amount < 0
+             ORDER BY d.date ASC, d.id ASC"
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(TransferPair {
+                date: row.get(0)?,
+                amount: row.get(1)?,
+                currency: row.get(2)?,
+                from_id: row.get(3)?,
+                from_description: row.get(4)?,
+                to_id: row.get(5)?,
+                to_description: row.get(6)?,
+            })
+        })?;
+        rows.collect()
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::classifier::ClassificationResult;
+
+/// Upper bounds of the confidence bands used for calibration; the last band
+/// includes 1.0.
+const CALIBRATION_BANDS: [f64; 5] = [0.5, 0.7, 0.8, 0.9, 1.0];
+
+/// Expected merchant and category of one transaction.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct Label {
+    pub merchant: String,
+    pub category: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct LabelsFile {
+    labels: HashMap<String, Label>,
+}
+
+/// Ground-truth labels keyed by transaction id, as in `data/ground-truth.toml`.
+pub fn load_labels(path: &Path) -> Result<HashMap<String, Label>, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read labels file {}: {}", path.display(), e))?;
+    let file: LabelsFile = toml::from_str(&text).map_err(|e| format!("invalid labels file {}: {}", path.display(), e))?;
+    Ok(file.labels)
+}
+
+/// What the pipeline said about one labelled transaction.
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    pub transaction_id: String,
+    pub description: String,
+    pub expected: Label,
+    pub predicted: ClassificationResult,
+}
+
+impl Outcome {
+    pub fn correct(&self) -> bool {
+        self.predicted.category == self.expected.category
+    }
+
+    /// Merchant names match when one contains the other, ignoring case and
+    /// punctuation, so "SBB" matches "SBB Mobile".
+    pub fn merchant_matches(&self) -> bool {
+        let normalise = |s: &str| s.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect::<String>();
+        let (expected, predicted) = (normalise(&self.expected.merchant), normalise(&self.predicted.merchant));
+        !expected.is_empty() && !predicted.is_empty() && (expected.contains(&predicted) || predicted.contains(&expected))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CategoryScore {
+    /// Transactions labelled with the category.
+    pub expected: usize,
+    /// Transactions the pipeline put in the category.
+    pub predicted: usize,
+    pub correct: usize,
+}
+
+impl CategoryScore {
+    pub fn precision(&self) -> Option<f64> {
+        (self.predicted > 0).then(|| self.correct as f64 / self.predicted as f64)
+    }
+
+    pub fn recall(&self) -> Option<f64> {
+        (self.expected > 0).then(|| self.correct as f64 / self.expected as f64)
+    }
+}
+
+/// Transactions whose confidence fell in `[from, to)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalibrationBand {
+    pub from: f64,
+    pub to: f64,
+    pub count: usize,
+    pub mean_confidence: f64,
+    pub accuracy: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Evaluation {
+    pub outcomes: Vec<Outcome>,
+    pub threshold: f64,
+    pub per_category: BTreeMap<String, CategoryScore>,
+    /// (expected, predicted) to count.
+    pub confusion: BTreeMap<(String, String), usize>,
+    pub calibration: Vec<CalibrationBand>,
+}
+
+impl Evaluation {
+    /// Score `outcomes`; results below `threshold` confidence count as flagged
+    /// for review, the rest as auto-accepted.
+    pub fn new(outcomes: Vec<Outcome>, threshold: f64) -> Self {
+        let mut per_category: BTreeMap<String, CategoryScore> = BTreeMap::new();
+        let mut confusion = BTreeMap::new();
+        for o in &outcomes {
+            per_category.entry(o.expected.category.clone()).or_default().expected += 1;
+            let predicted = per_category.entry(o.predicted.category.clone()).or_default();
+            predicted.predicted += 1;
+            if o.correct() {
+                predicted.correct += 1;
+            }
+            *confusion.entry((o.expected.category.clone(), o.predicted.category.clone())).or_insert(0) += 1;
+        }
+
+        let mut calibration = Vec::new();
+        let mut from = 0.0;
+        for (i, to) in CALIBRATION_BANDS.iter().copied().enumerate() {
+            let last = i == CALIBRATION_BANDS.len() - 1;
+            let band: Vec<&Outcome> = outcomes
+                .iter()
+                .filter(|o| o.predicted.confidence >= from && (o.predicted.confidence < to || last))
+                .collect();
+            if !band.is_empty() {
+                let n = band.len() as f64;
+                calibration.push(CalibrationBand {
+                    from,
+                    to,
+                    count: band.len(),
+                    mean_confidence: band.iter().map(|o| o.predicted.confidence).sum::<f64>() / n,
+                    accuracy: band.iter().filter(|o| o.correct()).count() as f64 / n,
+                });
+            }
+            from = to;
+        }
+
+        Evaluation { outcomes, threshold, per_category, confusion, calibration }
+    }
+
+    fn share(&self, outcomes: &[&Outcome], f: impl Fn(&Outcome) -> bool) -> Option<f64> {
+        (!outcomes.is_empty()).then(|| outcomes.iter().filter(|o| f(o)).count() as f64 / outcomes.len() as f64)
+    }
+
+    fn all(&self) -> Vec<&Outcome> {
+        self.outcomes.iter().collect()
+    }
+
+    fn accepted(&self) -> Vec<&Outcome> {
+        self.outcomes.iter().filter(|o| o.predicted.confidence >= self.threshold).collect()
+    }
+
+    pub fn accuracy(&self) -> Option<f64> {
+        self.share(&self.all(), Outcome::correct)
+    }
+
+    /// Accuracy of the results confident enough to skip review.
+    pub fn auto_accept_accuracy(&self) -> Option<f64> {
+        self.share(&self.accepted(), Outcome::correct)
+    }
+
+    pub fn flagging_rate(&self) -> Option<f64> {
+        self.share(&self.all(), |o| o.predicted.confidence < self.threshold)
+    }
+
+    pub fn merchant_match_rate(&self) -> Option<f64> {
+        self.share(&self.all(), Outcome::merchant_matches)
+    }
+
+    /// Mean gap between confidence and accuracy over the calibration bands,
+    /// weighted by band size. Zero means confidence is exactly as reliable as
+    /// it claims.
+    pub fn calibration_error(&self) -> Option<f64> {
+        let n = self.outcomes.len() as f64;
+        (n > 0.0).then(|| {
+            self.calibration
+                .iter()
+                .map(|b| b.count as f64 / n * (b.accuracy - b.mean_confidence).abs())
+                .sum()
+        })
+    }
+
+    /// Wrong results that would have been auto-accepted.
+    pub fn false_confidence(&self) -> Vec<&Outcome> {
+        self.accepted().into_iter().filter(|o| !o.correct()).collect()
+    }
+
+    pub fn misclassified(&self) -> Vec<&Outcome> {
+        self.outcomes.iter().filter(|o| !o.correct()).collect()
+    }
+
+    /// Categories in the confusion matrix, expected and predicted.
+    pub fn confusion_categories(&self) -> Vec<&str> {
+        let names: BTreeSet<&str> = self.confusion.keys().flat_map(|(e, p)| [e.as_str(), p.as_str()]).collect();
+        names.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcome(expected: &str, predicted: &str, confidence: f64) -> Outcome {
+        Outcome {
+            transaction_id: String::new(),
+            description: String::new(),
+            expected: Label { merchant: "SBB".to_string(), category: expected.to_string() },
+            predicted: ClassificationResult {
+                merchant: "SBB Mobile".to_string(),
+                category: predicted.to_string(),
+                confidence,
+                source: "llm".to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_spec_accuracy_and_flagging_rate() {
+        // 24 transactions: 20 above the threshold with 18 right, 4 below with 2 right
+        let mut outcomes = Vec::new();
+        outcomes.extend((0..18).map(|_| outcome("Transport", "Transport", 0.95)));
+        outcomes.extend((0..2).map(|_| outcome("Transport", "Shopping", 0.9)));
+        outcomes.extend((0..2).map(|_| outcome("Dining", "Dining", 0.6)));
+        outcomes.extend((0..2).map(|_| outcome("Dining", "Other", 0.5)));
+        let eval = Evaluation::new(outcomes, 0.80);
+
+        assert_eq!(eval.auto_accept_accuracy(), Some(0.9));
+        assert!((eval.accuracy().unwrap() - 20.0 / 24.0).abs() < 1e-9);
+        assert!((eval.flagging_rate().unwrap() - 4.0 / 24.0).abs() < 1e-9);
+        assert_eq!(eval.false_confidence().len(), 2);
+        assert_eq!(eval.misclassified().len(), 4);
+        assert_eq!(eval.merchant_match_rate(), Some(1.0));
+
+        let transport = &eval.per_category["Transport"];
+        assert_eq!((transport.expected, transport.predicted, transport.correct), (20, 18, 18));
+        assert_eq!(transport.precision(), Some(1.0));
+        assert_eq!(transport.recall(), Some(0.9));
+        assert_eq!(eval.per_category["Shopping"].recall(), None);
+        assert_eq!(eval.confusion[&("Transport".to_string(), "Shopping".to_string())], 2);
+        assert_eq!(eval.confusion_categories(), vec!["Dining", "Other", "Shopping", "Transport"]);
+    }
+
+    #[test]
+    fn test_calibration_bands() {
+        let eval = Evaluation::new(vec![
+            outcome("Transport", "Transport", 1.0),
+            outcome("Transport", "Shopping", 0.9),
+            outcome("Dining", "Dining", 0.55),
+        ], 0.80);
+        let bands: Vec<(f64, usize, f64)> = eval.calibration.iter().map(|b| (b.from, b.count, b.accuracy)).collect();
+        assert_eq!(bands, vec![(0.5, 1, 1.0), (0.9, 2, 0.5)]);
+        // |1.0 - 0.55| / 3 + 2 * |0.5 - 0.95| / 3
+        assert!((eval.calibration_error().unwrap() - 0.45).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_load_ground_truth() -> Result<(), String> {
+        let labels = load_labels(Path::new("data/ground-truth.toml"))?;
+        assert_eq!(labels.len(), 24);
+        assert_eq!(labels["2292043DJ7410461"], Label { merchant: "Steuerverwaltung".to_string(), category: "Taxes".to_string() });
+        Ok(())
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+use chrono::NaiveDate;
+
+/// One stored exchange rate: on `date`, one unit of `base` buys `rate` units of `quote`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FxRate {
+    pub date: NaiveDate,
+    pub base: String,
+    pub quote: String,
+    pub rate: f64,
+}
+
+/// Read a rates CSV. Two layouts are accepted, comma or semicolon separated:
+///
+/// - the ECB reference rates (`eurofxref-hist.csv`): a `Date` column followed by
+///   one column per currency, each holding units per EUR
+/// - one rate per row with `date`, `base`, `quote` and `rate` columns, e.g. SNB
+///   rates as `2026-01-30,EUR,CHF,0.9312`
+pub fn parse_rates_csv(path: &Path) -> Result<Vec<FxRate>, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let text = text.strip_prefix('\u{feff}').unwrap_or(&text);
+    let header = text.lines().next().ok_or("Empty rates file")?;
+    let delimiter = if header.contains(';') { b';' } else { b',' };
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(text.as_bytes());
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|e| format!("Failed to read header: {}", e))?
+        .iter()
+        .map(|h| h.to_ascii_lowercase())
+        .collect();
+    let column = |name: &str| headers.iter().position(|h| h == name);
+
+    let date_col = column("date").ok_or("Rates file has no 'date' column")?;
+    let long = (column("base"), column("quote"), column("rate"));
+
+    let mut rates = Vec::new();
+    for (i, record) in reader.records().enumerate() {
+        let record = record.map_err(|e| format!("Failed to parse rates row {}: {}", i + 1, e))?;
+        let field = |idx: usize| record.get(idx).unwrap_or("");
+        let date = parse_rate_date(field(date_col))?;
+
+        if let (Some(base), Some(quote), Some(rate)) = long {
+            let rate: f64 = field(rate)
+                .parse()
+                .map_err(|_| format!("Invalid rate '{}' in row {}", field(rate), i + 1))?;
+            rates.push(FxRate {
+                date,
+                base: field(base).to_ascii_uppercase(),
+                quote: field(quote).to_ascii_uppercase(),
+                rate,
+            });
+            continue;
+        }
+
+        // ECB layout: every other named column is a currency quoted against EUR
+        for (idx, currency) in headers.iter().enumerate() {
+            if idx == date_col || currency.len() != 3 {
+                continue;
+            }
+            // Discontinued currencies are reported as N/A
+            if let Ok(rate) = field(idx).parse::<f64>() {
+                rates.push(FxRate {
+                    date,
+                    base: "EUR".to_string(),
+                    quote: currency.to_ascii_uppercase(),
+                    rate,
+                });
+            }
+        }
+    }
+
+    if rates.is_empty() {
+        return Err(format!("No rates found in {}", path.display()));
+    }
+    Ok(rates)
+}
+
+fn parse_rate_date(s: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(s, "%d.%m.%Y"))
+        .map_err(|_| format!("Invalid rate date '{}'", s))
+}
+
+/// Stored rates indexed by currency pair, for converting transaction amounts.
+#[derive(Debug, Default)]
+pub struct FxTable {
+    series: HashMap<(String, String), Vec<(NaiveDate, f64)>>,
+    currencies: BTreeSet<String>,
+}
+
+impl FxTable {
+    pub fn new(rates: Vec<FxRate>) -> Self {
+        let mut table = FxTable::default();
+        for r in rates {
+            table.currencies.insert(r.base.clone());
+            table.currencies.insert(r.quote.clone());
+            table.series.entry((r.base, r.quote)).or_default().push((r.date, r.rate));
+        }
+        for series in table.series.values_mut() {
+            series.sort_by_key(|(date, _)| *date);
+        }
+        table
+    }
+
+    /// Units of `to` per unit of `from` on `date`. Uses the latest rate on or before
+    /// the date (the earliest one for older dates), inverting a stored pair or
+    /// crossing through a common currency when there is no direct rate.
+    pub fn rate(&self, from: &str, to: &str, date: NaiveDate) -> Option<f64> {
+        if from == to {
+            return Some(1.0);
+        }
+        self.direct(from, to, date).or_else(|| {
+            self.currencies
+                .iter()
+                .filter(|pivot| pivot.as_str() != from && pivot.as_str() != to)
+                .find_map(|pivot| Some(self.direct(from, pivot, date)? * self.direct(pivot, to, date)?))
+        })
+    }
+
+    fn direct(&self, from: &str, to: &str, date: NaiveDate) -> Option<f64> {
+        let lookup = |series: &Vec<(NaiveDate, f64)>| {
+            let idx = series.partition_point(|(d, _)| *d <= date);
+            series.get(idx.saturating_sub(1)).map(|(_, rate)| *rate)
+        };
+        if let Some(rate) = self.series.get(&(from.to_string(), to.to_string())).and_then(lookup) {
+            return Some(rate);
+        }
+        self.series
+            .get(&(to.to_string(), from.to_string()))
+            .and_then(lookup)
+            .filter(|rate| *rate != 0.0)
+            .map(|rate| 1.0 / rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_parse_ecb_and_long_layouts() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let ecb = dir.path().join("eurofxref-hist.csv");
+        std::fs::write(&ecb, "Date,USD,JPY,CYP,CHF,\n2026-01-30,1.0412,160.5,N/A,0.9405,\n2026-01-29,1.0398,161.1,N/A,0.9411,\n")?;
+        let rates = parse_rates_csv(&ecb)?;
+        assert_eq!(rates.len(), 6);
+        assert_eq!(rates[2], FxRate { date: d("2026-01-30"), base: "EUR".to_string(), quote: "CHF".to_string(), rate: 0.9405 });
+
+        let long = dir.path().join("snb.csv");
+        std::fs::write(&long, "Date;Base;Quote;Rate\n30.01.2026;usd;chf;0.9031\n")?;
+        assert_eq!(parse_rates_csv(&long)?[0].base, "USD");
+
+        let bad = dir.path().join("bad.csv");
+        std::fs::write(&bad, "Date,Base,Quote,Rate\n2026-01-30,USD,CHF,abc\n")?;
+        assert!(parse_rates_csv(&bad).unwrap_err().contains("Invalid rate 'abc'"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_rate_lookup_inverts_and_crosses() {
+        let rate = |date: &str, base: &str, quote: &str, rate: f64| FxRate {
+            date: d(date), base: base.to_string(), quote: quote.to_string(), rate,
+        };
+        let table = FxTable::new(vec![
+            rate("2026-02-01", "EUR", "CHF", 0.95),
+            rate("2026-01-01", "EUR", "CHF", 0.90),
+            rate("2026-01-01", "EUR", "USD", 1.20),
+        ]);
+
+        assert_eq!(table.rate("CHF", "CHF", d("2026-01-15")), Some(1.0));
+        assert_eq!(table.rate("EUR", "CHF", d("2026-01-31")), Some(0.90));
+        assert_eq!(table.rate("EUR", "CHF", d("2026-03-01")), Some(0.95));
+        // Dates before the first rate use the earliest one
+        assert_eq!(table.rate("EUR", "CHF", d("2025-06-01")), Some(0.90));
+        assert!((table.rate("CHF", "EUR", d("2026-01-15")).unwrap() - 1.0 / 0.90).abs() < 1e-9);
+        assert!((table.rate("USD", "CHF", d("2026-01-15")).unwrap() - 0.75).abs() < 1e-9);
+        assert_eq!(table.rate("GBP", "CHF", d("2026-01-15")), None);
+    }
+}
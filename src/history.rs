@@ -0,0 +1,82 @@
+use crate::classifier::ClassificationResult;
+
+/// One recorded change to a transaction's classification.
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    pub changed_at: String,
+    /// The command or chat tool that made the change, e.g. `review`,
+    /// `reclassify` or `chat: recategorise`.
+    pub changed_by: String,
+    pub before: ClassificationResult,
+    pub after: ClassificationResult,
+    /// What else happened, e.g. the split or the transfer it was paired with.
+    pub note: Option<String>,
+}
+
+impl HistoryEntry {
+    /// The fields that changed, in a form like "category Shopping → Groceries".
+    pub fn describe(&self) -> String {
+        let (before, after) = (&self.before, &self.after);
+        let mut parts = Vec::new();
+        if before.merchant != after.merchant {
+            parts.push(format!("merchant {} → {}", before.merchant, after.merchant));
+        }
+        if before.category != after.category {
+            parts.push(format!("category {} → {}", before.category, after.category));
+        }
+        if before.source != after.source {
+            parts.push(format!("source {} → {}", before.source, after.source));
+        }
+        if (before.confidence - after.confidence).abs() >= 0.005 {
+            parts.push(format!("confidence {:.2} → {:.2}", before.confidence, after.confidence));
+        }
+        parts.extend(self.note.clone());
+        parts.join(", ")
+    }
+}
+
+/// "Merchant, Category (source, confidence)".
+pub fn describe_classification(c: &ClassificationResult) -> String {
+    format!("{}, {} ({}, {:.2})", c.merchant, c.category, c.source, c.confidence)
+}
+
+/// The classification a transaction was imported with: the state before the
+/// first recorded change, or `current` when it was never changed.
+pub fn original<'a>(entries: &'a [HistoryEntry], current: &'a ClassificationResult) -> &'a ClassificationResult {
+    entries.first().map_or(current, |e| &e.before)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classification(merchant: &str, category: &str, source: &str, confidence: f64) -> ClassificationResult {
+        ClassificationResult {
+            merchant: merchant.to_string(),
+            category: category.to_string(),
+            confidence,
+            source: source.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_describe_lists_changed_fields() {
+        let entry = HistoryEntry {
+            changed_at: "2026-03-01T10:00:00+00:00".to_string(),
+            changed_by: "review".to_string(),
+            before: classification("Migros", "Shopping", "llm", 0.62),
+            after: classification("Migros", "Groceries", "manual", 1.0),
+            note: None,
+        };
+        assert_eq!(entry.describe(), "category Shopping → Groceries, source llm → manual, confidence 0.62 → 1.00");
+
+        let split = HistoryEntry {
+            before: entry.after.clone(),
+            note: Some("split into Groceries 70.00, Household 30.00".to_string()),
+            ..entry.clone()
+        };
+        assert_eq!(split.describe(), "split into Groceries 70.00, Household 30.00");
+        assert_eq!(original(&[entry.clone(), split], &entry.after).category, "Shopping");
+        assert_eq!(original(&[], &entry.after).category, "Groceries");
+    }
+}
@@ -1,28 +1,44 @@
 mod cache;
+mod balances;
+mod calibration;
 mod categories;
 mod classifier;
+mod cli;
 mod config;
+mod coverage;
 mod csv_parser;
-mod cc_rules;
 mod db;
+mod evaluation;
+mod fx;
+mod history;
+mod recurring;
+mod rules;
 mod review;
+mod splits;
+mod transfers;
 mod ai;
 mod chat;
 mod tools;
 
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::Path;
+use std::time::{Duration, Instant};
 use axum::Router;
 use tower_http::services::ServeDir;
-use classifier::Classifier;
-use config::Config;
-use db::{Database, CategoryInfo};
+use classifier::{ClassificationResult, Classifier, ClassifyRequest};
+use clap::{CommandFactory, Parser};
+use cli::{AccountsCommand, BalancesCommand, BudgetsCommand, CalibrationCommand, CategoriesCommand, Cli, Command, ConfigCommand, FxCommand, ImportsCommand, RulesCommand, SplitsCommand, TransfersCommand};
+use config::{Config, ProviderConfig};
+use csv_parser::CsvFormat;
+use db::{Database, CategoryInfo, BudgetPeriod, FewShotExample, ImportLogEntry};
+use rules::{RuleInput, RuleSet, SharedRules};
 use review::{run_review, run_recategorise, ReviewFilters};
 use ai::agent::{Agent, build_system_prompt};
-use ai::llm::LlmProvider;
-use chat::{ChatState, router as chat_router};
+use ai::llm::build_provider;
+use chat::{ChatState, ImportState, import_router, router as chat_router};
 use tools::ToolRegistry;
 
-#[derive(Default, Debug, PartialEq)]
+#[derive(Default, Debug, PartialEq, serde::Serialize)]
 pub struct ImportStats {
     pub total_parsed: usize,
     pub new_insertions: usize,
@@ -30,6 +46,13 @@ pub struct ImportStats {
     pub cache_hits: usize,
     pub rules_hits: usize,
     pub llm_calls: usize,
+    pub transfers_matched: usize,
+    /// Periods where the statement's reported balances do not match the imported transactions.
+    pub balance_gaps: usize,
+    /// Transactions from the file that need review at the calibrated confidence.
+    pub flagged: usize,
+    /// Rows an interrupted import had already handled when this one resumed it.
+    pub resumed_rows: usize,
 }
 
 impl ImportStats {
@@ -40,14 +63,183 @@ impl ImportStats {
         self.cache_hits += other.cache_hits;
         self.rules_hits += other.rules_hits;
         self.llm_calls += other.llm_calls;
+        self.transfers_matched += other.transfers_matched;
+        self.balance_gaps += other.balance_gaps;
+        self.flagged += other.flagged;
+        self.resumed_rows += other.resumed_rows;
+    }
+}
+
+/// Outcome of classifying a single new transaction during an import.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ImportProgress {
+    pub index: usize,
+    pub total: usize,
+    pub description: String,
+    pub merchant: String,
+    pub category: String,
+    pub confidence: f64,
+    pub source: String,
+    pub elapsed_ms: u128,
+}
+
+/// New merchants sent to the LLM per round of an import. Progress is reported
+/// after each round, so this bounds how long the output stalls.
+const CLASSIFY_ROUND: usize = 32;
+
+/// Where a planned row's classification will come from.
+enum Planned {
+    Classified(ClassificationResult, Duration),
+    /// Waiting for the round's answer for this merchant.
+    Pending(usize),
+}
+
+/// A row of a round with its classification and how long that took.
+struct RoundRow<'t> {
+    index: usize,
+    tx: &'t csv_parser::Transaction,
+    result: ClassificationResult,
+    elapsed: Duration,
+}
+
+/// Classifies rows the way an import does: rules first, then the merchant
+/// cache, then the LLM once per new merchant. Rows are planned one at a time
+/// and classified in rounds, so each round's new merchants go to the LLM
+/// together, concurrently and in batches as the classifier is configured.
+struct ClassifyRounds<'t> {
+    format: CsvFormat,
+    planned: Vec<(usize, &'t csv_parser::Transaction, Planned)>,
+    pending_keys: HashMap<String, usize>,
+    requests: Vec<(String, ClassifyRequest<'t>)>,
+}
+
+impl<'t> ClassifyRounds<'t> {
+    fn new(format: CsvFormat) -> Self {
+        Self { format, planned: Vec::new(), pending_keys: HashMap::new(), requests: Vec::new() }
+    }
+
+    /// Plan row `index`. `lookup` returns the cached classification of a
+    /// merchant key, if there is one.
+    fn plan<E>(
+        &mut self,
+        index: usize,
+        tx: &'t csv_parser::Transaction,
+        rules: &RuleSet,
+        stats: &mut ImportStats,
+        lookup: impl FnOnce(&str) -> Result<Option<ClassificationResult>, E>,
+    ) -> Result<(), E> {
+        let start = Instant::now();
+        let plan = if let Some(res) = rules.classify(&RuleInput::from_transaction(self.format, tx)) {
+            stats.rules_hits += 1;
+            Planned::Classified(res, start.elapsed())
+        } else {
+            let key = cache::normalise_merchant_key(&tx.description);
+            if let Some(mut cached) = lookup(&key)? {
+                stats.cache_hits += 1;
+                cached.source = "cache".to_string();
+                Planned::Classified(cached, start.elapsed())
+            } else {
+                // New merchant: ask the LLM once per key in this round
+                let next = self.requests.len();
+                let requests = &mut self.requests;
+                let n = *self.pending_keys.entry(key.clone()).or_insert_with(|| {
+                    requests.push((key, ClassifyRequest {
+                        description: &tx.description,
+                        amount: tx.debit.or(tx.credit),
+                        details: &tx.details,
+                    }));
+                    next
+                });
+                Planned::Pending(n)
+            }
+        };
+        self.planned.push((index, tx, plan));
+        Ok(())
+    }
+
+    fn is_full(&self) -> bool {
+        self.requests.len() >= CLASSIFY_ROUND
+    }
+
+    /// Ask the LLM about the round's new merchants. Returns the round's rows
+    /// in the order they were planned, and each new merchant key with its
+    /// answer, leaving out fallbacks so they are not cached. Repeats of a
+    /// merchant asked about in this round count as cache hits, as they would
+    /// one at a time.
+    #[allow(clippy::type_complexity)]
+    fn classify(
+        &mut self,
+        classifier: &Classifier,
+        examples: &[FewShotExample],
+        categories: &[CategoryInfo],
+        stats: &mut ImportStats,
+    ) -> (Vec<RoundRow<'t>>, Vec<(String, ClassificationResult)>) {
+        let batch: Vec<ClassifyRequest> = self.requests.iter().map(|(_, r)| *r).collect();
+        let answers = if batch.is_empty() { Vec::new() } else { classifier.classify_many(&batch, examples, categories) };
+        stats.llm_calls += answers.len();
+
+        let mut reported = vec![false; answers.len()];
+        let rows = self
+            .planned
+            .drain(..)
+            .map(|(index, tx, plan)| {
+                let (result, elapsed) = match plan {
+                    Planned::Classified(res, elapsed) => (res, elapsed),
+                    Planned::Pending(n) if !reported[n] => {
+                        reported[n] = true;
+                        answers[n].clone()
+                    }
+                    Planned::Pending(n) => {
+                        stats.cache_hits += 1;
+                        (ClassificationResult { source: "cache".to_string(), ..answers[n].0.clone() }, Duration::ZERO)
+                    }
+                };
+                RoundRow { index, tx, result, elapsed }
+            })
+            .collect();
+        let new = self
+            .requests
+            .drain(..)
+            .zip(answers)
+            .map(|((key, _), (res, _))| (key, res))
+            .filter(|(_, res)| !Classifier::is_fallback(res))
+            .collect();
+        self.pending_keys.clear();
+        (rows, new)
     }
 }
 
 pub fn import_file(
     db: &Database,
     classifier: &Classifier,
+    rules: &RuleSet,
+    csv_path: &Path,
+    categories: &[CategoryInfo],
+    resume: bool,
+) -> Result<ImportStats, Box<dyn std::error::Error>> {
+    import_file_with_progress(db, classifier, rules, csv_path, categories, resume, |p| {
+        println!(
+            "  [{}/{}] {} → {} ({}) [{:.2}] via {} ({}ms)",
+            p.index, p.total, p.description, p.category, p.merchant, p.confidence, p.source, p.elapsed_ms
+        );
+    })
+}
+
+/// Same as `import_file`, but reports each classified transaction to `on_progress`
+/// instead of printing it.
+///
+/// The import is logged as in progress when it starts and checkpointed after
+/// each round, so a crash loses at most the round in flight. With `resume` an
+/// interrupted import of the same file continues after its last checkpoint;
+/// without it, such an import is an error.
+pub fn import_file_with_progress(
+    db: &Database,
+    classifier: &Classifier,
+    rules: &RuleSet,
     csv_path: &Path,
     categories: &[CategoryInfo],
+    resume: bool,
+    mut on_progress: impl FnMut(ImportProgress),
 ) -> Result<ImportStats, Box<dyn std::error::Error>> {
     let (csv_format, transactions) = csv_parser::parse_csv(csv_path)?;
     let total = transactions.len();
@@ -62,250 +254,284 @@ pub fn import_file(
         .and_then(|s| s.to_str())
         .unwrap_or("unknown");
 
-    let examples = db.get_few_shot_examples()?;
-
-    for (i, tx) in transactions.iter().enumerate() {
-        // 1. Duplicate detection
-        if db.transaction_exists(&tx.transaction_id)? {
-            stats.duplicates_skipped += 1;
-            continue;
+    let file_hash = coverage::file_hash(csv_path)?;
+    let (log_id, skip) = match db.interrupted_import(import_batch)? {
+        Some(previous) if resume => {
+            if previous.file_hash.as_deref() != Some(file_hash.as_str()) {
+                return Err(format!(
+                    "{} has changed since its import was interrupted; remove that import with `imports rollback {}` and import the file again",
+                    import_batch, import_batch
+                )
+                .into());
+            }
+            stats.new_insertions = previous.row_count;
+            stats.resumed_rows = previous.rows_done;
+            (previous.id, previous.rows_done)
         }
+        Some(previous) => {
+            return Err(format!(
+                "an import of {} stopped after {} of its rows on {}; continue it with `import --resume` or remove it with `imports rollback {}`",
+                import_batch, previous.rows_done, &previous.started_at[..10.min(previous.started_at.len())], import_batch
+            )
+            .into());
+        }
+        None => (db.start_import(import_batch, Some(&file_hash))?, 0),
+    };
 
-        let classify_start = std::time::Instant::now();
+    let examples = db.get_few_shot_examples()?;
 
-        // 2. Rules-based classification (CreditCard only)
-        let mut rules_result = None;
-        if csv_format == csv_parser::CsvFormat::CreditCard {
-            if let Some(res) = cc_rules::classify(&tx.description, tx.sector.as_deref()) {
-                stats.rules_hits += 1;
-                rules_result = Some(res);
-            }
-        }
+    let mut rounds = ClassifyRounds::new(csv_format);
+    let mut seen_ids = HashSet::new();
 
-        // 3. Normalise key for cache lookup (if no rules result)
-        let result = if let Some(res) = rules_result {
-            res
+    for (i, tx) in transactions.iter().enumerate().skip(skip) {
+        // 1. Duplicate detection, including repeats within the file
+        if !seen_ids.insert(tx.transaction_id.as_str()) || db.transaction_exists(&tx.transaction_id)? {
+            stats.duplicates_skipped += 1;
         } else {
-            let key = cache::normalise_merchant_key(&tx.description);
+            // 2. Rules-based classification, then 3. the merchant cache, then
+            // 4. the LLM for the round's new merchants
+            rounds.plan(i, tx, rules, &mut stats, |key| db.cache_lookup(key))?;
+        }
 
-            // 4. Cache lookup vs LLM classification
-            if let Some(mut cached) = db.cache_lookup(&key)? {
-                stats.cache_hits += 1;
-                cached.source = "cache".to_string();
-                cached
-            } else {
-                stats.llm_calls += 1;
-                let amount = tx.debit.or(tx.credit);
-                let res = classifier.classify(&tx.description, amount, &tx.details, &examples, categories);
+        if !rounds.is_full() && i + 1 < total {
+            continue;
+        }
+        let (rows, answers) = rounds.classify(classifier, &examples, categories, &mut stats);
 
-                // Store in cache for future use
-                db.cache_insert(&key, &res)?;
-                res
+        // 5. Cache the answers, so later rows find them, then report and insert
+        // in file order, all as one checkpoint
+        db.checkpoint_import(log_id, i + 1, || -> Result<usize, Box<dyn std::error::Error>> {
+            for (key, res) in &answers {
+                db.cache_insert(key, res, Some(import_batch))?;
             }
-        };
-
-        let elapsed_ms = classify_start.elapsed().as_millis();
-        println!(
-            "  [{}/{}] {} → {} ({}) [{:.2}] via {} ({}ms)",
-            i + 1, total, tx.description, result.category, result.merchant, result.confidence, result.source, elapsed_ms
-        );
+            for RoundRow { index, tx, result, elapsed } in rows {
+                on_progress(ImportProgress {
+                    index: index + 1,
+                    total,
+                    description: tx.description.clone(),
+                    merchant: result.merchant.clone(),
+                    category: result.category.clone(),
+                    confidence: result.confidence,
+                    source: result.source.clone(),
+                    elapsed_ms: elapsed.as_millis(),
+                });
+
+                if db.insert_transaction(tx, &result, Some(import_batch))? {
+                    stats.new_insertions += 1;
+                }
+            }
+            Ok(stats.new_insertions)
+        })?;
+    }
 
-        // 5. Insert transaction
-        if db.insert_transaction(tx, &result, Some(import_batch))? {
-            stats.new_insertions += 1;
+    // 6. The period and accounts the file covers, for the import log
+    let statement_period = csv_parser::parse_statement_period(csv_path)?;
+    let booked_period = transactions
+        .iter()
+        .map(|tx| tx.trade_date)
+        .min()
+        .zip(transactions.iter().map(|tx| tx.trade_date).max());
+    let mut accounts = Vec::new();
+    for account in transactions.iter().filter_map(|tx| tx.account.as_ref()) {
+        if !accounts.contains(&account) {
+            accounts.push(account);
         }
     }
+    let account_ids = accounts.into_iter().map(|a| db.upsert_account(a)).collect::<Result<Vec<_>, _>>()?;
+    let log_entry = ImportLogEntry {
+        filename: import_batch.to_string(),
+        row_count: stats.new_insertions,
+        file_hash: Some(file_hash),
+        period: statement_period.or(booked_period),
+        period_exact: statement_period.is_some(),
+        account_ids,
+    };
+
+    // 7. Pair up transfers between this file and earlier imports
+    stats.transfers_matched = db.match_transfers(transfers::TRANSFER_WINDOW_DAYS)?;
+
+    // 8. Check the statement's reported balances against the account's transactions
+    if let Some(balances) = csv_parser::parse_statement_balances(csv_path)? {
+        let account_id = db.store_statement_balances(&balances, import_batch)?;
+        stats.balance_gaps = db.reconcile_account(account_id)?.len();
+    }
 
-    // 5. Log import run
-    db.log_import(import_batch, stats.new_insertions)?;
+    // 9. Rate the new transactions with the calibration learned from reviews
+    stats.flagged = db.calibrate_batch(import_batch, calibration::REVIEW_THRESHOLD)?;
 
+    // 10. Detect recurring payments again, now that transfers are paired
+    db.refresh_recurring_payments()?;
+
+    // 11. Only now is the import complete
+    db.finish_import(log_id, &log_entry)?;
     Ok(stats)
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args: Vec<String> = std::env::args().collect();
-
-    if args.len() < 2 {
-        println!("Usage: budget-analyser <command> [args]");
-        println!("Commands:");
-        println!("  import <path> [db_path] [model] [endpoint]");
-        println!("  serve");
-        println!("  review [db_path] [--category C] [--since S] [--until U] [--merchant M] [--threshold T]");
-        println!("  reclassify [db_path] [model] [endpoint] [--category C] [--since S] [--until U] [--merchant M] [--threshold T]");
-        println!("  recategorise --category <name> [db_path]");
-        println!("  categories list [db_path]");
-        println!("  categories add <name> <description> [db_path]");
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    if let Command::Completions { shell } = cli.command {
+        clap_complete::generate(shell, &mut Cli::command(), "budget-analyser", &mut std::io::stdout());
         return Ok(());
     }
 
-    let command = &args[1];
-
-    match command.as_str() {
-        "import" => {
-            let input_path = args.get(2).map(|s| s.as_str()).unwrap_or("data/synthetic-ubstransactions-feb2026.csv");
-            let db_path = args.get(3).map(|s| s.as_str()).unwrap_or("data/budget.db");
-            let model = args.get(4).map(|s| s.as_str()).unwrap_or("qwen3:8b");
-            let endpoint = args.get(5).map(|s| s.as_str()).unwrap_or("http://localhost:11434");
-
-            run_import(input_path, db_path, model, endpoint)
-        }
-        "serve" | "chat" => {
-            let config = Config::from_env().map_err(|err| -> Box<dyn std::error::Error> { err.into() })?;
-            run_server(config).await
-        }
-        "review" => {
-            let mut db_path = "data/budget.db";
-            let mut category = None;
-            let mut since = None;
-            let mut until = None;
-            let mut merchant = None;
-            let mut threshold = 0.80;
-
-            let mut i = 2;
-            while i < args.len() {
-                match args[i].as_str() {
-                    "--category" => { category = args.get(i + 1).map(|s| s.as_str()); i += 2; }
-                    "--since" => { since = args.get(i + 1).map(|s| s.as_str()); i += 2; }
-                    "--until" => { until = args.get(i + 1).map(|s| s.as_str()); i += 2; }
-                    "--merchant" => { merchant = args.get(i + 1).map(|s| s.as_str()); i += 2; }
-                    "--threshold" => { 
-                        threshold = args.get(i + 1).and_then(|s| s.parse().ok()).unwrap_or(0.80); 
-                        i += 2; 
-                    }
-                    path if !path.starts_with("--") => {
-                        db_path = path;
-                        i += 1;
+    let config = Config::load(&cli.overrides())?;
+    let db_path = config.database_url.as_str();
+
+    match cli.command {
+        Command::Import { path, resume } => run_import(&path, resume, &config),
+        Command::Serve => {
+            // Only the server needs an async runtime; the CLI commands are synchronous
+            tokio::runtime::Runtime::new()?.block_on(run_server(config))
+        }
+        Command::Review(args) => {
+            let db = Database::open(Path::new(db_path))?;
+            let categories = db.list_categories()?;
+            run_review(&db, args.filters(), &categories)?;
+            run_calibration(&db, CalibrationCommand::Fit)
+        }
+        Command::Reclassify(args) => run_reclassify(db_path, &config.classifier, args.filters()),
+        Command::Recategorise { category } => {
+            let db = Database::open(Path::new(db_path))?;
+            let categories = db.list_categories()?;
+            run_recategorise(&db, &category, &categories)?;
+            run_calibration(&db, CalibrationCommand::Fit)
+        }
+        Command::Evaluate { csv, labels, threshold, no_cache } => run_evaluate(&csv, &labels, threshold, !no_cache, &config),
+        Command::Categories(sub) => {
+            let db = Database::open(Path::new(db_path))?;
+            match sub {
+                CategoriesCommand::List => {
+                    let cats = db.list_categories()?;
+                    println!("Spending Categories:");
+                    for cat in cats {
+                        println!("- {}: {}", cat.name, cat.description);
                     }
-                    _ => i += 1,
                 }
+                CategoriesCommand::Add { name, description } => match db.add_category(&name, &description) {
+                    Ok(()) => println!("Added category: {}", name),
+                    Err(_) => println!("Error: category '{}' already exists.", name),
+                },
             }
-
+            Ok(())
+        }
+        Command::Budgets(sub) => {
             let db = Database::open(Path::new(db_path))?;
-            let categories = db.list_categories()?;
-            run_review(&db, ReviewFilters {
-                category,
-                since,
-                until,
-                merchant,
-                threshold,
-            }, &categories)
-        }
-        "reclassify" => {
-            let mut db_path = "data/budget.db";
-            let mut model = "qwen3:8b";
-            let mut endpoint = "http://localhost:11434";
-            let mut category = None;
-            let mut since = None;
-            let mut until = None;
-            let mut merchant = None;
-            let mut threshold = 0.80;
-
-            let mut i = 2;
-            while i < args.len() {
-                match args[i].as_str() {
-                    "--category" => { category = args.get(i + 1).map(|s| s.as_str()); i += 2; }
-                    "--since" => { since = args.get(i + 1).map(|s| s.as_str()); i += 2; }
-                    "--until" => { until = args.get(i + 1).map(|s| s.as_str()); i += 2; }
-                    "--merchant" => { merchant = args.get(i + 1).map(|s| s.as_str()); i += 2; }
-                    "--threshold" => { 
-                        threshold = args.get(i + 1).and_then(|s| s.parse().ok()).unwrap_or(0.80); 
-                        i += 2; 
+            match sub {
+                BudgetsCommand::List => {
+                    let budgets = db.list_budgets()?;
+                    if budgets.is_empty() {
+                        println!("No budgets set.");
+                        return Ok(());
                     }
-                    path if !path.starts_with("--") => {
-                        // Very basic heuristic: if it contains : it's a model or endpoint
-                        if path.contains(":") {
-                            if path.starts_with("http") {
-                                endpoint = path;
-                            } else {
-                                model = path;
-                            }
-                        } else {
-                            db_path = path;
-                        }
-                        i += 1;
+                    println!("Budgets:");
+                    for b in budgets {
+                        let monthly = b.monthly_limit.map(|v| format!("{:.2}/month", v)).unwrap_or_else(|| "-".to_string());
+                        let annual = b.annual_limit.map(|v| format!("{:.2}/year", v)).unwrap_or_else(|| "-".to_string());
+                        println!("- {}: {} | {}", b.category, monthly, annual);
+                    }
+                }
+                BudgetsCommand::Set { category, amount, annual } => {
+                    if !db.list_categories()?.iter().any(|c| c.name == category) {
+                        println!("Error: unknown category '{}'. Use `categories list` to see valid names.", category);
+                        return Ok(());
+                    }
+                    let period = if annual { BudgetPeriod::Annual } else { BudgetPeriod::Monthly };
+                    db.set_budget(&category, period, amount)?;
+                    println!("Set {} budget for {}: {:.2}", if annual { "annual" } else { "monthly" }, category, amount);
+                }
+                BudgetsCommand::Remove { category } => {
+                    if db.remove_budget(&category)? {
+                        println!("Removed budget for {}", category);
+                    } else {
+                        println!("No budget set for {}", category);
                     }
-                    _ => i += 1,
                 }
             }
-
-            run_reclassify(db_path, model, endpoint, ReviewFilters {
-                category,
-                since,
-                until,
-                merchant,
-                threshold,
-            })
+            Ok(())
         }
-        "recategorise" => {
-            let mut db_path = "data/budget.db";
-            let mut category = None;
-
-            let mut i = 2;
-            while i < args.len() {
-                match args[i].as_str() {
-                    "--category" => { category = args.get(i + 1).map(|s| s.as_str()); i += 2; }
-                    path if !path.starts_with("--") => {
-                        db_path = path;
-                        i += 1;
+        Command::Recurring => run_recurring(db_path),
+        Command::Splits(sub) => run_splits(db_path, sub),
+        Command::History { id } => run_history(db_path, id),
+        Command::Fx(sub) => run_fx(db_path, &config.base_currency, sub),
+        Command::Imports(ImportsCommand::Status { account }) => run_import_status(db_path, account.as_deref()),
+        Command::Imports(ImportsCommand::Rollback { batch, revert_cache, dry_run }) => run_rollback(db_path, &batch, revert_cache, dry_run),
+        Command::Balances(sub) => run_balances(db_path, &config.base_currency, sub),
+        Command::Accounts(sub) => {
+            let db = Database::open(Path::new(db_path))?;
+            if let AccountsCommand::Rename { id, name } = sub {
+                if !db.rename_account(id, &name)? {
+                    return Err(format!("No account with id {}", id).into());
+                }
+            }
+            let accounts = db.list_accounts()?;
+            if accounts.is_empty() {
+                println!("No accounts yet; they are picked up from imported statements.");
+            }
+            for a in accounts {
+                println!("#{:<3} {:<8} {:<22} {:<28} {:<16} {:>5} tx  {}",
+                    a.id, a.kind, a.number, a.iban.as_deref().unwrap_or("-"), a.holder.as_deref().unwrap_or("-"),
+                    a.transactions, a.name.as_deref().unwrap_or(""));
+            }
+            Ok(())
+        }
+        Command::Transfers(sub) => {
+            let db = Database::open(Path::new(db_path))?;
+            match sub {
+                TransfersCommand::Match { window } => println!("Matched {} new transfer pairs.", db.match_transfers(window)?),
+                TransfersCommand::Unlink { id } => {
+                    match db.unlink_transfer(id)? {
+                        Some(other) => println!("Unlinked #{} from #{}; both have their earlier categories back.", id, other),
+                        None => println!("Transaction #{} is not linked to a transfer.", id),
                     }
-                    _ => i += 1,
+                    return Ok(());
                 }
+                TransfersCommand::List => {}
             }
-
-            if let Some(cat) = category {
-                let db = Database::open(Path::new(db_path))?;
-                let categories = db.list_categories()?;
-                run_recategorise(&db, cat, &categories)
-            } else {
-                println!("Usage: budget-analyser recategorise --category <name> [db_path]");
-                Ok(())
+            let pairs = db.list_transfers()?;
+            if pairs.is_empty() {
+                println!("No transfers linked.");
             }
+            for p in pairs {
+                println!("{}  {:>10.2} {}  #{} {}  ->  #{} {}", p.date, p.amount, p.currency, p.from_id, p.from_description, p.to_id, p.to_description);
+            }
+            Ok(())
         }
-        "categories" => {
-            let sub = args.get(2).map(|s| s.as_str()).unwrap_or("list");
+        Command::Calibration(sub) => run_calibration(&Database::open(Path::new(db_path))?, sub),
+        Command::Rules(sub) => {
+            let db = Database::open(Path::new(db_path))?;
+            let rules = RuleSet::load(&config.rules_files, &db.list_categories()?)?;
             match sub {
-                "list" => {
-                    let db_path = args.get(3).map(|s| s.as_str()).unwrap_or("data/budget.db");
-                    let db = Database::open(Path::new(db_path))?;
-                    let cats = db.list_categories()?;
-                    println!("Spending Categories:");
-                    for cat in cats {
-                        println!("- {}: {}", cat.name, cat.description);
+                RulesCommand::Check => {
+                    println!("Rules OK: {} rules, {} sectors", rules.rule_count(), rules.sector_count());
+                    for path in &config.rules_files {
+                        println!("  {}", path.display());
                     }
-                    Ok(())
                 }
-                "add" => {
-                    let name = args.get(3).map(|s| s.as_str());
-                    let desc = args.get(4).map(|s| s.as_str());
-                    let db_path = args.get(5).map(|s| s.as_str()).unwrap_or("data/budget.db");
-                    
-                    if let (Some(n), Some(d)) = (name, desc) {
-                        let db = Database::open(Path::new(db_path))?;
-                        match db.add_category(n, d) {
-                            Ok(()) => println!("Added category: {}", n),
-                            Err(_) => println!("Error: category '{}' already exists.", n),
-                        }
-                        Ok(())
+                RulesCommand::Test { description, details, amount, sector, format } => {
+                    let format = format.unwrap_or(if sector.is_some() {
+                        csv_parser::CsvFormat::CreditCard
                     } else {
-                        println!("Usage: budget-analyser categories add <name> <description> [db_path]");
-                        Ok(())
+                        csv_parser::CsvFormat::AccountStatement
+                    });
+                    let input = RuleInput { format, description: &description, details: &details, sector: sector.as_deref(), amount };
+                    match rules.explain(&input) {
+                        Some(m) => {
+                            println!("Rule:       {}", m.rule);
+                            println!("Merchant:   {}", m.result.merchant);
+                            println!("Category:   {}", m.result.category);
+                            println!("Confidence: {:.2}", m.result.confidence);
+                        }
+                        None => println!("No rule matched; the transaction would go to the cache or LLM."),
                     }
                 }
-                _ => {
-                    println!("Unknown categories subcommand: {}", sub);
-                    Ok(())
-                }
             }
+            Ok(())
         }
-        // Backward compatibility
-        path => {
-            let db_path = args.get(2).map(|s| s.as_str()).unwrap_or("data/budget.db");
-            let model = args.get(3).map(|s| s.as_str()).unwrap_or("qwen3:8b");
-            let endpoint = args.get(4).map(|s| s.as_str()).unwrap_or("http://localhost:11434");
-
-            run_import(path, db_path, model, endpoint)
+        Command::Config(ConfigCommand::Show) => {
+            show_config(&config);
+            Ok(())
         }
+        Command::Completions { .. } => unreachable!("handled before loading the config"),
     }
 }
 
@@ -314,6 +540,14 @@ async fn run_server(config: Config) -> Result<(), Box<dyn std::error::Error>> {
         .with_env_filter("info")
         .init();
 
+    // Make sure the schema (including tables added since the DB was created) exists,
+    // and that amounts convert into the configured base currency
+    let db = Database::open(Path::new(&config.database_url))?;
+    for (currency, count) in db.refresh_base_rates(&config.base_currency)? {
+        tracing::warn!("{} {} transactions have no FX rate to {}; reported unconverted", count, currency, config.base_currency);
+    }
+    drop(db);
+
     let pool = db::connect_pool(&config.database_url).await?;
     let data_summary = db::load_data_summary(&pool).await?;
     tracing::info!(
@@ -323,23 +557,32 @@ async fn run_server(config: Config) -> Result<(), Box<dyn std::error::Error>> {
         data_summary.max_date.as_deref().unwrap_or("unknown")
     );
 
-    let categories = sqlx::query_as::<_, (String, String)>("SELECT name, description FROM categories")
-        .fetch_all(&pool)
-        .await?
-        .into_iter()
-        .map(|(name, description)| CategoryInfo { name, description })
-        .collect::<Vec<_>>();
+    let categories = db::load_categories(&pool).await?;
+    let rules = SharedRules::new(RuleSet::load(&config.rules_files, &categories)?);
+    if !config.rules_files.is_empty() {
+        tokio::spawn(watch_rules(rules.clone(), config.rules_files.clone(), pool.clone()));
+    }
 
-    let llm = LlmProvider::new(config.anthropic_api_key, "claude-sonnet-4-5-20250929".to_string());
-    let tools = ToolRegistry::new();
+    let llm = build_provider(&config.chat)?;
+    tracing::info!("chat model: {} ({})", config.chat.model, config.chat.kind);
+    let tools = ToolRegistry::new().with_base_currency(&config.base_currency);
     let system_prompt = build_system_prompt(&data_summary, &categories);
     let agent = Agent::new(llm, tools, system_prompt, pool.clone());
 
-    let sessions = chat::SessionStore::new();
+    let import_state = ImportState {
+        agent: agent.clone(),
+        database_url: config.database_url.clone(),
+        classifier: config.classifier.clone(),
+        rules,
+        base_currency: config.base_currency.clone(),
+    };
+    let sessions = chat::SessionStore::new(pool.clone());
     let chat_state = ChatState { agent, sessions };
 
     let static_router = Router::new().nest_service("/", ServeDir::new("static"));
-    let app = static_router.merge(chat_router(chat_state));
+    let app = static_router
+        .merge(chat_router(chat_state))
+        .merge(import_router(import_state));
 
     let listener = tokio::net::TcpListener::bind(&config.bind_address).await?;
     tracing::info!("chat server listening on {}", &config.bind_address);
@@ -348,15 +591,234 @@ async fn run_server(config: Config) -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-fn run_reclassify(db_path: &str, model: &str, endpoint: &str, filters: ReviewFilters) -> Result<(), Box<dyn std::error::Error>> {
+/// Reload the rules files whenever one changes. A file that fails validation
+/// is reported and the previous rules stay in effect.
+async fn watch_rules(rules: SharedRules, files: Vec<std::path::PathBuf>, pool: sqlx::SqlitePool) {
+    let mut seen = rules::last_modified(&files);
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(2));
+    loop {
+        interval.tick().await;
+        let modified = rules::last_modified(&files);
+        if modified == seen {
+            continue;
+        }
+        seen = modified;
+
+        let categories = match db::load_categories(&pool).await {
+            Ok(categories) => categories,
+            Err(e) => {
+                tracing::error!("failed to load categories for rules reload: {}", e);
+                continue;
+            }
+        };
+        match RuleSet::load(&files, &categories) {
+            Ok(loaded) => {
+                tracing::info!("reloaded rules: {} rules, {} sectors", loaded.rule_count(), loaded.sector_count());
+                rules.replace(loaded);
+            }
+            Err(e) => tracing::warn!("rules files not reloaded, keeping previous rules: {}", e),
+        }
+    }
+}
+
+fn show_config(config: &Config) {
+    match &config.source {
+        Some(path) => println!("# Config file: {}", path.display()),
+        None => println!("# Config file: none (using defaults)"),
+    }
+    // API keys only come from the environment and are never printed
+    for (role, provider) in [("chat", &config.chat), ("classifier", &config.classifier)] {
+        if let Some(var) = provider.kind.api_key_var() {
+            let state = if provider.api_key.is_some() { "set" } else { "not set" };
+            println!("# {} API key ({}): {}", role, var, state);
+        }
+    }
+    println!();
+    print!("{}", config.to_toml());
+}
+
+fn print_classifier(config: &ProviderConfig) {
+    println!("  Provider:   {}", config.kind);
+    println!("  Model:      {}", config.model);
+    println!("  Endpoint:   {}", config.endpoint.as_deref().unwrap_or("(default)"));
+}
+
+fn percent(share: Option<f64>) -> String {
+    share.map_or("-".to_string(), |s| format!("{:.1}%", s * 100.0))
+}
+
+fn run_calibration(db: &Database, command: CalibrationCommand) -> Result<(), Box<dyn std::error::Error>> {
+    let entries = match command {
+        CalibrationCommand::Show => db.calibration()?,
+        CalibrationCommand::Fit => db.fit_calibration()?,
+    };
+    if entries.is_empty() {
+        println!("No reviews yet; confidence is taken as the classifier reports it.");
+        return Ok(());
+    }
+    println!("Confidence calibration from reviews (review below {:.2})", calibration::REVIEW_THRESHOLD);
+    for e in &entries {
+        let label = match &e.category {
+            Some(category) => format!("  {}", category),
+            None => format!("{} (all categories)", e.source),
+        };
+        println!(
+            "  {:<28} {:>4}/{:<4} kept  mean {:.2}  x{:.2}  (0.90 -> {:.2})",
+            label, e.correct, e.reviews, e.mean_confidence, e.factor, e.calibrate(0.9)
+        );
+    }
+    Ok(())
+}
+
+fn run_evaluate(csv_path: &Path, labels_path: &Path, threshold: f64, use_cache: bool, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let labels = evaluation::load_labels(labels_path)?;
+    let db_path = Path::new(config.database_url.as_str());
+    println!("UBS Transaction Categoriser (Evaluate)");
+    println!("  Input:      {}", csv_path.display());
+    println!("  Labels:     {}", labels_path.display());
+    println!("  Database:   {} (read only{})", db_path.display(), if use_cache { "" } else { ", cache ignored" });
+    print_classifier(&config.classifier);
+    println!();
+
+    // The real database only supplies categories, few-shot examples and cached
+    // merchants; without one the defaults are used
+    let db = if db_path.exists() { Database::open_read_only(db_path)? } else { Database::open(Path::new(":memory:"))? };
+    let classifier = Classifier::from_config(&config.classifier)?;
+    let categories = db.list_categories()?;
+    let rules = RuleSet::load(&config.rules_files, &categories)?;
+    let examples = db.get_few_shot_examples()?;
+    let (csv_format, transactions) = csv_parser::parse_csv(csv_path)?;
+
+    // Labelled rows are classified as an import would: in rounds, with the
+    // merchants classified earlier in this run cached
+    let mut run_cache: HashMap<String, ClassificationResult> = HashMap::new();
+    let mut rounds = ClassifyRounds::new(csv_format);
+    let mut stats = ImportStats::default();
+    let mut classified = Vec::new();
+    let mut unlabelled = 0;
+    for (i, tx) in transactions.iter().enumerate() {
+        if labels.contains_key(&tx.transaction_id) {
+            rounds.plan(i, tx, &rules, &mut stats, |key| match run_cache.get(key) {
+                Some(res) => Ok(Some(res.clone())),
+                None if use_cache => db.cache_lookup(key),
+                None => Ok(None),
+            })?;
+        } else {
+            unlabelled += 1;
+        }
+        if rounds.is_full() || i + 1 == transactions.len() {
+            let (rows, answers) = rounds.classify(&classifier, &examples, &categories, &mut stats);
+            run_cache.extend(answers);
+            classified.extend(rows);
+        }
+    }
+
+    // Confidence is judged after calibration, as import stores it
+    let calibration = db.calibration()?;
+    let mut sources: BTreeMap<String, usize> = BTreeMap::new();
+    let mut found = HashSet::new();
+    let mut outcomes = Vec::new();
+    for RoundRow { tx, mut result, .. } in classified {
+        result.confidence = calibration::calibrated_confidence(&calibration, &result.source, &result.category, result.confidence);
+        *sources.entry(result.source.clone()).or_insert(0) += 1;
+        found.insert(&tx.transaction_id);
+        outcomes.push(evaluation::Outcome {
+            transaction_id: tx.transaction_id.clone(),
+            description: tx.description.clone(),
+            expected: labels[&tx.transaction_id].clone(),
+            predicted: result,
+        });
+    }
+    let missing = labels.len() - found.len();
+
+    if outcomes.is_empty() {
+        println!("No labelled transactions in {}.", csv_path.display());
+        return Ok(());
+    }
+    let eval = evaluation::Evaluation::new(outcomes, threshold);
+    let total = eval.outcomes.len();
+    let accepted = eval.outcomes.iter().filter(|o| o.predicted.confidence >= threshold).count();
+    let correct = eval.outcomes.iter().filter(|o| o.correct()).count();
+    let sources: Vec<String> = sources.iter().map(|(s, n)| format!("{} {}", s, n)).collect();
+
+    println!("Evaluated {} labelled transactions ({})", total, sources.join(", "));
+    if unlabelled > 0 {
+        println!("  {} transactions in the file have no label and were skipped", unlabelled);
+    }
+    if missing > 0 {
+        println!("  {} labels have no transaction in the file", missing);
+    }
+    if !calibration.is_empty() {
+        println!("  Confidence is calibrated from {} reviewed sources and categories", calibration.len());
+    }
+    println!("  Accuracy:              {} ({}/{})", percent(eval.accuracy()), correct, total);
+    println!("  Auto-accept accuracy:  {} ({} at confidence >= {:.2})", percent(eval.auto_accept_accuracy()), accepted, threshold);
+    println!("  Flagging rate:         {} ({}/{})", percent(eval.flagging_rate()), total - accepted, total);
+    println!("  Merchant match rate:   {}", percent(eval.merchant_match_rate()));
+    println!("  Calibration error:     {:.3}", eval.calibration_error().unwrap_or(0.0));
+    println!("  False confidence:      {}", eval.false_confidence().len());
+
+    println!();
+    println!("{:<24} {:>8} {:>9} {:>9} {:>7}", "Category", "Expected", "Predicted", "Precision", "Recall");
+    for (category, score) in &eval.per_category {
+        println!("{:<24} {:>8} {:>9} {:>9} {:>7}", category, score.expected, score.predicted, percent(score.precision()), percent(score.recall()));
+    }
+
+    println!();
+    println!("Confusion matrix (rows expected, columns predicted)");
+    let names = eval.confusion_categories();
+    print!("{:<24}", "");
+    for i in 1..=names.len() {
+        print!(" {:>4}", i);
+    }
+    println!();
+    for (i, expected) in names.iter().enumerate() {
+        print!("{:<24}", format!("{:>2}. {}", i + 1, expected));
+        for predicted in &names {
+            match eval.confusion.get(&(expected.to_string(), predicted.to_string())) {
+                Some(count) => print!(" {:>4}", count),
+                None => print!(" {:>4}", "."),
+            }
+        }
+        println!();
+    }
+
+    println!();
+    println!("Calibration (confidence band, count, mean confidence, accuracy)");
+    for band in &eval.calibration {
+        println!("  {:.2}-{:.2} {:>6} {:>8.2} {:>8}", band.from, band.to, band.count, band.mean_confidence, percent(Some(band.accuracy)));
+    }
+
+    let false_confidence = eval.false_confidence();
+    if !false_confidence.is_empty() {
+        println!();
+        println!("False confidence (wrong but above the threshold)");
+        for o in false_confidence {
+            println!("  {} {}: {} as {} [{:.2}] via {}, expected {} ({})",
+                o.transaction_id, o.description, o.predicted.merchant, o.predicted.category, o.predicted.confidence,
+                o.predicted.source, o.expected.category, o.expected.merchant);
+        }
+    }
+    let flagged: Vec<_> = eval.misclassified().into_iter().filter(|o| o.predicted.confidence < threshold).collect();
+    if !flagged.is_empty() {
+        println!();
+        println!("Misclassified but flagged for review");
+        for o in flagged {
+            println!("  {} {}: {} [{:.2}] via {}, expected {}",
+                o.transaction_id, o.description, o.predicted.category, o.predicted.confidence, o.predicted.source, o.expected.category);
+        }
+    }
+    Ok(())
+}
+
+fn run_reclassify(db_path: &str, classifier_config: &ProviderConfig, filters: ReviewFilters) -> Result<(), Box<dyn std::error::Error>> {
     println!("UBS Transaction Categoriser (Reclassify)");
     println!("  Database:   {}", db_path);
-    println!("  Model:      {}", model);
-    println!("  Endpoint:   {}", endpoint);
+    print_classifier(classifier_config);
     println!();
 
     let db = Database::open(Path::new(db_path))?;
-    let classifier = Classifier::new(endpoint, model);
+    let classifier = Classifier::from_config(classifier_config)?;
     let examples = db.get_few_shot_examples()?;
     let categories = db.list_categories()?;
 
@@ -366,6 +828,7 @@ fn run_reclassify(db_path: &str, model: &str, endpoint: &str, filters: ReviewFil
         filters.since,
         filters.until,
         filters.merchant,
+        filters.account,
     )?;
 
     if transactions.is_empty() {
@@ -394,8 +857,10 @@ fn run_reclassify(db_path: &str, model: &str, endpoint: &str, filters: ReviewFil
             // Amount awareness: we don't have the original tx struct here, but we have amount
             let res = classifier.classify(&tx.raw_description, Some(tx.amount), "", &examples, &categories);
             
-            // Store in cache
-            db.cache_insert(&key, &res)?;
+            // Store in cache, unless the LLM gave no answer
+            if !Classifier::is_fallback(&res) {
+                db.cache_insert(&key, &res, None)?;
+            }
             res
         };
 
@@ -404,7 +869,7 @@ fn run_reclassify(db_path: &str, model: &str, endpoint: &str, filters: ReviewFil
             println!("  {} → {} (was {})", tx.raw_description, result.category, tx.category);
         }
 
-        db.update_transaction(tx.id, &result.merchant, &result.category, result.confidence, &result.source)?;
+        db.update_transaction(tx.id, &result.merchant, &result.category, result.confidence, &result.source, "reclassify")?;
     }
 
     println!("\nReclassification Summary");
@@ -416,12 +881,271 @@ fn run_reclassify(db_path: &str, model: &str, endpoint: &str, filters: ReviewFil
     Ok(())
 }
 
-fn run_import(input_path: &str, db_path: &str, model: &str, endpoint: &str) -> Result<(), Box<dyn std::error::Error>> {
+fn run_recurring(db_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let db = Database::open(Path::new(db_path))?;
+    let Some(as_of) = db.latest_transaction_date()? else {
+        println!("No transactions in database.");
+        return Ok(());
+    };
+
+    db.refresh_recurring_payments()?;
+    let payments = db.recurring_payments()?;
+
+    if payments.is_empty() {
+        println!("No recurring payments detected.");
+        return Ok(());
+    }
+
+    println!("Recurring payments (data up to {})", as_of);
+    for p in &payments {
+        let mut flags = Vec::new();
+        if let Some(prev) = p.previous_amount {
+            flags.push(format!("price up from {:.2}", prev));
+        }
+        if p.missed > 0 {
+            flags.push(format!("missed {}", p.missed));
+        }
+        println!(
+            "  {:<30} {:<10} {:>9.2} {:>10.2}/yr  last {}  next {}  {}",
+            p.merchant_name,
+            p.cadence,
+            p.typical_amount,
+            p.annual_cost(),
+            p.last_date,
+            p.next_expected,
+            flags.join(", ")
+        );
+    }
+
+    let total: f64 = payments.iter().map(|p| p.annual_cost()).sum();
+    println!();
+    println!("  Detected:           {}", payments.len());
+    println!("  Price increases:    {}", payments.iter().filter(|p| p.price_increased()).count());
+    println!("  Missed payments:    {}", payments.iter().filter(|p| p.missed > 0).count());
+    println!("  Annual cost:        {:.2}", total);
+
+    Ok(())
+}
+
+fn run_fx(db_path: &str, base_currency: &str, command: FxCommand) -> Result<(), Box<dyn std::error::Error>> {
+    let db = Database::open(Path::new(db_path))?;
+    if let FxCommand::Import { path } = &command {
+        let rates = fx::parse_rates_csv(path)?;
+        let source = path.file_name().and_then(|n| n.to_str()).unwrap_or("unknown");
+        let count = db.import_fx_rates(&rates, source)?;
+        println!("Imported {} rates from {}", count, path.display());
+    }
+
+    let series = db.list_fx_series()?;
+    if series.is_empty() {
+        println!("No FX rates stored.");
+    }
+    for s in &series {
+        println!("- {}/{}: {} rates, {} to {}", s.base, s.quote, s.count, s.first_date, s.last_date);
+    }
+
+    let missing = db.refresh_base_rates(base_currency)?;
+    if missing.is_empty() {
+        println!("All transactions convert to {}.", base_currency);
+    }
+    for (currency, count) in &missing {
+        println!("Missing: {} {} transactions have no rate to {}", count, currency, base_currency);
+    }
+    Ok(())
+}
+
+fn run_rollback(db_path: &str, batch: &str, revert_cache: bool, dry_run: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let db = Database::open(Path::new(db_path))?;
+    let plan = db.plan_rollback(batch)?;
+    if plan.transactions == 0 && plan.statement_balances == 0 && plan.cache_entries == 0 {
+        println!("Nothing imported from '{}'. See `imports status` for the file names.", batch);
+        return Ok(());
+    }
+    let summary = if dry_run { plan } else { db.rollback_import(batch, revert_cache)? };
+    let verb = if dry_run { "Would remove" } else { "Removed" };
+    println!(
+        "{} {} transactions from {} ({} to {}), {} of them Uncategorised.",
+        verb,
+        summary.transactions,
+        batch,
+        summary.first_date.as_deref().unwrap_or("-"),
+        summary.last_date.as_deref().unwrap_or("-"),
+        summary.uncategorised
+    );
+    if summary.splits > 0 {
+        println!("  {} split parts", summary.splits);
+    }
+    if summary.statement_balances > 0 {
+        println!("  {} reported statement balances", summary.statement_balances);
+    }
+    if summary.transfers_unlinked > 0 {
+        println!("  {} transfers in other imports are unlinked and get back their earlier category", summary.transfers_unlinked);
+    }
+    if revert_cache || dry_run {
+        println!("  {} merchant cache entries from this import{}", summary.cache_entries, if revert_cache { "" } else { " (kept unless --revert-cache)" });
+    }
+    if dry_run {
+        println!("Dry run: nothing changed.");
+    }
+    Ok(())
+}
+
+fn run_import_status(db_path: &str, account: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let db = Database::open(Path::new(db_path))?;
+    let status = db.import_status(account)?;
+    let interrupted = db.interrupted_imports()?;
+    if status.accounts.is_empty() && interrupted.is_empty() {
+        println!("No imports logged.");
+        return Ok(());
+    }
+
+    for coverage in &status.accounts {
+        let label = coverage.account.as_ref().map_or("Files without an account".to_string(), |a| a.label());
+        println!("{}", label);
+        for import in &coverage.imports {
+            let period = import.period.map_or("period unknown".to_string(), |(from, to)| format!("{} to {}", from, to));
+            println!("  #{:<4} {:<32} {:<26} {:>5} new  {}", import.id, import.filename, period, import.row_count, &import.imported_at[..10.min(import.imported_at.len())]);
+        }
+        for (from, to) in &coverage.covered {
+            println!("  covered {} to {}", from, to);
+        }
+        for issue in &coverage.issues {
+            println!("  ! {}", issue);
+        }
+        println!();
+    }
+    for issue in &status.renamed {
+        println!("! {}", issue);
+    }
+    for import in &interrupted {
+        println!(
+            "! The import of {} started {} stopped after {} rows; continue it with `import --resume` or remove it with `imports rollback {}`",
+            import.filename, &import.started_at[..10.min(import.started_at.len())], import.rows_done, import.filename
+        );
+    }
+    Ok(())
+}
+
+fn run_balances(db_path: &str, base_currency: &str, command: BalancesCommand) -> Result<(), Box<dyn std::error::Error>> {
+    let db = Database::open(Path::new(db_path))?;
+    let history = db.balance_history(base_currency, None)?;
+    if history.accounts.is_empty() {
+        println!("No account balances yet; import a statement first.");
+        return Ok(());
+    }
+
+    match command {
+        BalancesCommand::List => {
+            let month = history.months.last().map(|m| m.format("%Y-%m").to_string()).unwrap_or_default();
+            println!("Balances at the end of {}:", month);
+            for a in &history.accounts {
+                let balance = a.balances.last().copied().unwrap_or(0.0);
+                let note = if a.anchored { "" } else { "  (no reported balance, counted from zero)" };
+                println!("  {:<40} {:>12.2} {}{}", a.account.label(), balance, a.currency, note);
+            }
+            println!("  {:<40} {:>12.2} {}", "Net worth", history.net_worth.last().copied().unwrap_or(0.0), base_currency);
+            for currency in &history.unconverted {
+                println!("Warning: no {} to {} rate; counted unconverted.", currency, base_currency);
+            }
+        }
+        BalancesCommand::Check => {
+            let mut gaps = 0;
+            for a in &history.accounts {
+                for gap in &a.gaps {
+                    gaps += 1;
+                    println!(
+                        "{}: from {} to {} the balance changed by {:.2} but the transactions add up to {:.2} ({:.2} {} unaccounted)",
+                        a.account.label(), gap.from, gap.to, gap.reported_change, gap.booked_change, gap.missing(), a.currency
+                    );
+                }
+            }
+            if gaps == 0 {
+                println!("All reported balances match the imported transactions.");
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_splits(db_path: &str, command: SplitsCommand) -> Result<(), Box<dyn std::error::Error>> {
+    let db = Database::open(Path::new(db_path))?;
+    let id = match command {
+        SplitsCommand::Find { search, limit } => {
+            let found = db.find_transactions(&search, limit)?;
+            if found.is_empty() {
+                println!("No transactions match '{}'.", search);
+            }
+            for tx in found {
+                println!("#{:<6} {}  {:>10.2} {}  {} [{}]", tx.id, tx.date, tx.amount, tx.currency, tx.merchant_name, tx.category);
+            }
+            return Ok(());
+        }
+        SplitsCommand::Show { id } => id,
+        SplitsCommand::Set { id, parts } => {
+            let Some(tx) = db.get_transaction(id)? else {
+                println!("Error: no transaction with id {}.", id);
+                return Ok(());
+            };
+            match splits::resolve_splits(tx.amount, &parts, &db.list_categories()?) {
+                Ok(splits) => db.set_splits(id, &splits, "splits")?,
+                Err(e) => {
+                    println!("Error: {}.", e);
+                    return Ok(());
+                }
+            }
+            id
+        }
+        SplitsCommand::Clear { id } => {
+            db.set_splits(id, &[], "splits")?;
+            id
+        }
+    };
+
+    let Some(tx) = db.get_transaction(id)? else {
+        println!("Error: no transaction with id {}.", id);
+        return Ok(());
+    };
+    println!("#{} {}  {:.2} {}  {}", tx.id, tx.date, tx.amount, tx.currency, tx.merchant_name);
+    println!("  {}", tx.raw_description);
+    let parts = db.get_splits(id)?;
+    if parts.is_empty() {
+        println!("  Not split, counted as {}", tx.category);
+    }
+    for part in parts {
+        println!("  {:>10.2}  {}", part.amount, part.category);
+    }
+    Ok(())
+}
+
+fn run_history(db_path: &str, id: i64) -> Result<(), Box<dyn std::error::Error>> {
+    let db = Database::open(Path::new(db_path))?;
+    let (Some(tx), Some(current)) = (db.get_transaction(id)?, db.current_classification(id)?) else {
+        println!("Error: no transaction with id {}.", id);
+        return Ok(());
+    };
+    let entries = db.transaction_history(id)?;
+    println!("#{} {}  {:.2} {}  {}", tx.id, tx.date, tx.amount, tx.currency, tx.raw_description);
+    println!("  Imported as  {}", history::describe_classification(history::original(&entries, &current)));
+    for entry in &entries {
+        println!("  {}  {:<24} {}", &entry.changed_at[..19.min(entry.changed_at.len())], entry.changed_by, entry.describe());
+    }
+    if entries.is_empty() {
+        println!("  Not changed since import.");
+    } else {
+        println!("  Now          {}", history::describe_classification(&current));
+    }
+    Ok(())
+}
+
+fn run_import(input_path: &Path, resume: bool, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let db_path = config.database_url.as_str();
     println!("UBS Transaction Categoriser (Import)");
-    println!("  Input:      {}", input_path);
+    println!("  Input:      {}", input_path.display());
     println!("  Database:   {}", db_path);
-    println!("  Model:      {}", model);
-    println!("  Endpoint:   {}", endpoint);
+    print_classifier(&config.classifier);
+    for path in &config.rules_files {
+        println!("  Rules:      {}", path.display());
+    }
     println!();
 
     // Ensure data directory exists
@@ -431,8 +1155,9 @@ fn run_import(input_path: &str, db_path: &str, model: &str, endpoint: &str) -> R
 
     // Open database
     let db = Database::open(Path::new(db_path))?;
-    let classifier = Classifier::new(endpoint, model);
+    let classifier = Classifier::from_config(&config.classifier)?;
     let categories = db.list_categories()?;
+    let rules = RuleSet::load(&config.rules_files, &categories)?;
 
     let metadata = std::fs::metadata(input_path)?;
     let mut files = Vec::new();
@@ -447,11 +1172,11 @@ fn run_import(input_path: &str, db_path: &str, model: &str, endpoint: &str) -> R
         }
         files.sort();
     } else {
-        files.push(Path::new(input_path).to_path_buf());
+        files.push(input_path.to_path_buf());
     }
 
     if files.is_empty() {
-        println!("No CSV files found at: {}", input_path);
+        println!("No CSV files found at: {}", input_path.display());
         return Ok(());
     }
 
@@ -460,21 +1185,36 @@ fn run_import(input_path: &str, db_path: &str, model: &str, endpoint: &str) -> R
 
     for (i, file_path) in files.iter().enumerate() {
         println!("Importing file {}/{}: {}", i + 1, total_files, file_path.display());
-        let file_stats = import_file(&db, &classifier, file_path, &categories)?;
-        
-        println!("  File Summary: {} parsed, {} new, {} skipped, {} rules hits, {} cache hits, {} llm calls",
+        let file_stats = import_file(&db, &classifier, &rules, file_path, &categories, resume)?;
+        if file_stats.resumed_rows > 0 {
+            println!("  Resumed after row {}", file_stats.resumed_rows);
+        }
+
+        println!("  File Summary: {} parsed, {} new, {} skipped, {} rules hits, {} cache hits, {} llm calls, {} transfers matched, {} balance gaps, {} need review",
             file_stats.total_parsed,
             file_stats.new_insertions,
             file_stats.duplicates_skipped,
             file_stats.rules_hits,
             file_stats.cache_hits,
-            file_stats.llm_calls
+            file_stats.llm_calls,
+            file_stats.transfers_matched,
+            file_stats.balance_gaps,
+            file_stats.flagged
         );
         println!();
         
         overall_stats.accumulate(&file_stats);
     }
 
+    let missing = db.refresh_base_rates(&config.base_currency)?;
+    for (currency, count) in &missing {
+        println!("Warning: {} {} transactions have no FX rate to {}. Import rates with `fx import`.", count, currency, config.base_currency);
+    }
+
+    if overall_stats.balance_gaps > 0 {
+        println!("Warning: reported balances do not match the imported transactions; run `balances check` to see where.");
+    }
+
     if total_files > 1 {
         println!("Overall Summary ({} files)", total_files);
         println!("  Total parsed:       {}", overall_stats.total_parsed);
@@ -483,6 +1223,9 @@ fn run_import(input_path: &str, db_path: &str, model: &str, endpoint: &str) -> R
         println!("  Total rules hits:   {}", overall_stats.rules_hits);
         println!("  Total cache hits:   {}", overall_stats.cache_hits);
         println!("  Total LLM calls:    {}", overall_stats.llm_calls);
+        println!("  Total transfers:    {}", overall_stats.transfers_matched);
+        println!("  Balance gaps:       {}", overall_stats.balance_gaps);
+        println!("  Need review:        {}", overall_stats.flagged);
     } else if total_files == 1 {
         println!("Import Complete");
         println!("  Total parsed:       {}", overall_stats.total_parsed);
@@ -491,6 +1234,9 @@ fn run_import(input_path: &str, db_path: &str, model: &str, endpoint: &str) -> R
         println!("  Rules hits:         {}", overall_stats.rules_hits);
         println!("  Cache hits:         {}", overall_stats.cache_hits);
         println!("  LLM calls:          {}", overall_stats.llm_calls);
+        println!("  Transfers matched:  {}", overall_stats.transfers_matched);
+        println!("  Balance gaps:       {}", overall_stats.balance_gaps);
+        println!("  Need review:        {}", overall_stats.flagged);
     }
 
     Ok(())
@@ -499,6 +1245,8 @@ fn run_import(input_path: &str, db_path: &str, model: &str, endpoint: &str) -> R
 #[cfg(test)]
 mod integration_tests {
     use super::*;
+    use std::sync::Arc;
+    use ai::ollama::OllamaProvider;
     use tempfile::tempdir;
 
     #[test]
@@ -509,12 +1257,12 @@ mod integration_tests {
         let cats = db.list_categories()?;
         
         // Unreachable endpoint so any LLM call fails fast
-        let classifier = Classifier::new("http://127.0.0.1:1", "dummy-model");
+        let classifier = Classifier::new(Arc::new(OllamaProvider::new("http://127.0.0.1:1", "dummy-model")));
         
         let csv_path = Path::new("tests/fixtures/credit_card_tiny.csv");
         if !csv_path.exists() { return Ok(()); }
 
-        let stats = import_file(&db, &classifier, csv_path, &cats)?;
+        let stats = import_file(&db, &classifier, &RuleSet::builtin(), csv_path, &cats, false)?;
         
         assert_eq!(stats.llm_calls, 0, "Should have 0 LLM calls");
         assert!(stats.rules_hits > 0);
@@ -544,26 +1292,366 @@ mod integration_tests {
         assert_eq!(get_cat("SBB Mobile")?, "Transport");
         assert_eq!(get_cat("L.H.")?, "Children");
 
+        // Foreign card spend keeps the charged amount and the card issuer's rate
+        let (amount, original_amount, original_currency, fx_rate): (f64, f64, String, f64) = conn.query_row(
+            "SELECT amount, original_amount, original_currency, fx_rate FROM transactions WHERE raw_description LIKE 'DB BAHN%'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )?;
+        assert_eq!((amount, original_amount, original_currency.as_str(), fx_rate), (-48.06, 50.0, "EUR", 0.9612));
+        let domestic: Option<String> = conn.query_row(
+            "SELECT original_currency FROM transactions WHERE raw_description LIKE 'MIGROS%'",
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!(domestic, None);
+
+        // Each cardholder on the invoice is tracked as their own card
+        let accounts = db.list_accounts()?;
+        let holders: Vec<(&str, Option<&str>, i64)> = accounts
+            .iter()
+            .map(|a| (a.kind.as_str(), a.holder.as_deref(), a.transactions))
+            .collect();
+        assert_eq!(holders, vec![("card", Some("OTHER USER"), 1), ("card", Some("TEST USER"), 5)]);
+        assert!(accounts.iter().all(|a| a.number == "1234 5678 9012 3456"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_reports_progress_per_new_transaction() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let db = Database::open(&dir.path().join("progress.db"))?;
+        let cats = db.list_categories()?;
+        let classifier = Classifier::new(Arc::new(OllamaProvider::new("http://127.0.0.1:1", "dummy-model")));
+        let rules = RuleSet::builtin();
+        let csv_path = Path::new("tests/fixtures/credit_card_tiny.csv");
+        if !csv_path.exists() { return Ok(()); }
+
+        let mut events = Vec::new();
+        let stats = import_file_with_progress(&db, &classifier, &rules, csv_path, &cats, false, |p| events.push(p))?;
+        assert_eq!(events.len(), stats.new_insertions);
+        assert!(events.iter().all(|p| p.source == "rules" && p.total == stats.total_parsed));
+
+        // Re-importing the same file only skips duplicates and reports nothing
+        let mut events = Vec::new();
+        let stats = import_file_with_progress(&db, &classifier, &rules, csv_path, &cats, false, |p| events.push(p))?;
+        assert!(events.is_empty());
+        assert_eq!(stats.duplicates_skipped, stats.total_parsed);
+
         Ok(())
     }
 
     #[test]
-    fn test_import_account_statement_falls_to_llm() -> Result<(), Box<dyn std::error::Error>> {
+    fn test_import_account_statement_uses_rules_then_llm() -> Result<(), Box<dyn std::error::Error>> {
         let dir = tempdir()?;
         let db_path = dir.path().join("test_as.db");
         let db = Database::open(&db_path)?;
         let cats = db.list_categories()?;
-        let classifier = Classifier::new("http://127.0.0.1:1", "dummy-model");
+        let classifier = Classifier::new(Arc::new(OllamaProvider::new("http://127.0.0.1:1", "dummy-model")));
         let csv_path = Path::new("tests/fixtures/account_statement_tiny.csv");
         if !csv_path.exists() { return Ok(()); }
 
-        let stats = import_file(&db, &classifier, csv_path, &cats)?;
-        
-        // The import succeeds, but because it's an account statement, it shouldn't use rules.
-        // It will attempt to call the LLM (and fallback to Uncategorised).
-        assert_eq!(stats.rules_hits, 0, "Account statements should not use rules");
-        assert!(stats.llm_calls > 0, "Account statements should fall back to LLM");
-        
+        // The tax e-bill is matched by its IBAN; the TWINT payment has no rule
+        let rules_path = dir.path().join("rules.toml");
+        std::fs::write(&rules_path, "[[rules]]\niban = 'CH94 3000 0006 7000 0187 9'\nmerchant = 'Steuerverwaltung'\ncategory = 'Fees'\n")?;
+        let rules = RuleSet::load(&[rules_path], &cats)?;
+
+        let stats = import_file(&db, &classifier, &rules, csv_path, &cats, false)?;
+        assert_eq!(stats.rules_hits, 1, "Account statements should use rules");
+        assert_eq!(stats.llm_calls, 1, "Unmatched rows should fall back to LLM");
+
+        let conn = rusqlite::Connection::open(&db_path)?;
+        let (merchant, source): (String, String) = conn.query_row(
+            "SELECT merchant_name, source FROM transactions WHERE raw_description LIKE 'Steuerverwaltung%'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        assert_eq!(merchant, "Steuerverwaltung");
+        assert_eq!(source, "rules");
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_logs_statement_period_accounts_and_hash() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let db = Database::open(&dir.path().join("import_log.db"))?;
+        let cats = db.list_categories()?;
+        let classifier = Classifier::new(Arc::new(OllamaProvider::new("http://127.0.0.1:1", "dummy-model")));
+        let csv_path = Path::new("tests/fixtures/account_statement_tiny.csv");
+        if !csv_path.exists() { return Ok(()); }
+
+        // Rules for both rows, so the LLM is not needed
+        let rules_path = dir.path().join("rules.toml");
+        std::fs::write(&rules_path, "[[rules]]\niban = 'CH94 3000 0006 7000 0187 9'\ncategory = 'Fees'\n\n[[rules]]\ndescription = 'SBB MOBILE'\ncategory = 'Transport'\n")?;
+        let rules = RuleSet::load(&[rules_path], &cats)?;
+        let stats = import_file(&db, &classifier, &rules, csv_path, &cats, false)?;
+        assert_eq!((stats.rules_hits, stats.llm_calls), (2, 0));
+        assert_eq!(stats.balance_gaps, 1, "the excerpt does not reconcile, see db::balances");
+
+        // The import log keeps the statement's own period rather than the
+        // bookings', the account it covers and the file's hash
+        let records = db.import_records()?;
+        let (record, account_ids) = &records[0];
+        let date = |s: &str| chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        assert_eq!(record.period, Some((date("2026-01-01"), date("2026-02-15"))));
+        assert!(record.period_exact);
+        assert_eq!(account_ids, &db.list_accounts()?.iter().map(|a| a.id).collect::<Vec<_>>());
+        assert_eq!(record.file_hash.as_deref(), Some(coverage::file_hash(csv_path)?.as_str()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_corrections_calibrate_the_review_queue() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let db_path = dir.path().join("calibration.db");
+        let db = Database::open(&db_path)?;
+        let conn = rusqlite::Connection::open(&db_path)?;
+        // The LLM said 0.90 for every one; reviews corrected 8 of the 10 Shopping answers
+        for i in 0..14 {
+            let category = if i < 12 { "Shopping" } else { "Transport" };
+            conn.execute(
+                "INSERT INTO transactions (date, raw_description, amount, currency, merchant_name, category, source, confidence, transaction_id, import_batch, created_at)
+                 VALUES ('2026-03-01', ?, -10.0, 'CHF', 'Shop', ?, 'llm', 0.9, ?, 'mar.csv', '')",
+                rusqlite::params![format!("SHOP {}", i), category, format!("t{}", i)],
+            )?;
+        }
+        for id in 1..=10 {
+            let category = if id <= 8 { "Household" } else { "Shopping" };
+            db.apply_manual_classification(&format!("SHOP {}", id - 1), "SHOP", "Shop", category, &[id], "review")?;
+        }
+        let flagged = |db: &Database| -> Result<Vec<i64>, rusqlite::Error> {
+            Ok(db.get_flagged_transactions(calibration::REVIEW_THRESHOLD, None, None, None, None, None)?.iter().map(|t| t.id).collect())
+        };
+        assert!(flagged(&db)?.is_empty(), "0.90 is above the threshold until calibrated");
+
+        let entries = db.fit_calibration()?;
+        assert_eq!(entries.len(), 2, "llm overall and llm Shopping");
+        assert_eq!(db.calibration()?, entries);
+        assert_eq!(flagged(&db)?, vec![11, 12, 13, 14], "the remaining llm answers are now doubted");
+        assert_eq!(db.calibrate_batch("mar.csv", calibration::REVIEW_THRESHOLD)?, 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rollback_removes_batch_and_its_llm_cache_entries() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let db_path = dir.path().join("rollback.db");
+        let db = Database::open(&db_path)?;
+        let cats = db.list_categories()?;
+        let classifier = Classifier::new(Arc::new(OllamaProvider::new("http://127.0.0.1:1", "dummy-model")));
+        let csv_path = Path::new("tests/fixtures/account_statement_tiny.csv");
+        if !csv_path.exists() { return Ok(()); }
+
+        // With the LLM down, both rows fall back to Uncategorised, which is not
+        // cached; stand in an answer the import did get
+        let first = import_file(&db, &classifier, &RuleSet::builtin(), csv_path, &cats, false)?;
+        assert_eq!(first.llm_calls, 2);
+        assert_eq!(db.plan_rollback("account_statement_tiny.csv")?.cache_entries, 0);
+        let answer = ClassificationResult { merchant: "Coop".to_string(), category: "Groceries".to_string(), confidence: 0.9, source: "llm".to_string() };
+        db.cache_insert("COOP", &answer, Some("account_statement_tiny.csv"))?;
+
+        let plan = db.plan_rollback("account_statement_tiny.csv")?;
+        assert_eq!((plan.transactions, plan.uncategorised, plan.cache_entries, plan.statement_balances), (2, 2, 1, 2));
+        assert_eq!(db.plan_rollback("account_statement_tiny.csv")?, plan, "a preview changes nothing");
+
+        let done = db.rollback_import("account_statement_tiny.csv", true)?;
+        assert_eq!(done, plan);
+        assert!(db.import_status(None)?.accounts.is_empty());
+        let conn = rusqlite::Connection::open(&db_path)?;
+        let logged: (i64, i64) = conn.query_row(
+            "SELECT (SELECT COUNT(*) FROM import_rollbacks), (SELECT COUNT(*) FROM import_log WHERE rolled_back_at IS NOT NULL)",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        assert_eq!(logged, (1, 1));
+
+        assert!(db.cache_lookup("COOP")?.is_none(), "the import's cache entries go with it");
+        let again = import_file(&db, &classifier, &RuleSet::builtin(), csv_path, &cats, false)?;
+        assert_eq!((again.new_insertions, again.cache_hits, again.llm_calls), (2, 0, 2));
+        Ok(())
+    }
+
+    #[test]
+    fn test_rollback_restores_category_of_transfer_counterpart() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let db_path = dir.path().join("rollback_transfers.db");
+        let db = Database::open(&db_path)?;
+        let cats = db.list_categories()?;
+        let classifier = Classifier::new(Arc::new(OllamaProvider::new("http://127.0.0.1:1", "dummy-model")));
+        let header = "trade_date,booking_date,value_date,currency,debit,credit,balance,transaction_id,description,details,footnotes\n";
+        let checking = dir.path().join("checking.csv");
+        std::fs::write(&checking, format!("{}02.03.2026,02.03.2026,02.03.2026,CHF,500.00,,1000.00,c1,Transfer to savings,,\n", header))?;
+        let savings = dir.path().join("savings.csv");
+        std::fs::write(&savings, format!("{}03.03.2026,03.03.2026,03.03.2026,CHF,,500.00,2500.00,s1,Transfer from checking,,\n", header))?;
+
+        import_file(&db, &classifier, &RuleSet::builtin(), &checking, &cats, false)?;
+        let paired = import_file(&db, &classifier, &RuleSet::builtin(), &savings, &cats, false)?;
+        assert_eq!(paired.transfers_matched, 1);
+
+        let done = db.rollback_import("savings.csv", false)?;
+        assert_eq!(done.transfers_unlinked, 1);
+        let conn = rusqlite::Connection::open(&db_path)?;
+        let (id, transfer_of, category): (i64, Option<i64>, String) = conn.query_row(
+            "SELECT id, transfer_of, category FROM transactions WHERE transaction_id = 'c1'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+        assert_eq!((transfer_of, category.as_str()), (None, "Uncategorised"));
+        let logged: (String, String) = conn.query_row(
+            "SELECT before_category, after_category FROM transaction_history WHERE tx_id = ? AND changed_by = 'rollback'",
+            [id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        assert_eq!(logged, ("Transfers".to_string(), "Uncategorised".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_interrupted_import_resumes_from_checkpoint() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let db_path = dir.path().join("resume.db");
+        let db = Database::open(&db_path)?;
+        let cats = db.list_categories()?;
+        let classifier = Classifier::new(Arc::new(OllamaProvider::new("http://127.0.0.1:1", "dummy-model"))).with_concurrency(CLASSIFY_ROUND);
+        // 40 new merchants: one full round, then 8 more
+        let header = "trade_date,booking_date,value_date,currency,debit,credit,balance,transaction_id,description,details,footnotes\n";
+        let csv: String = (0..40u8)
+            .map(|i| {
+                let name = format!("{}{}", (b'A' + i / 26) as char, (b'A' + i % 26) as char);
+                format!("02.03.2026,02.03.2026,02.03.2026,CHF,5.00,,100.00,r{},SHOP {},,\n", i, name)
+            })
+            .collect();
+        let path = dir.path().join("resume.csv");
+        std::fs::write(&path, format!("{}{}", header, csv))?;
+
+        // The process dies while the second round is being written
+        let crashed = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            import_file_with_progress(&db, &classifier, &RuleSet::builtin(), &path, &cats, false, |p| {
+                assert!(p.index <= CLASSIFY_ROUND, "simulated crash");
+            })
+        }));
+        assert!(crashed.is_err());
+        let interrupted = db.interrupted_import("resume.csv")?.expect("import logged as in progress");
+        assert_eq!((interrupted.rows_done, interrupted.row_count), (32, 32));
+        assert!(db.import_status(None)?.accounts.is_empty(), "an unfinished import covers nothing yet");
+
+        let refused = import_file(&db, &classifier, &RuleSet::builtin(), &path, &cats, false);
+        assert!(refused.unwrap_err().to_string().contains("--resume"));
+
+        let mut resumed_rows = Vec::new();
+        let stats = import_file_with_progress(&db, &classifier, &RuleSet::builtin(), &path, &cats, true, |p| {
+            resumed_rows.push(p.index);
+        })?;
+        assert_eq!(resumed_rows, (33..=40).collect::<Vec<_>>());
+        assert_eq!((stats.new_insertions, stats.resumed_rows, stats.llm_calls, stats.duplicates_skipped), (40, 32, 8, 0));
+
+        assert!(db.interrupted_import("resume.csv")?.is_none());
+        let records = db.import_records()?;
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].0.row_count, 40);
+        let conn = rusqlite::Connection::open(&db_path)?;
+        let stored: i64 = conn.query_row("SELECT COUNT(*) FROM transactions", [], |row| row.get(0))?;
+        assert_eq!(stored, 40);
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_asks_once_per_new_merchant() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let db = Database::open(&dir.path().join("rounds.db"))?;
+        let cats = db.list_categories()?;
+        let classifier = Classifier::new(Arc::new(OllamaProvider::new("http://127.0.0.1:1", "dummy-model"))).with_concurrency(4);
+        let header = "trade_date,booking_date,value_date,currency,debit,credit,balance,transaction_id,description,details,footnotes\n";
+        let rows = [
+            ("k1", "KIOSK ZUERICH 0001"),
+            ("b1", "BAECKEREI HUG"),
+            ("k2", "KIOSK ZUERICH 0002"),
+            ("k2", "KIOSK ZUERICH 0002"),
+            ("b2", "BAECKEREI HUG"),
+        ];
+        let csv: String = rows
+            .iter()
+            .map(|(id, desc)| format!("02.03.2026,02.03.2026,02.03.2026,CHF,5.00,,100.00,{},{},,\n", id, desc))
+            .collect();
+        let path = dir.path().join("march.csv");
+        std::fs::write(&path, format!("{}{}", header, csv))?;
+
+        let mut order = Vec::new();
+        let stats = import_file_with_progress(&db, &classifier, &RuleSet::builtin(), &path, &cats, false, |p| {
+            order.push((p.index, p.source));
+        })?;
+        assert_eq!(
+            (stats.new_insertions, stats.duplicates_skipped, stats.llm_calls, stats.cache_hits),
+            (4, 1, 2, 2)
+        );
+        let expected: Vec<(usize, String)> = [(1, "llm"), (2, "llm"), (3, "cache"), (5, "cache")]
+            .iter()
+            .map(|(i, s)| (*i, s.to_string()))
+            .collect();
+        assert_eq!(order, expected, "reported in file order");
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_links_transfers_between_files() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let db_path = dir.path().join("transfers.db");
+        let db = Database::open(&db_path)?;
+        let cats = db.list_categories()?;
+        let classifier = Classifier::new(Arc::new(OllamaProvider::new("http://127.0.0.1:1", "dummy-model")));
+        let header = "trade_date,booking_date,value_date,currency,debit,credit,balance,transaction_id,description,details,footnotes\n";
+
+        // Besides the transfer: a purchase a friend paid back, and a transfer the
+        // user had already classified by hand
+        let checking = dir.path().join("checking.csv");
+        std::fs::write(&checking, format!(
+            "{}02.03.2026,02.03.2026,02.03.2026,CHF,500.00,,1000.00,c1,Transfer to savings,,\n\
+             02.03.2026,02.03.2026,02.03.2026,CHF,42.00,,958.00,c2,DIGITEC GALAXUS,,\n\
+             02.03.2026,02.03.2026,02.03.2026,CHF,300.00,,658.00,c3,Transfer to savings,,\n",
+            header
+        ))?;
+        let savings = dir.path().join("savings.csv");
+        std::fs::write(&savings, format!(
+            "{}03.03.2026,03.03.2026,03.03.2026,CHF,,500.00,2500.00,s1,Transfer from checking,,\n\
+             03.03.2026,03.03.2026,03.03.2026,CHF,,42.00,2542.00,s2,Max Muster,,\n\
+             03.03.2026,03.03.2026,03.03.2026,CHF,,300.00,2842.00,s3,Transfer from checking,,\n",
+            header
+        ))?;
+
+        let first = import_file(&db, &classifier, &RuleSet::builtin(), &checking, &cats, false)?;
+        assert_eq!(first.transfers_matched, 0, "nothing to pair with yet");
+        let conn = rusqlite::Connection::open(&db_path)?;
+        let id_of = |transaction_id: &str| -> rusqlite::Result<i64> {
+            conn.query_row("SELECT id FROM transactions WHERE transaction_id = ?", [transaction_id], |row| row.get(0))
+        };
+        db.update_transaction(id_of("c3")?, "Landlord", "Housing", 1.0, "manual", "review")?;
+        let second = import_file(&db, &classifier, &RuleSet::builtin(), &savings, &cats, false)?;
+        assert_eq!(second.transfers_matched, 1);
+
+        let linked: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM transactions WHERE transfer_of IS NOT NULL AND category = 'Transfers'",
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!(linked, 2);
+        assert_eq!(db.list_transfers()?.len(), 1);
+        let logged: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM transaction_history WHERE changed_by = 'transfers' AND note LIKE 'paired with #%'",
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!(logged, 2);
+        assert_eq!(db.current_classification(id_of("c3")?)?.unwrap().category, "Housing", "manual rows are not paired");
+
+        // Unlinking restores both categories and keeps the pair apart
+        assert_eq!(db.unlink_transfer(id_of("s1")?)?, Some(id_of("c1")?));
+        assert!(db.list_transfers()?.is_empty());
+        for side in ["c1", "s1"] {
+            assert_eq!(db.current_classification(id_of(side)?)?.unwrap().category, "Uncategorised");
+        }
+        assert_eq!(db.match_transfers(transfers::TRANSFER_WINDOW_DAYS)?, 0);
+        assert_eq!(db.unlink_transfer(id_of("s1")?)?, None);
         Ok(())
     }
 }
@@ -0,0 +1,310 @@
+use std::collections::HashMap;
+use std::fmt;
+use chrono::NaiveDate;
+
+use crate::cache::normalise_merchant_key;
+
+/// A spending transaction considered for recurring-payment detection.
+#[derive(Debug, Clone)]
+pub struct ChargeRow {
+    pub raw_description: String,
+    pub merchant_name: String,
+    pub category: String,
+    pub date: NaiveDate,
+    /// Positive amount spent.
+    pub amount: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cadence {
+    Weekly,
+    Monthly,
+    Quarterly,
+    Annual,
+}
+
+impl fmt::Display for Cadence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cadence::Weekly => write!(f, "weekly"),
+            Cadence::Monthly => write!(f, "monthly"),
+            Cadence::Quarterly => write!(f, "quarterly"),
+            Cadence::Annual => write!(f, "annual"),
+        }
+    }
+}
+
+impl std::str::FromStr for Cadence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Cadence::ALL
+            .into_iter()
+            .find(|c| c.to_string() == s)
+            .ok_or_else(|| format!("unknown cadence '{}'", s))
+    }
+}
+
+impl Cadence {
+    const ALL: [Cadence; 4] = [Cadence::Weekly, Cadence::Monthly, Cadence::Quarterly, Cadence::Annual];
+
+    fn period_days(&self) -> f64 {
+        match self {
+            Cadence::Weekly => 7.0,
+            Cadence::Monthly => 30.44,
+            Cadence::Quarterly => 91.31,
+            Cadence::Annual => 365.25,
+        }
+    }
+
+    /// How far an interval may drift from the nominal period and still count.
+    fn tolerance_days(&self) -> f64 {
+        match self {
+            Cadence::Weekly => 2.0,
+            Cadence::Monthly => 4.0,
+            Cadence::Quarterly => 10.0,
+            Cadence::Annual => 20.0,
+        }
+    }
+
+    fn min_occurrences(&self) -> usize {
+        match self {
+            Cadence::Annual => 2,
+            _ => 3,
+        }
+    }
+
+    pub fn per_year(&self) -> f64 {
+        365.25 / self.period_days()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RecurringPayment {
+    pub merchant_key: String,
+    pub merchant_name: String,
+    pub category: String,
+    pub cadence: Cadence,
+    /// The current (most recent) charge amount.
+    pub typical_amount: f64,
+    /// Amount before the most recent price increase, if there was one.
+    pub previous_amount: Option<f64>,
+    pub occurrences: usize,
+    pub first_date: NaiveDate,
+    pub last_date: NaiveDate,
+    pub next_expected: NaiveDate,
+    /// Number of expected charges that have not shown up by the reference date.
+    pub missed: usize,
+}
+
+impl RecurringPayment {
+    pub fn annual_cost(&self) -> f64 {
+        self.typical_amount * self.cadence.per_year()
+    }
+
+    pub fn price_increased(&self) -> bool {
+        self.previous_amount.is_some()
+    }
+}
+
+/// Relative change between two consecutive charges that counts as a price change.
+const PRICE_STEP: f64 = 0.01;
+/// Relative change that marks amounts as variable spending rather than a fixed charge.
+const VARIABLE_STEP: f64 = 0.10;
+
+/// Group charges by normalised merchant key and detect periodic payments.
+/// `as_of` is the reference date used to decide whether an occurrence was missed;
+/// pass the latest imported transaction date rather than today, since statements
+/// are imported after the fact.
+pub fn detect_all(rows: &[ChargeRow], as_of: NaiveDate) -> Vec<RecurringPayment> {
+    let mut groups: HashMap<String, Vec<&ChargeRow>> = HashMap::new();
+    for row in rows {
+        groups.entry(normalise_merchant_key(&row.raw_description)).or_default().push(row);
+    }
+
+    let mut found: Vec<RecurringPayment> = groups
+        .into_iter()
+        .filter_map(|(key, charges)| detect(&key, &charges, as_of))
+        .collect();
+
+    sort_by_annual_cost(&mut found);
+    found
+}
+
+/// Most expensive first, then by merchant key.
+pub fn sort_by_annual_cost(payments: &mut [RecurringPayment]) {
+    payments.sort_by(|a, b| b.annual_cost().total_cmp(&a.annual_cost()).then_with(|| a.merchant_key.cmp(&b.merchant_key)));
+}
+
+fn detect(key: &str, charges: &[&ChargeRow], as_of: NaiveDate) -> Option<RecurringPayment> {
+    if key.is_empty() || charges.len() < 2 {
+        return None;
+    }
+
+    let mut charges: Vec<&ChargeRow> = charges.to_vec();
+    charges.sort_by_key(|c| c.date);
+
+    let intervals: Vec<f64> = charges
+        .windows(2)
+        .map(|w| (w[1].date - w[0].date).num_days() as f64)
+        .collect();
+    let median_interval = median(&intervals);
+
+    let cadence = Cadence::ALL
+        .into_iter()
+        .find(|c| (median_interval - c.period_days()).abs() <= c.tolerance_days())?;
+
+    if charges.len() < cadence.min_occurrences() {
+        return None;
+    }
+
+    // Most intervals must sit on the cadence; occasional gaps are tolerated
+    let on_cadence = intervals
+        .iter()
+        .filter(|i| (**i - cadence.period_days()).abs() <= cadence.tolerance_days())
+        .count();
+    if on_cadence * 3 < intervals.len() * 2 {
+        return None;
+    }
+
+    // Fixed charges move in occasional steps (price changes); variable spending
+    // like groceries changes on almost every occurrence.
+    let amounts: Vec<f64> = charges.iter().map(|c| c.amount).collect();
+    let large_steps = amounts
+        .windows(2)
+        .filter(|w| relative_change(w[0], w[1]) > VARIABLE_STEP)
+        .count();
+    if large_steps > (amounts.len() / 4).max(1) {
+        return None;
+    }
+
+    let last = charges[charges.len() - 1];
+    let previous_amount = amounts
+        .iter()
+        .rev()
+        .find(|a| relative_change(**a, last.amount) > PRICE_STEP)
+        .copied()
+        .filter(|prev| *prev < last.amount);
+
+    let period = chrono::Duration::days(cadence.period_days().round() as i64);
+    let next_expected = last.date + period;
+    let overdue_days = (as_of - next_expected).num_days() as f64;
+    let missed = if overdue_days > cadence.tolerance_days() {
+        1 + ((overdue_days - cadence.tolerance_days()) / cadence.period_days()).floor() as usize
+    } else {
+        0
+    };
+
+    Some(RecurringPayment {
+        merchant_key: key.to_string(),
+        merchant_name: last.merchant_name.clone(),
+        category: last.category.clone(),
+        cadence,
+        typical_amount: last.amount,
+        previous_amount,
+        occurrences: charges.len(),
+        first_date: charges[0].date,
+        last_date: last.date,
+        next_expected,
+        missed,
+    })
+}
+
+fn relative_change(from: f64, to: f64) -> f64 {
+    if from.abs() < f64::EPSILON {
+        return if to.abs() < f64::EPSILON { 0.0 } else { 1.0 };
+    }
+    ((to - from) / from).abs()
+}
+
+fn median(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    } else {
+        sorted[mid]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(desc: &str, date: &str, amount: f64) -> ChargeRow {
+        ChargeRow {
+            raw_description: desc.to_string(),
+            merchant_name: desc.to_string(),
+            category: "Subscriptions".to_string(),
+            date: NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap(),
+            amount,
+        }
+    }
+
+    fn d(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_monthly_subscription_with_price_increase() {
+        let rows = vec![
+            row("NETFLIX.COM", "2025-10-05", 15.90),
+            row("NETFLIX.COM", "2025-11-05", 15.90),
+            row("NETFLIX.COM", "2025-12-05", 15.90),
+            row("NETFLIX.COM", "2026-01-05", 18.90),
+            row("NETFLIX.COM", "2026-02-05", 18.90),
+        ];
+        let found = detect_all(&rows, d("2026-02-20"));
+        assert_eq!(found.len(), 1);
+        let sub = &found[0];
+        assert_eq!(sub.cadence, Cadence::Monthly);
+        assert_eq!(sub.typical_amount, 18.90);
+        assert_eq!(sub.previous_amount, Some(15.90));
+        assert_eq!(sub.missed, 0);
+    }
+
+    #[test]
+    fn test_missed_occurrence() {
+        let rows = vec![
+            row("SPOTIFY", "2025-09-12", 12.95),
+            row("SPOTIFY", "2025-10-12", 12.95),
+            row("SPOTIFY", "2025-11-12", 12.95),
+        ];
+        let found = detect_all(&rows, d("2026-01-20"));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].missed, 2);
+        assert!(!found[0].price_increased());
+    }
+
+    #[test]
+    fn test_variable_spending_is_not_recurring() {
+        let rows = vec![
+            row("MIGROS BASEL", "2026-01-03", 54.20),
+            row("MIGROS BASEL", "2026-01-10", 12.10),
+            row("MIGROS BASEL", "2026-01-17", 88.45),
+            row("MIGROS BASEL", "2026-01-24", 31.00),
+            row("MIGROS BASEL", "2026-01-31", 64.90),
+        ];
+        assert!(detect_all(&rows, d("2026-02-01")).is_empty());
+    }
+
+    #[test]
+    fn test_annual_and_weekly_cadence() {
+        let rows = vec![
+            row("VIGNETTE", "2024-01-15", 40.0),
+            row("VIGNETTE", "2025-01-14", 40.0),
+            row("SWIMMING CLUB", "2026-01-06", 8.0),
+            row("SWIMMING CLUB", "2026-01-13", 8.0),
+            row("SWIMMING CLUB", "2026-01-20", 8.0),
+            row("SWIMMING CLUB", "2026-01-27", 8.0),
+        ];
+        let found = detect_all(&rows, d("2026-01-28"));
+        let cadence_of = |key: &str| found.iter().find(|r| r.merchant_key == key).map(|r| r.cadence);
+        assert_eq!(cadence_of("VIGNETTE"), Some(Cadence::Annual));
+        assert_eq!(cadence_of("SWIMMING CLUB"), Some(Cadence::Weekly));
+    }
+}
@@ -2,13 +2,13 @@ use std::io::{self, Write};
 use std::collections::BTreeMap;
 use crate::db::{Database, StoredTransaction, CategoryInfo};
 use crate::cache::normalise_merchant_key;
-use crate::classifier::ClassificationResult;
 
 pub struct ReviewFilters<'a> {
     pub category: Option<&'a str>,
     pub since: Option<&'a str>,
     pub until: Option<&'a str>,
     pub merchant: Option<&'a str>,
+    pub account: Option<&'a str>,
     pub threshold: f64,
 }
 
@@ -27,6 +27,7 @@ pub fn run_review(db: &Database, filters: ReviewFilters, categories: &[CategoryI
         filters.since,
         filters.until,
         filters.merchant,
+        filters.account,
     )?;
 
     if transactions.is_empty() {
@@ -79,18 +80,8 @@ pub fn run_review(db: &Database, filters: ReviewFilters, categories: &[CategoryI
 
             match choice {
                 "1" => {
-                    let result = ClassificationResult {
-                        merchant: first.merchant_name.clone(),
-                        category: first.category.clone(),
-                        confidence: 1.0,
-                        source: "manual".to_string(),
-                    };
-                    db.cache_insert(&key, &result)?;
-                    db.insert_few_shot_example(&key, &first.raw_description, &first.merchant_name, &first.category)?;
-
-                    for tx in group {
-                        db.update_transaction(tx.id, &first.merchant_name, &first.category, 1.0, "manual")?;
-                    }
+                    let ids: Vec<i64> = group.iter().map(|t| t.id).collect();
+                    db.apply_manual_classification(key, &first.raw_description, &first.merchant_name, &first.category, &ids, "review")?;
                     stats.confirmed += group.len();
                     stats.transactions += group.len();
                     println!("Confirmed {} transactions.", group.len());
@@ -108,18 +99,8 @@ pub fn run_review(db: &Database, filters: ReviewFilters, categories: &[CategoryI
                     if let Ok(idx) = cat_input.trim().parse::<usize>() {
                         if idx > 0 && idx <= categories.len() {
                             let new_cat = &categories[idx - 1].name;
-                            let result = ClassificationResult {
-                                merchant: first.merchant_name.clone(),
-                                category: new_cat.clone(),
-                                confidence: 1.0,
-                                source: "manual".to_string(),
-                            };
-                            db.cache_insert(&key, &result)?;
-                            db.insert_few_shot_example(&key, &first.raw_description, &first.merchant_name, new_cat)?;
-
-                            for tx in group {
-                                db.update_transaction(tx.id, &first.merchant_name, new_cat, 1.0, "manual")?;
-                            }
+                            let ids: Vec<i64> = group.iter().map(|t| t.id).collect();
+                            db.apply_manual_classification(key, &first.raw_description, &first.merchant_name, new_cat, &ids, "review")?;
                             stats.corrected += group.len();
                             stats.transactions += group.len();
                             println!("Updated {} transactions to {}.", group.len(), new_cat);
@@ -135,18 +116,8 @@ pub fn run_review(db: &Database, filters: ReviewFilters, categories: &[CategoryI
                     io::stdin().read_line(&mut merchant_input)?;
                     let new_merchant = merchant_input.trim();
                     if !new_merchant.is_empty() {
-                        let result = ClassificationResult {
-                            merchant: new_merchant.to_string(),
-                            category: first.category.clone(),
-                            confidence: 1.0,
-                            source: "manual".to_string(),
-                        };
-                        db.cache_insert(&key, &result)?;
-                        db.insert_few_shot_example(&key, &first.raw_description, new_merchant, &first.category)?;
-
-                        for tx in group {
-                            db.update_transaction(tx.id, new_merchant, &first.category, 1.0, "manual")?;
-                        }
+                        let ids: Vec<i64> = group.iter().map(|t| t.id).collect();
+                        db.apply_manual_classification(key, &first.raw_description, new_merchant, &first.category, &ids, "review")?;
                         stats.corrected += group.len();
                         stats.transactions += group.len();
                         println!("Updated {} transactions to merchant {}.", group.len(), new_merchant);
@@ -238,18 +209,8 @@ pub fn run_recategorise(db: &Database, category: &str, categories: &[CategoryInf
                     if let Ok(idx) = cat_input.trim().parse::<usize>() {
                         if idx > 0 && idx <= categories.len() {
                             let new_cat = &categories[idx - 1].name;
-                            let result = ClassificationResult {
-                                merchant: first.merchant_name.clone(),
-                                category: new_cat.clone(),
-                                confidence: 1.0,
-                                source: "manual".to_string(),
-                            };
-                            db.cache_insert(&key, &result)?;
-                            db.insert_few_shot_example(&key, &first.raw_description, &first.merchant_name, new_cat)?;
-
-                            for tx in group {
-                                db.update_transaction(tx.id, &first.merchant_name, new_cat, 1.0, "manual")?;
-                            }
+                            let ids: Vec<i64> = group.iter().map(|t| t.id).collect();
+                            db.apply_manual_classification(key, &first.raw_description, &first.merchant_name, new_cat, &ids, "recategorise")?;
                             stats.corrected += group.len();
                             stats.transactions += group.len();
                             println!("Updated {} transactions to {}.", group.len(), new_cat);
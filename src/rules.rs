@@ -0,0 +1,635 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::classifier::ClassificationResult;
+use crate::csv_parser::{CsvFormat, Transaction};
+use crate::db::CategoryInfo;
+
+/// Built-in rules, layered under any user rules files.
+const BUILTIN_RULES: &str = include_str!("../rules/builtin.toml");
+const BUILTIN_ORIGIN: &str = "built-in";
+
+/// Confidence of a rule that does not set its own.
+const DEFAULT_RULE_CONFIDENCE: f64 = 0.95;
+const SECTOR_CONFIDENCE: f64 = 0.90;
+
+static BUILTIN: Lazy<RuleSet> = Lazy::new(|| {
+    let mut rules = RuleSet::default();
+    rules.add_file(BUILTIN_RULES, BUILTIN_ORIGIN).expect("built-in rules are valid");
+    rules
+});
+
+/// Rules-based classifier, tried before the merchant cache and the LLM for
+/// every statement format.
+///
+/// Precedence order:
+/// 1. Rules, by descending priority, then rules files before built-in, then file order
+/// 2. Sector lookup (MCC mapping, credit cards only); a rules file can remap a built-in sector
+/// 3. None (fall back to cache/LLM)
+///
+/// Authoritative sector mapping reference:
+/// openspec/specs/rules-based-cc-classification/spec.md
+#[derive(Debug, Clone, Default)]
+pub struct RuleSet {
+    rules: Vec<Rule>,
+    sectors: HashMap<String, SectorRule>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Direction {
+    Debit,
+    Credit,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    label: String,
+    format: Option<CsvFormat>,
+    description: Option<Regex>,
+    details: Option<Regex>,
+    /// Compact upper-case IBAN, searched for in the details.
+    iban: Option<String>,
+    direction: Option<Direction>,
+    min_amount: Option<f64>,
+    max_amount: Option<f64>,
+    /// `None` uses the normalised description.
+    merchant: Option<String>,
+    category: String,
+    confidence: f64,
+    priority: i32,
+    origin: String,
+}
+
+#[derive(Debug, Clone)]
+struct SectorRule {
+    category: String,
+    origin: String,
+}
+
+/// The fields of a transaction that rules can match on.
+#[derive(Debug, Clone, Copy)]
+pub struct RuleInput<'a> {
+    pub format: CsvFormat,
+    pub description: &'a str,
+    pub details: &'a str,
+    pub sector: Option<&'a str>,
+    /// Negative for debits, positive for credits.
+    pub amount: Option<f64>,
+}
+
+/// A classification together with a description of the rule that produced it.
+#[derive(Debug, Clone)]
+pub struct RuleMatch {
+    pub rule: String,
+    pub result: ClassificationResult,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesFile {
+    #[serde(default)]
+    rules: Vec<RuleEntry>,
+    /// Category name to the sectors it covers.
+    #[serde(default)]
+    sectors: HashMap<String, Vec<String>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleEntry {
+    name: Option<String>,
+    format: Option<String>,
+    description: Option<String>,
+    details: Option<String>,
+    iban: Option<String>,
+    direction: Option<String>,
+    min_amount: Option<f64>,
+    max_amount: Option<f64>,
+    merchant: Option<String>,
+    category: String,
+    confidence: Option<f64>,
+    #[serde(default)]
+    priority: i32,
+}
+
+impl<'a> RuleInput<'a> {
+    pub fn from_transaction(format: CsvFormat, tx: &'a Transaction) -> Self {
+        Self {
+            format,
+            description: &tx.description,
+            details: &tx.details,
+            sector: tx.sector.as_deref(),
+            amount: tx.debit.map(|d| -d).or(tx.credit),
+        }
+    }
+}
+
+impl Rule {
+    fn from_entry(entry: RuleEntry, label: String, origin: &str) -> Result<Self, String> {
+        let regex = |field: &str, pattern: Option<String>| {
+            pattern
+                .map(|p| Regex::new(&p))
+                .transpose()
+                .map_err(|e| format!("{}: {} has an invalid {} pattern: {}", origin, label, field, e))
+        };
+        let direction = match entry.direction.as_deref() {
+            None => None,
+            Some("debit") => Some(Direction::Debit),
+            Some("credit") => Some(Direction::Credit),
+            Some(other) => return Err(format!("{}: {} has unknown direction '{}' (expected debit or credit)", origin, label, other)),
+        };
+        let format = entry
+            .format
+            .map(|f| f.parse::<CsvFormat>())
+            .transpose()
+            .map_err(|e| format!("{}: {}: {}", origin, label, e))?;
+        let confidence = entry.confidence.unwrap_or(DEFAULT_RULE_CONFIDENCE);
+        if !(0.0..=1.0).contains(&confidence) {
+            return Err(format!("{}: {} has confidence {} outside 0.0 to 1.0", origin, label, confidence));
+        }
+
+        let rule = Rule {
+            description: regex("description", entry.description)?,
+            details: regex("details", entry.details)?,
+            iban: entry.iban.map(|i| compact(&i)),
+            direction,
+            min_amount: entry.min_amount,
+            max_amount: entry.max_amount,
+            format,
+            merchant: entry.merchant,
+            category: entry.category,
+            confidence,
+            priority: entry.priority,
+            origin: origin.to_string(),
+            label,
+        };
+        if rule.description.is_none() && rule.details.is_none() && rule.iban.is_none() {
+            return Err(format!("{}: {} needs a description, details or iban condition", origin, rule.label));
+        }
+        Ok(rule)
+    }
+
+    fn matches(&self, input: &RuleInput<'_>) -> bool {
+        if self.format.is_some_and(|f| f != input.format) {
+            return false;
+        }
+        if self.description.as_ref().is_some_and(|re| !re.is_match(input.description)) {
+            return false;
+        }
+        if self.details.as_ref().is_some_and(|re| !re.is_match(input.details)) {
+            return false;
+        }
+        if self.iban.as_ref().is_some_and(|iban| !compact(input.details).contains(iban.as_str())) {
+            return false;
+        }
+
+        let needs_amount = self.direction.is_some() || self.min_amount.is_some() || self.max_amount.is_some();
+        if needs_amount {
+            let Some(amount) = input.amount else { return false };
+            let direction = if amount < 0.0 { Direction::Debit } else { Direction::Credit };
+            if self.direction.is_some_and(|d| d != direction) {
+                return false;
+            }
+            // Ranges apply to the absolute amount, so they read the same for debits and credits
+            if self.min_amount.is_some_and(|min| amount.abs() < min) || self.max_amount.is_some_and(|max| amount.abs() > max) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Upper-case with whitespace removed, so IBANs match however they are grouped.
+fn compact(s: &str) -> String {
+    s.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_uppercase()
+}
+
+impl RuleSet {
+    pub fn builtin() -> Self {
+        BUILTIN.clone()
+    }
+
+    /// The built-in rules plus each file in `paths`, checking that every rule
+    /// names one of `categories`.
+    pub fn load(paths: &[PathBuf], categories: &[CategoryInfo]) -> Result<Self, String> {
+        let builtin = Self::builtin();
+        let mut rules = Self::default();
+        for path in paths {
+            let text = std::fs::read_to_string(path)
+                .map_err(|e| format!("failed to read rules file {}: {}", path.display(), e))?;
+            rules.add_file(&text, &path.display().to_string())?;
+        }
+
+        // File rules win over built-in ones at equal priority; file sectors replace built-in mappings
+        rules.rules.extend(builtin.rules);
+        rules.rules.sort_by_key(|r| std::cmp::Reverse(r.priority));
+        for (sector, rule) in builtin.sectors {
+            rules.sectors.entry(sector).or_insert(rule);
+        }
+
+        rules.validate(categories)?;
+        Ok(rules)
+    }
+
+    fn add_file(&mut self, text: &str, origin: &str) -> Result<(), String> {
+        let file: RulesFile = toml::from_str(text).map_err(|e| format!("invalid rules file {}: {}", origin, e))?;
+
+        for (i, entry) in file.rules.into_iter().enumerate() {
+            let label = match &entry.name {
+                Some(name) => format!("rule '{}'", name),
+                None => format!("rule {}", i + 1),
+            };
+            self.rules.push(Rule::from_entry(entry, label, origin)?);
+        }
+        self.rules.sort_by_key(|r| std::cmp::Reverse(r.priority));
+
+        for (category, sectors) in file.sectors {
+            for sector in sectors {
+                self.sectors.insert(
+                    sector.trim().to_string(),
+                    SectorRule { category: category.clone(), origin: origin.to_string() },
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn validate(&self, categories: &[CategoryInfo]) -> Result<(), String> {
+        let known = |name: &str| categories.iter().any(|c| c.name == name);
+        let mut errors = Vec::new();
+        for r in &self.rules {
+            if !known(&r.category) {
+                errors.push(format!("{}: {} uses unknown category '{}'", r.origin, r.label, r.category));
+            }
+        }
+        let mut sectors: Vec<_> = self.sectors.iter().filter(|(_, r)| !known(&r.category)).collect();
+        sectors.sort_by(|a, b| a.0.cmp(b.0));
+        for (sector, r) in sectors {
+            errors.push(format!("{}: sector '{}' uses unknown category '{}'", r.origin, sector, r.category));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+
+    pub fn rule_count(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn sector_count(&self) -> usize {
+        self.sectors.len()
+    }
+
+    pub fn classify(&self, input: &RuleInput<'_>) -> Option<ClassificationResult> {
+        self.explain(input).map(|m| m.result)
+    }
+
+    /// Like `classify`, but also describes which rule fired.
+    pub fn explain(&self, input: &RuleInput<'_>) -> Option<RuleMatch> {
+        // 1. Rules
+        if let Some(rule) = self.rules.iter().find(|r| r.matches(input)) {
+            return Some(RuleMatch {
+                rule: format!("{} ({})", rule.label, rule.origin),
+                result: ClassificationResult {
+                    merchant: rule.merchant.clone().unwrap_or_else(|| normalise_merchant(input.description)),
+                    category: rule.category.clone(),
+                    confidence: rule.confidence,
+                    source: "rules".to_string(),
+                },
+            });
+        }
+
+        // 2. Sector Lookup
+        if let Some(sec) = input.sector {
+            let sec_trimmed = sec.trim();
+            if !sec_trimmed.is_empty() {
+                if let Some(rule) = self.sectors.get(sec_trimmed) {
+                    return Some(RuleMatch {
+                        rule: format!("sector '{}' ({})", sec_trimmed, rule.origin),
+                        result: ClassificationResult {
+                            merchant: normalise_merchant(input.description),
+                            category: rule.category.clone(),
+                            confidence: SECTOR_CONFIDENCE,
+                            source: "rules".to_string(),
+                        },
+                    });
+                } else {
+                    tracing::warn!("unmapped CC sector: {}", sec_trimmed);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// Rules shared with the running server, swapped when a rules file changes.
+#[derive(Clone)]
+pub struct SharedRules {
+    current: Arc<RwLock<Arc<RuleSet>>>,
+}
+
+impl SharedRules {
+    pub fn new(rules: RuleSet) -> Self {
+        Self { current: Arc::new(RwLock::new(Arc::new(rules))) }
+    }
+
+    pub fn current(&self) -> Arc<RuleSet> {
+        self.current.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn replace(&self, rules: RuleSet) {
+        *self.current.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(rules);
+    }
+}
+
+/// Latest modification time across `paths`, used to notice edited rules files.
+pub fn last_modified(paths: &[PathBuf]) -> Option<std::time::SystemTime> {
+    paths
+        .iter()
+        .filter_map(|p| std::fs::metadata(Path::new(p)).and_then(|m| m.modified()).ok())
+        .max()
+}
+
+pub fn normalise_merchant(description: &str) -> String {
+    let mut s = description.to_string();
+
+    // 1. Strip trailing ISO-3 country codes preceded by whitespace
+    static RE_COUNTRY: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s+[A-Z]{3}$").unwrap());
+    s = RE_COUNTRY.replace(&s, "").to_string();
+
+    // 2. Strip trailing location tails (repeated whitespace followed by a city name or postcode pattern)
+    // We'll strip things that look like postcodes (4-5 digits) or ZH 2
+    static RE_LOCATION: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s{2,}(\d{4,5}|[A-Z]{2}\s+\d)$").unwrap());
+    s = RE_LOCATION.replace(&s, "").to_string();
+
+    // 3. Collapse runs of 2+ whitespace characters to a single space
+    static RE_WS: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s{2,}").unwrap());
+    s = RE_WS.replace_all(&s, " ").to_string();
+
+    // 4. Strip trailing card-number masks and everything after
+    static RE_MASK: Lazy<Regex> = Lazy::new(|| Regex::new(r"[\*X]{3,}.*$").unwrap());
+    s = RE_MASK.replace(&s, "").to_string();
+
+    // 5. Trim leading/trailing whitespace and punctuation
+    s.trim_matches(|c: char| c.is_whitespace() || c.is_ascii_punctuation()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card<'a>(description: &'a str, sector: Option<&'a str>) -> RuleInput<'a> {
+        RuleInput {
+            format: CsvFormat::CreditCard,
+            description,
+            details: "",
+            sector,
+            amount: Some(-10.0),
+        }
+    }
+
+    fn statement<'a>(description: &'a str, details: &'a str, amount: f64) -> RuleInput<'a> {
+        RuleInput {
+            format: CsvFormat::AccountStatement,
+            description,
+            details,
+            sector: None,
+            amount: Some(amount),
+        }
+    }
+
+    fn classify(description: &str, sector: Option<&str>) -> Option<ClassificationResult> {
+        RuleSet::builtin().classify(&card(description, sector))
+    }
+
+    fn categories() -> Vec<CategoryInfo> {
+        ["Cash", "Children", "Dining", "Fees", "Groceries", "Healthcare", "Housing", "Income", "Other", "Shopping", "Subscriptions", "Transfers", "Transport", "Travel"]
+            .iter()
+            .map(|name| CategoryInfo { name: name.to_string(), description: String::new() })
+            .collect()
+    }
+
+    #[test]
+    fn test_normalise_merchant() {
+        assert_eq!(normalise_merchant("Phills BBQ              Cham         CHE"), "Phills BBQ Cham");
+        assert_eq!(normalise_merchant("MERCHANT NAME XXXXXXXXXX 1234 CHE"), "MERCHANT NAME");
+        assert_eq!(normalise_merchant("MIGROS ZH TIEFE           ZURICH       CHE"), "MIGROS ZH TIEFE ZURICH");
+        assert_eq!(normalise_merchant("Coop-1234               Bern         CHE"), "Coop-1234 Bern");
+        assert_eq!(normalise_merchant("APPLE.COM/BILL          CUPERTINO    USA"), "APPLE.COM/BILL CUPERTINO");
+    }
+
+    #[test]
+    fn test_twint_family() {
+        // Positive cases
+        for s in ["L", "L.H", "T.H", "K.H", "L.HF"] {
+            let desc = format!("TWINT *Sent to {} 076***1234 CHE", s);
+            let res = classify(&desc, None).unwrap();
+            assert_eq!(res.category, "Children", "Failed for {}", s);
+            assert_eq!(res.merchant, "Family");
+        }
+
+        // Negative cases
+        assert_eq!(classify("TWINT *Sent to T.P 079***9999 CHE", None).unwrap().category, "Transfers");
+        assert_eq!(classify("TWINT *Sent to J.F 079***9999 CHE", None).unwrap().category, "Transfers");
+        assert_eq!(classify("TWINT *Sent to K 079***9999 CHE", None).unwrap().category, "Transfers"); // K bare is Transfers
+    }
+
+    #[test]
+    fn test_twint_other() {
+        assert_eq!(classify("TWINT *UBS TWINT Zürich CHE", None).unwrap().category, "Transfers");
+        assert_eq!(classify("TWINT *UBS TWINT", None).unwrap().merchant, "UBS TWINT");
+    }
+
+    #[test]
+    fn test_overrides_precedence() {
+        // Supermarket override beats Fast-Food sector
+        let res = classify("Migros MR Brunaupark Zürich CHE", Some("Fast-Food Restaurants")).unwrap();
+        assert_eq!(res.category, "Groceries");
+        assert_eq!(res.merchant, "Migros MR Brunaupark Zürich");
+
+        // UBS staff cafeteria override beats Hotels sector
+        let res = classify("UBS Rest. Flur Zürich Zürich CHE", Some("Hotels")).unwrap();
+        assert_eq!(res.category, "Dining");
+        assert_eq!(res.merchant, "UBS Staff Restaurant");
+    }
+
+    #[test]
+    fn test_sector_lookup_buckets() {
+        let buckets = [
+            ("Restaurants", "Dining"),
+            ("Grocery stores", "Groceries"),
+            ("Hotels", "Travel"),
+            ("Airlines", "Travel"),
+            ("Passenger railways", "Transport"),
+            ("Pharmacies", "Healthcare"),
+            ("Clothing store", "Shopping"),
+            ("Digital goods", "Subscriptions"),
+            ("Government Services", "Fees"),
+            ("Schools and Educational Services", "Children"),
+            ("Barber or beauty shops", "Shopping"),
+            ("Cinema", "Other"),
+        ];
+
+        for (sector, expected_cat) in buckets {
+            let res = classify("Some Merchant", Some(sector)).unwrap();
+            assert_eq!(res.category, expected_cat, "Failed for sector {}", sector);
+        }
+    }
+
+    #[test]
+    fn test_empty_sector_no_override() {
+        assert!(classify("Unknown Merchant", None).is_none());
+        assert!(classify("Unknown Merchant", Some("   ")).is_none());
+    }
+
+    #[test]
+    fn test_unknown_sector() {
+        // Unknown sector should return None (fall through)
+        assert!(classify("Unknown Merchant", Some("Future Sector")).is_none());
+    }
+
+    #[test]
+    fn test_twint_family_real_data() {
+        assert_eq!(
+            classify("TWINT  *Sent to L.H.     076***0912   CHE", None).unwrap().category,
+            "Children"
+        );
+        assert_eq!(
+            classify("TWINT  *Sent to K.H.     076***0001   CHE", None).unwrap().category,
+            "Children"
+        );
+        assert_eq!(
+            classify("TWINT  *Sent to T.H.     076***0002   CHE", None).unwrap().category,
+            "Children"
+        );
+        assert_eq!(
+            classify("TWINT  *Sent to J.V.     079***8439   CHE", None).unwrap().category,
+            "Transfers"
+        );
+        assert_eq!(
+            classify("TWINT  *Sent to K.       079***3438   CHE", None).unwrap().category,
+            "Transfers"
+        );
+    }
+
+    #[test]
+    fn test_account_statement_builtin_rules() {
+        let rules = RuleSet::builtin();
+
+        let salary = rules.classify(&statement("ACME AG", "Salary February; Incoming payment UBS", 6200.0)).unwrap();
+        assert_eq!(salary.category, "Income");
+        assert_eq!(salary.confidence, 0.90);
+        // Direction matters: paying out "salary" to a nanny is not income
+        assert!(rules.classify(&statement("Nanny", "Salary February", -900.0)).is_none());
+
+        let card_bill = rules.classify(&statement("UBS Switzerland", "CREDIT CARD STATEMENT; REF: 16.01.2026", -14992.55)).unwrap();
+        assert_eq!(card_bill.category, "Transfers");
+        assert_eq!(card_bill.merchant, "UBS Credit Card");
+
+        let standing = rules.classify(&statement("Alice Newe H", "Allowance; Standing order domestic", -25.0)).unwrap();
+        assert_eq!(standing.category, "Transfers");
+        assert!(standing.confidence < 0.80, "standing orders should land in review");
+
+        // Credit card overrides do not fire on account statements
+        assert!(rules.classify(&statement("MIGROS BANK", "Payment", -10.0)).is_none());
+    }
+
+    #[test]
+    fn test_rules_file_extends_builtin() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("my-rules.toml");
+        std::fs::write(&path, r#"
+[[rules]]
+name = "Galaxus"
+description = '(?i)^GALAXUS'
+merchant = "Galaxus"
+category = "Shopping"
+
+[[rules]]
+format = "credit-card"
+description = '(?i)^MIGROS\s+BANK'
+category = "Fees"
+
+[[rules]]
+name = "Tax office"
+iban = "CH9430000006700001879"
+direction = "debit"
+merchant = "Steuerverwaltung"
+category = "Housing"
+
+[[rules]]
+name = "Large rent"
+description = '(?i)^Immo'
+min_amount = 1000
+max_amount = 5000
+category = "Housing"
+
+[[rules]]
+name = "Cash in a shop"
+priority = 10
+details = 'Bancomat Migros'
+category = "Groceries"
+confidence = 0.6
+
+[sectors]
+Shopping = ["Florists"]
+Other = ["Restaurants"]
+"#)?;
+        let rules = RuleSet::load(&[path], &categories())?;
+
+        let m = rules.explain(&card("GALAXUS.CH WOHLEN CHE", None)).unwrap();
+        assert_eq!(m.result.merchant, "Galaxus");
+        assert_eq!(m.rule, format!("rule 'Galaxus' ({})", dir.path().join("my-rules.toml").display()));
+
+        // File rules are tried before the built-in supermarket rule
+        assert_eq!(rules.classify(&card("MIGROS BANK AG ZURICH CHE", None)).unwrap().category, "Fees");
+        assert_eq!(rules.classify(&card("MIGROS ZH TIEFE ZURICH CHE", None)).unwrap().category, "Groceries");
+
+        assert_eq!(rules.classify(&card("Blumen Krämer", Some("Florists"))).unwrap().category, "Shopping");
+        assert_eq!(rules.classify(&card("Some Merchant", Some("Restaurants"))).unwrap().category, "Other");
+        assert_eq!(rules.classify(&card("Some Merchant", Some("Hotels"))).unwrap().category, "Travel");
+
+        // IBAN matches however it is grouped in the details
+        let tax = statement("Steuerverwaltung;Chur", "EBILL-RECHNUNG; Account no. IBAN: CH94 3000 0006 7000 0187 9", -877.45);
+        assert_eq!(rules.classify(&tax).unwrap().merchant, "Steuerverwaltung");
+
+        assert_eq!(rules.classify(&statement("Immo AG", "Standing order domestic", -2100.0)).unwrap().category, "Housing");
+        // Outside the amount range, the lower-priority built-in standing order rule applies
+        assert_eq!(rules.classify(&statement("Immo AG", "Standing order domestic", -250.0)).unwrap().category, "Transfers");
+
+        // Higher priority beats the built-in cash withdrawal rule
+        let cash = rules.classify(&statement("Migros", "Bancomat Migros Zürich", -50.0)).unwrap();
+        assert_eq!(cash.category, "Groceries");
+        assert_eq!(cash.confidence, 0.6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rules_file_validation() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let bad_regex = dir.path().join("bad-regex.toml");
+        std::fs::write(&bad_regex, "[[rules]]\ndescription = '(unclosed'\ncategory = 'Dining'\n")?;
+        let err = RuleSet::load(&[bad_regex], &categories()).unwrap_err();
+        assert!(err.contains("invalid description pattern"), "{}", err);
+
+        let bad_category = dir.path().join("bad-category.toml");
+        std::fs::write(&bad_category, "[sectors]\nGardening = ['Florists']\n")?;
+        let err = RuleSet::load(&[bad_category], &categories()).unwrap_err();
+        assert!(err.contains("unknown category 'Gardening'"), "{}", err);
+
+        let no_condition = dir.path().join("no-condition.toml");
+        std::fs::write(&no_condition, "[[rules]]\ndirection = 'credit'\ncategory = 'Income'\n")?;
+        let err = RuleSet::load(&[no_condition], &categories()).unwrap_err();
+        assert!(err.contains("needs a description, details or iban"), "{}", err);
+        Ok(())
+    }
+}
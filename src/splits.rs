@@ -0,0 +1,140 @@
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use crate::db::CategoryInfo;
+
+/// Amounts closer than this are treated as equal (half a cent).
+const TOLERANCE: f64 = 0.005;
+
+/// One part of a split transaction. The amount has the same sign as the parent's.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Split {
+    pub category: String,
+    pub amount: f64,
+}
+
+/// A part as entered by the user: a positive amount, or none to take whatever is left.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SplitRequest {
+    pub category: String,
+    pub amount: Option<f64>,
+}
+
+/// Parses `Category=12.50`, or a bare `Category` for the remainder.
+impl FromStr for SplitRequest {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (category, amount) = match s.split_once('=') {
+            Some((category, amount)) => {
+                let amount = amount
+                    .trim()
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|a| a.is_finite())
+                    .ok_or_else(|| format!("'{}' is not an amount", amount.trim()))?;
+                (category, Some(amount))
+            }
+            None => (s, None),
+        };
+        let category = category.trim();
+        if category.is_empty() {
+            return Err(format!("'{}' has no category (expected CATEGORY=AMOUNT)", s));
+        }
+        Ok(SplitRequest { category: category.to_string(), amount })
+    }
+}
+
+/// Turn the requested parts into splits of a transaction of `parent_amount`.
+/// Categories are matched case-insensitively, at most one part may leave out its
+/// amount, and the parts must add up to the transaction amount.
+pub fn resolve_splits(parent_amount: f64, requests: &[SplitRequest], categories: &[CategoryInfo]) -> Result<Vec<Split>, String> {
+    if requests.len() < 2 {
+        return Err("a split needs at least two parts".to_string());
+    }
+    let total = parent_amount.abs();
+    let sign = if parent_amount < 0.0 { -1.0 } else { 1.0 };
+
+    let mut resolved: Vec<(String, Option<f64>)> = Vec::new();
+    for request in requests {
+        let category = categories
+            .iter()
+            .find(|c| c.name.eq_ignore_ascii_case(&request.category))
+            .map(|c| c.name.clone())
+            .ok_or_else(|| format!("unknown category '{}'", request.category))?;
+        if resolved.iter().any(|(name, _)| *name == category) {
+            return Err(format!("{} appears more than once", category));
+        }
+        if let Some(amount) = request.amount {
+            if !amount.is_finite() || amount <= 0.0 {
+                return Err(format!("the amount for {} must be positive", category));
+            }
+        }
+        resolved.push((category, request.amount));
+    }
+
+    let given: f64 = resolved.iter().filter_map(|(_, amount)| *amount).sum();
+    let open = resolved.iter().filter(|(_, amount)| amount.is_none()).count();
+    let remainder = total - given;
+    match open {
+        0 if remainder.abs() > TOLERANCE => {
+            return Err(format!("the parts add up to {:.2}, but the transaction is {:.2}", given, total));
+        }
+        0 => {}
+        1 if remainder <= TOLERANCE => {
+            return Err(format!("the parts already add up to {:.2} of {:.2}, nothing is left for the remainder", given, total));
+        }
+        1 => {}
+        _ => return Err("only one part can leave out its amount".to_string()),
+    }
+
+    Ok(resolved
+        .into_iter()
+        .map(|(category, amount)| Split { category, amount: sign * amount.unwrap_or(remainder) })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn categories() -> Vec<CategoryInfo> {
+        ["Groceries", "Shopping", "Dining"]
+            .iter()
+            .map(|name| CategoryInfo { name: name.to_string(), description: String::new() })
+            .collect()
+    }
+
+    fn parts(args: &[&str]) -> Vec<SplitRequest> {
+        args.iter().map(|a| a.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_resolve_splits_with_remainder() {
+        let splits = resolve_splits(-100.0, &parts(&["groceries=80.50", "Shopping"]), &categories()).unwrap();
+        assert_eq!(splits, vec![
+            Split { category: "Groceries".to_string(), amount: -80.5 },
+            Split { category: "Shopping".to_string(), amount: -19.5 },
+        ]);
+    }
+
+    #[test]
+    fn test_resolve_splits_rejects_bad_parts() {
+        let cats = categories();
+        let err = |args: &[&str]| resolve_splits(-100.0, &parts(args), &cats).unwrap_err();
+        assert!(err(&["Groceries=60", "Shopping=30"]).contains("add up to 90.00"));
+        assert!(err(&["Groceries=100", "Shopping"]).contains("nothing is left"));
+        assert!(err(&["Groceries", "Shopping"]).contains("only one part"));
+        assert!(err(&["Groceries=50", "Fuel=50"]).contains("unknown category 'Fuel'"));
+        assert!(err(&["Groceries=50", "groceries=50"]).contains("more than once"));
+        assert!(err(&["Groceries=100"]).contains("at least two"));
+        assert!("=5".parse::<SplitRequest>().is_err());
+        assert!("Groceries=five".parse::<SplitRequest>().is_err());
+        assert!("Groceries=NaN".parse::<SplitRequest>().is_err());
+        assert!("Groceries=inf".parse::<SplitRequest>().is_err());
+        let nan = |category: &str| SplitRequest { category: category.to_string(), amount: Some(f64::NAN) };
+        let err = resolve_splits(-100.0, &[nan("Groceries"), nan("Shopping")], &cats).unwrap_err();
+        assert!(err.contains("must be positive"), "{}", err);
+    }
+}
@@ -0,0 +1,153 @@
+use serde::Deserialize;
+use sqlx::SqlitePool;
+
+use super::{with_database, ChartData, ChartSpec, Dataset, ToolError, ToolOutput};
+
+#[derive(Debug, Deserialize)]
+struct BalanceHistoryInput {
+	account: Option<String>,
+	months: Option<usize>,
+}
+
+pub(super) async fn balance_history(
+	pool: &SqlitePool,
+	input: serde_json::Value,
+	base: &str,
+) -> Result<ToolOutput, ToolError> {
+	let input: BalanceHistoryInput = serde_json::from_value(input)
+		.map_err(|e| ToolError::InvalidInput(e.to_string()))?;
+
+	let account = input.account.map(|a| a.trim().to_string()).filter(|a| !a.is_empty());
+	let base_owned = base.to_string();
+	let history = with_database(pool, move |db| db.balance_history(&base_owned, account.as_deref())).await?;
+	if history.accounts.is_empty() {
+		return Ok(ToolOutput {
+			summary: "No account balances found; import a statement first.".to_string(),
+			charts: Vec::new(),
+		});
+	}
+
+	// Only chart the most recent months when asked
+	let skip = input.months.map_or(0, |n| history.months.len().saturating_sub(n.max(1)));
+	let labels: Vec<String> = history.months[skip..].iter().map(|m| m.format("%Y-%m").to_string()).collect();
+	let last_month = labels.last().cloned().unwrap_or_default();
+
+	let mut datasets = Vec::new();
+	let mut parts = Vec::new();
+	let mut notes = Vec::new();
+	for a in &history.accounts {
+		let label = a.account.label();
+		let latest = a.balances.last().copied().unwrap_or(0.0);
+		let estimated = if a.anchored { "" } else { ", counted from zero" };
+		parts.push(format!("{} {} {:.2}{}", label, a.currency, latest, estimated));
+		for gap in &a.gaps {
+			notes.push(format!(
+				"{} does not reconcile from {} to {}: {} {:.2} unaccounted, likely a missing import",
+				label, gap.from, gap.to, a.currency, gap.missing()
+			));
+		}
+		datasets.push(Dataset {
+			name: label,
+			values: a.balances[skip..].to_vec(),
+		});
+	}
+
+	let net_worth = &history.net_worth[skip..];
+	let mut summary = format!(
+		"Balances at the end of {}: {}. Net worth {} {:.2}",
+		last_month,
+		parts.join(", "),
+		base,
+		net_worth.last().copied().unwrap_or(0.0)
+	);
+	if net_worth.len() > 1 {
+		summary.push_str(&format!(" (from {} {:.2} at the end of {})", base, net_worth[0], labels[0]));
+	}
+	summary.push('.');
+	for note in notes {
+		summary.push_str(&format!(" {}.", note));
+	}
+	for currency in &history.unconverted {
+		summary.push_str(&format!(" {} balances have no rate to {} and are counted unconverted.", currency, base));
+	}
+
+	if history.accounts.len() > 1 {
+		datasets.push(Dataset {
+			name: format!("Net worth ({})", base),
+			values: net_worth.to_vec(),
+		});
+	}
+
+	let chart = ChartSpec {
+		chart_type: "line".to_string(),
+		title: "Balances and Net Worth".to_string(),
+		data: ChartData { labels, datasets },
+		height: Some(320),
+	};
+
+	Ok(ToolOutput {
+		summary,
+		charts: vec![chart],
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::csv_parser::{AccountKind, AccountRef, StatementBalances};
+	use crate::db::Database;
+	use chrono::NaiveDate;
+	use serde_json::json;
+
+	#[tokio::test]
+	async fn test_balance_history_charts_accounts_and_net_worth() -> Result<(), Box<dyn std::error::Error>> {
+		let dir = tempfile::tempdir()?;
+		let db_path = dir.path().join("balances.db");
+		let db = Database::open(&db_path)?;
+		let date = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+		let checking = db.store_statement_balances(&StatementBalances {
+			account: AccountRef {
+				kind: AccountKind::Account,
+				number: "0292 00123456.78".to_string(),
+				iban: None,
+				holder: None,
+			},
+			opening: Some((date("2026-01-01"), 1000.0)),
+			closing: Some((date("2026-02-28"), 3300.0)),
+		}, "checking.csv")?;
+		let card = db.upsert_account(&AccountRef {
+			kind: AccountKind::Card,
+			number: "1234 5678 9012 3456".to_string(),
+			iban: None,
+			holder: Some("ANNA MUSTER".to_string()),
+		})?;
+
+		let conn = rusqlite::Connection::open(&db_path)?;
+		for (i, (account, date, amount)) in [
+			(checking, "2026-01-05", -100.0),
+			(checking, "2026-01-25", 2500.0),
+			(checking, "2026-02-10", -40.0),
+			(card, "2026-02-14", -25.0),
+		].iter().enumerate() {
+			conn.execute(
+				"INSERT INTO transactions (date, raw_description, amount, currency, merchant_name, category, source, confidence, transaction_id, account_id, created_at)
+				 VALUES (?, 'X', ?, 'CHF', 'X', 'Shopping', 'llm', 0.9, ?, ?, '')",
+				rusqlite::params![date, amount, format!("t{}", i), account],
+			)?;
+		}
+
+		let pool = crate::db::connect_pool(db_path.to_str().unwrap()).await?;
+		let output = balance_history(&pool, json!({}), "CHF").await.map_err(|e| e.to_string())?;
+		assert!(output.summary.starts_with(
+			"Balances at the end of 2026-02: account 0292 00123456.78 CHF 3300.00, card 1234 5678 9012 3456 (ANNA MUSTER) CHF -25.00, counted from zero. Net worth CHF 3275.00 (from CHF 940.00 at the end of 2025-12)."
+		), "{}", output.summary);
+		// A 60.00 debit is missing from February
+		assert!(output.summary.contains("from 2025-12-31 to 2026-02-28: CHF -60.00 unaccounted"), "{}", output.summary);
+		assert_eq!(output.charts[0].data.datasets.len(), 3);
+
+		let output = balance_history(&pool, json!({ "account": "anna" }), "CHF").await.map_err(|e| e.to_string())?;
+		assert_eq!(output.charts[0].data.datasets.len(), 1);
+		assert!(output.summary.contains("Net worth CHF -25.00"), "{}", output.summary);
+		Ok(())
+	}
+}
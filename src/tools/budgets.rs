@@ -0,0 +1,224 @@
+use serde::Deserialize;
+use sqlx::{Row, SqlitePool};
+
+use crate::db::Budget;
+use super::{conversion_note, ChartData, ChartSpec, Dataset, ToolError, ToolOutput};
+
+#[derive(Debug, Deserialize)]
+struct BudgetStatusInput {
+	year: Option<i32>,
+	month: Option<u32>,
+	category: Option<String>,
+}
+
+/// The period a budget comparison covers. A month is compared against monthly
+/// limits, a whole year against annual limits.
+#[derive(Debug, Clone, PartialEq)]
+enum BudgetWindow {
+	Month { year: i32, month: u32 },
+	Year { year: i32 },
+}
+
+impl BudgetWindow {
+	fn label(&self) -> String {
+		match self {
+			BudgetWindow::Month { year, month } => format!("{}-{:02}", year, month),
+			BudgetWindow::Year { year } => year.to_string(),
+		}
+	}
+
+	fn limit(&self, budget: &Budget) -> Option<f64> {
+		match self {
+			BudgetWindow::Month { .. } => budget.monthly_equivalent(),
+			BudgetWindow::Year { .. } => budget.annual_equivalent(),
+		}
+	}
+}
+
+/// Work out which period to report on. With no filters we use the most recent
+/// month that has data, since statements are imported after the fact.
+async fn resolve_window(pool: &SqlitePool, input: &BudgetStatusInput) -> Result<Option<BudgetWindow>, ToolError> {
+	if let Some(month) = input.month {
+		if !(1..=12).contains(&month) {
+			return Err(ToolError::InvalidInput(format!("month must be between 1 and 12, got {}", month)));
+		}
+	}
+
+	match (input.year, input.month) {
+		(Some(year), Some(month)) => Ok(Some(BudgetWindow::Month { year, month })),
+		(Some(year), None) => Ok(Some(BudgetWindow::Year { year })),
+		(None, month) => {
+			let latest: Option<String> = sqlx::query_scalar("SELECT MAX(date) FROM transactions")
+				.fetch_one(pool)
+				.await?;
+			let Some(latest) = latest else { return Ok(None) };
+			let year: i32 = latest.get(0..4).and_then(|y| y.parse().ok()).unwrap_or_default();
+			let latest_month: u32 = latest.get(5..7).and_then(|m| m.parse().ok()).unwrap_or(1);
+			Ok(Some(BudgetWindow::Month { year, month: month.unwrap_or(latest_month) }))
+		}
+	}
+}
+
+pub(super) async fn budget_status(
+	pool: &SqlitePool,
+	input: serde_json::Value,
+	base: &str,
+) -> Result<ToolOutput, ToolError> {
+	let input: BudgetStatusInput = serde_json::from_value(input)
+		.map_err(|e| ToolError::InvalidInput(e.to_string()))?;
+
+	let budget_rows = sqlx::query("SELECT category, monthly_limit, annual_limit FROM budgets ORDER BY category ASC")
+		.fetch_all(pool)
+		.await?;
+	let budgets: Vec<Budget> = budget_rows
+		.iter()
+		.map(|row| Budget {
+			category: row.try_get("category").unwrap_or_default(),
+			monthly_limit: row.try_get("monthly_limit").unwrap_or(None),
+			annual_limit: row.try_get("annual_limit").unwrap_or(None),
+		})
+		.filter(|b| input.category.as_ref().is_none_or(|c| c.eq_ignore_ascii_case(&b.category)))
+		.collect();
+
+	if budgets.is_empty() {
+		let summary = match &input.category {
+			Some(cat) => format!("No budget is set for {}.", cat),
+			None => "No budgets have been set. Use `budget-analyser budgets set <category> <amount>` to add one.".to_string(),
+		};
+		return Ok(ToolOutput { summary, charts: Vec::new() });
+	}
+
+	let Some(window) = resolve_window(pool, &input).await? else {
+		return Ok(ToolOutput {
+			summary: "No transactions found to compare against budgets.".to_string(),
+			charts: Vec::new(),
+		});
+	};
+
+	let (period_condition, period_params) = match &window {
+		BudgetWindow::Month { year, month } => (
+			"strftime('%Y', date) = ? AND strftime('%m', date) = ?",
+			vec![year.to_string(), format!("{:02}", month)],
+		),
+		BudgetWindow::Year { year } => ("strftime('%Y', date) = ?", vec![year.to_string()]),
+	};
+
+	let query = format!(
+		"SELECT category, -SUM(amount) as spend FROM transaction_lines WHERE amount < 0 AND category != 'Transfers' AND {} GROUP BY category",
+		period_condition
+	);
+	let mut spend_query = sqlx::query(&query);
+	for param in &period_params {
+		spend_query = spend_query.bind(param);
+	}
+	let spend_rows = spend_query.fetch_all(pool).await?;
+	let spend_for = |category: &str| -> f64 {
+		spend_rows
+			.iter()
+			.find(|row| row.try_get::<String, _>("category").map(|c| c == category).unwrap_or(false))
+			.and_then(|row| row.try_get::<f64, _>("spend").ok())
+			.unwrap_or(0.0)
+	};
+
+	let mut labels = Vec::new();
+	let mut budget_values = Vec::new();
+	let mut actual_values = Vec::new();
+	let mut lines = Vec::new();
+	let mut over = Vec::new();
+
+	for budget in &budgets {
+		let Some(limit) = window.limit(budget) else { continue };
+		let actual = spend_for(&budget.category);
+		let pct = if limit > 0.0 { actual / limit * 100.0 } else { 0.0 };
+		let status = if actual > limit {
+			over.push(budget.category.clone());
+			format!("over by {} {:.2}", base, actual - limit)
+		} else {
+			format!("{} {:.2} remaining", base, limit - actual)
+		};
+		lines.push(format!(
+			"{}: {} {:.2} of {} {:.2} ({:.0}%, {})",
+			budget.category, base, actual, base, limit, pct, status
+		));
+		labels.push(budget.category.clone());
+		budget_values.push(limit);
+		actual_values.push(actual);
+	}
+
+	let headline = if over.is_empty() {
+		format!("Budget status for {}: all budgeted categories are within their limits.", window.label())
+	} else {
+		format!("Budget status for {}: over budget in {}.", window.label(), over.join(", "))
+	};
+	let conditions = [
+		"amount < 0".to_string(),
+		"category != 'Transfers'".to_string(),
+		period_condition.to_string(),
+	];
+	let note = conversion_note(pool, base, &conditions, &period_params).await?;
+	let summary = format!("{}\n{}{}", headline, lines.join("\n"), note);
+
+	let chart = ChartSpec {
+		chart_type: "bar".to_string(),
+		title: format!("Budget vs Actual ({})", window.label()),
+		data: ChartData {
+			labels,
+			datasets: vec![
+				Dataset {
+					name: "Budget".to_string(),
+					values: budget_values,
+				},
+				Dataset {
+					name: "Actual".to_string(),
+					values: actual_values,
+				},
+			],
+		},
+		height: Some(320),
+	};
+
+	Ok(ToolOutput {
+		summary,
+		charts: vec![chart],
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::db::{BudgetPeriod, Database};
+	use serde_json::json;
+	use tempfile::tempdir;
+
+	#[tokio::test]
+	async fn test_budget_status_flags_overspend() -> Result<(), Box<dyn std::error::Error>> {
+		let dir = tempdir()?;
+		let db_path = dir.path().join("budgets.db");
+		let db = Database::open(&db_path)?;
+		db.set_budget("Dining", BudgetPeriod::Monthly, 100.0)?;
+		db.set_budget("Groceries", BudgetPeriod::Annual, 2400.0)?;
+
+		let conn = rusqlite::Connection::open(&db_path)?;
+		for (i, (date, amount, category)) in [
+			("2026-03-02", -80.0, "Dining"),
+			("2026-03-15", -45.5, "Dining"),
+			("2026-03-10", -120.0, "Groceries"),
+			("2026-02-10", -500.0, "Dining"),
+		].iter().enumerate() {
+			conn.execute(
+				"INSERT INTO transactions (date, raw_description, amount, currency, merchant_name, category, source, confidence, transaction_id, created_at)
+				 VALUES (?, 'x', ?, 'CHF', 'x', ?, 'manual', 1.0, ?, '')",
+				rusqlite::params![date, amount, category, format!("t{}", i)],
+			)?;
+		}
+
+		let pool = crate::db::connect_pool(db_path.to_str().unwrap()).await?;
+		let output = budget_status(&pool, json!({}), "CHF").await.map_err(|e| e.to_string())?;
+
+		assert!(output.summary.contains("2026-03"), "defaults to latest month: {}", output.summary);
+		assert!(output.summary.contains("over budget in Dining"), "{}", output.summary);
+		assert!(output.summary.contains("Groceries: CHF 120.00 of CHF 200.00"), "{}", output.summary);
+		assert_eq!(output.charts[0].data.datasets[1].values, vec![125.5, 120.0]);
+		Ok(())
+	}
+}
@@ -0,0 +1,111 @@
+use serde::Deserialize;
+use sqlx::SqlitePool;
+
+use crate::history::{describe_classification, original};
+use super::{with_database, ToolError, ToolOutput};
+
+/// At most this many transactions are shown for a search.
+const MAX_TRANSACTIONS: usize = 5;
+
+#[derive(Debug, Deserialize)]
+struct TransactionHistoryInput {
+	id: Option<i64>,
+	search: Option<String>,
+}
+
+pub(super) async fn transaction_history(
+	pool: &SqlitePool,
+	input: serde_json::Value,
+) -> Result<ToolOutput, ToolError> {
+	let input: TransactionHistoryInput = serde_json::from_value(input)
+		.map_err(|e| ToolError::InvalidInput(e.to_string()))?;
+	let search = input.search.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+	if input.id.is_none() && search.is_none() {
+		return Err(ToolError::InvalidInput("either id or search is required".to_string()));
+	}
+
+	let lines = with_database(pool, move |db| {
+		let transactions = match (input.id, &search) {
+			(Some(id), _) => db.get_transaction(id)?.into_iter().collect(),
+			(None, Some(search)) => db.find_transactions(search, MAX_TRANSACTIONS)?,
+			(None, None) => Vec::new(),
+		};
+		let mut lines = Vec::new();
+		for tx in transactions {
+			let Some(current) = db.current_classification(tx.id)? else { continue };
+			let entries = db.transaction_history(tx.id)?;
+			let mut line = format!(
+				"#{} {} {} {:.2}: imported as {}",
+				tx.id,
+				tx.date,
+				tx.currency,
+				tx.amount,
+				describe_classification(original(&entries, &current))
+			);
+			for entry in &entries {
+				line.push_str(&format!(
+					"; {} by {}: {}",
+					&entry.changed_at[..10.min(entry.changed_at.len())],
+					entry.changed_by,
+					entry.describe()
+				));
+			}
+			if entries.is_empty() {
+				line.push_str("; not changed since");
+			}
+			lines.push(line);
+		}
+		Ok(lines)
+	})
+	.await?;
+
+	let summary = if lines.is_empty() {
+		"No matching transaction.".to_string()
+	} else {
+		lines.join("\n")
+	};
+	Ok(ToolOutput {
+		summary,
+		charts: Vec::new(),
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::db::Database;
+	use crate::splits::Split;
+	use serde_json::json;
+
+	#[tokio::test]
+	async fn test_history_shows_llm_result_and_corrections() -> Result<(), Box<dyn std::error::Error>> {
+		let dir = tempfile::tempdir()?;
+		let db_path = dir.path().join("history.db");
+		let db = Database::open(&db_path)?;
+		let conn = rusqlite::Connection::open(&db_path)?;
+		conn.execute(
+			"INSERT INTO transactions (date, raw_description, amount, currency, merchant_name, category, source, confidence, transaction_id, created_at)
+			 VALUES ('2026-03-01', 'MIGROS BASEL', -100.0, 'CHF', 'Migros', 'Shopping', 'llm', 0.62, 't1', '')",
+			[],
+		)?;
+		let id = conn.last_insert_rowid();
+		db.apply_manual_classification("MIGROS BASEL", "MIGROS BASEL", "Migros", "Groceries", &[id], "review")?;
+		db.set_splits(id, &[
+			Split { category: "Groceries".to_string(), amount: -70.0 },
+			Split { category: "Household".to_string(), amount: -30.0 },
+		], "chat: split_transaction")?;
+		// Writing the same classification again is not a change
+		db.update_transaction(id, "Migros", "Groceries", 1.0, "manual", "review")?;
+
+		let pool = crate::db::connect_pool(db_path.to_str().unwrap()).await?;
+		let output = transaction_history(&pool, json!({ "search": "migros" })).await.map_err(|e| e.to_string())?;
+		let lines: Vec<&str> = output.summary.split("; ").collect();
+		assert_eq!(lines.len(), 3, "{}", output.summary);
+		assert!(lines[0].ends_with("imported as Migros, Shopping (llm, 0.62)"), "{}", lines[0]);
+		assert!(lines[1].ends_with("by review: category Shopping → Groceries, source llm → manual, confidence 0.62 → 1.00"), "{}", lines[1]);
+		assert!(lines[2].ends_with("by chat: split_transaction: split into Groceries 70.00, Household 30.00"), "{}", lines[2]);
+
+		assert!(transaction_history(&pool, json!({})).await.is_err());
+		Ok(())
+	}
+}
@@ -0,0 +1,105 @@
+use serde::Deserialize;
+use sqlx::SqlitePool;
+
+use super::{with_database, ToolError, ToolOutput};
+
+#[derive(Debug, Deserialize)]
+struct ImportStatusInput {
+	account: Option<String>,
+}
+
+pub(super) async fn import_status(
+	pool: &SqlitePool,
+	input: serde_json::Value,
+) -> Result<ToolOutput, ToolError> {
+	let input: ImportStatusInput = serde_json::from_value(input)
+		.map_err(|e| ToolError::InvalidInput(e.to_string()))?;
+
+	let account = input.account.map(|a| a.trim().to_string()).filter(|a| !a.is_empty());
+	let (status, interrupted) = with_database(pool, move |db| {
+		Ok((db.import_status(account.as_deref())?, db.interrupted_imports()?))
+	})
+	.await?;
+	if status.accounts.is_empty() && interrupted.is_empty() {
+		return Ok(ToolOutput {
+			summary: "No imports logged.".to_string(),
+			charts: Vec::new(),
+		});
+	}
+
+	let mut parts = Vec::new();
+	for coverage in &status.accounts {
+		let label = coverage.account.as_ref().map_or("files without an account".to_string(), |a| a.label());
+		let covered: Vec<String> = coverage.covered.iter().map(|(from, to)| format!("{} to {}", from, to)).collect();
+		let mut line = format!(
+			"{}: {} imports, covering {}",
+			label,
+			coverage.imports.len(),
+			if covered.is_empty() { "unknown dates".to_string() } else { covered.join(" and ") }
+		);
+		for issue in &coverage.issues {
+			line.push_str(&format!("; {}", issue));
+		}
+		parts.push(line);
+	}
+	for issue in &status.renamed {
+		parts.push(issue.to_string());
+	}
+	for import in &interrupted {
+		parts.push(format!(
+			"the import of {} stopped after {} rows and has to be resumed with `import --resume`",
+			import.filename, import.rows_done
+		));
+	}
+
+	Ok(ToolOutput {
+		summary: format!("Import coverage: {}.", parts.join(". ")),
+		charts: Vec::new(),
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::csv_parser::{AccountKind, AccountRef};
+	use crate::db::{Database, ImportLogEntry};
+	use chrono::NaiveDate;
+	use serde_json::json;
+
+	#[tokio::test]
+	async fn test_import_status_reports_gaps_and_renamed_files() -> Result<(), Box<dyn std::error::Error>> {
+		let dir = tempfile::tempdir()?;
+		let db_path = dir.path().join("imports.db");
+		let db = Database::open(&db_path)?;
+		let account = db.upsert_account(&AccountRef {
+			kind: AccountKind::Account,
+			number: "0292 00123456.78".to_string(),
+			iban: None,
+			holder: None,
+		})?;
+		let date = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+		for (name, hash, from, to) in [
+			("jan.csv", "a", "2026-01-01", "2026-01-31"),
+			("mar.csv", "c", "2026-03-01", "2026-03-31"),
+			("export (1).csv", "a", "2026-01-01", "2026-01-31"),
+		] {
+			db.log_import(&ImportLogEntry {
+				filename: name.to_string(),
+				row_count: 10,
+				file_hash: Some(hash.to_string()),
+				period: Some((date(from), date(to))),
+				period_exact: true,
+				account_ids: vec![account],
+			})?;
+		}
+
+		let pool = crate::db::connect_pool(db_path.to_str().unwrap()).await?;
+		let output = import_status(&pool, json!({})).await.map_err(|e| e.to_string())?;
+		assert_eq!(
+			output.summary,
+			"Import coverage: account 0292 00123456.78: 3 imports, covering 2026-01-01 to 2026-01-31 and 2026-03-01 to 2026-03-31; \
+			 gap: nothing imported from 2026-02-01 to 2026-02-28. same file imported as jan.csv, export (1).csv."
+		);
+		Ok(())
+	}
+}
@@ -1,3 +1,14 @@
+mod balances;
+mod budgets;
+mod history;
+mod imports;
+mod recategorise;
+mod recurring;
+mod sql;
+mod splits;
+
+use std::collections::HashSet;
+
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sqlx::{Row, SqlitePool};
@@ -5,6 +16,8 @@ use sqlx::sqlite::SqliteArguments;
 use sqlx::Arguments;
 
 use crate::ai::llm::ToolDefinition;
+use crate::db::Database;
+use recategorise::PendingChanges;
 
 #[derive(Debug, Clone, Serialize)]
 pub struct ToolOutput {
@@ -38,6 +51,17 @@ pub struct Dataset {
 pub enum ToolError {
 	InvalidInput(String),
 	Query(sqlx::Error),
+	Write(String),
+}
+
+impl std::fmt::Display for ToolError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			ToolError::InvalidInput(msg) => write!(f, "invalid input: {}", msg),
+			ToolError::Query(err) => write!(f, "query failed: {}", err),
+			ToolError::Write(msg) => write!(f, "write failed: {}", msg),
+		}
+	}
 }
 
 impl From<sqlx::Error> for ToolError {
@@ -46,14 +70,50 @@ impl From<sqlx::Error> for ToolError {
 	}
 }
 
+impl From<rusqlite::Error> for ToolError {
+	fn from(err: rusqlite::Error) -> Self {
+		ToolError::Write(err.to_string())
+	}
+}
+
+/// Which conversation a tool call belongs to, and which of the user's
+/// messages in it the agent is answering.
+#[derive(Debug, Clone, Default)]
+pub struct ToolContext {
+	pub conversation_id: String,
+	/// The user's messages in the conversation so far, counting the current one.
+	pub turn: usize,
+}
+
+/// Run a write against the database through the same `Database` methods the CLI
+/// uses, so chat-driven changes follow the exact same code path.
+async fn with_database<T, F>(pool: &SqlitePool, f: F) -> Result<T, ToolError>
+where
+	F: FnOnce(&Database) -> rusqlite::Result<T> + Send + 'static,
+	T: Send + 'static,
+{
+	let path = pool.connect_options().get_filename().to_path_buf();
+	tokio::task::spawn_blocking(move || {
+		let db = Database::open(&path)?;
+		f(&db)
+	})
+	.await
+	.map_err(|e| ToolError::Write(e.to_string()))?
+	.map_err(ToolError::from)
+}
+
 pub struct ToolRegistry {
 	definitions: Vec<ToolDefinition>,
+	pending: PendingChanges,
+	base_currency: String,
 }
 
 impl Clone for ToolRegistry {
     fn clone(&self) -> Self {
         Self {
             definitions: self.definitions.clone(),
+            pending: self.pending.clone(),
+            base_currency: self.base_currency.clone(),
         }
     }
 }
@@ -64,12 +124,13 @@ impl ToolRegistry {
             definitions: vec![
 				ToolDefinition {
 					name: "spending_by_category".to_string(),
-					description: "Summarise spending by category with optional year/month filters.".to_string(),
+					description: "Summarise spending by category with optional year/month and account filters.".to_string(),
 					input_schema: json!({
 						"type": "object",
 						"properties": {
 							"year": { "type": "integer" },
-							"month": { "type": "integer" }
+							"month": { "type": "integer" },
+							"account": { "type": "string", "description": "Only this account or card: number, IBAN, holder or account name" }
 						},
 						"additionalProperties": false
 					}),
@@ -81,7 +142,8 @@ impl ToolRegistry {
 						"type": "object",
 						"properties": {
 							"category": { "type": "string" },
-							"year": { "type": "integer" }
+							"year": { "type": "integer" },
+							"account": { "type": "string", "description": "Only this account or card: number, IBAN, holder or account name" }
 						},
 						"additionalProperties": false
 					}),
@@ -93,7 +155,8 @@ impl ToolRegistry {
 						"type": "object",
 						"properties": {
 							"category": { "type": "string" },
-							"top_n": { "type": "integer" }
+							"top_n": { "type": "integer" },
+							"account": { "type": "string", "description": "Only this account or card: number, IBAN, holder or account name" }
 						},
 						"required": ["category"],
 						"additionalProperties": false
@@ -108,7 +171,8 @@ impl ToolRegistry {
 							"search": { "type": "string", "description": "Search term to match against merchant name or raw description" },
 							"category": { "type": "string" },
 							"year": { "type": "integer" },
-							"month": { "type": "integer" }
+							"month": { "type": "integer" },
+							"account": { "type": "string", "description": "Only this account or card: number, IBAN, holder or account name" }
 						},
 						"required": ["search"],
 						"additionalProperties": false
@@ -116,7 +180,7 @@ impl ToolRegistry {
 				},
 				ToolDefinition {
 					name: "list_transactions".to_string(),
-					description: "List individual transactions matching a search term. Returns date, amount, merchant, and raw description for each transaction.".to_string(),
+					description: "List individual transactions matching a search term. Returns id, date, amount, merchant, and raw description for each transaction; split transactions are listed once per part.".to_string(),
 					input_schema: json!({
 						"type": "object",
 						"properties": {
@@ -124,6 +188,7 @@ impl ToolRegistry {
 							"category": { "type": "string" },
 							"year": { "type": "integer" },
 							"month": { "type": "integer" },
+							"account": { "type": "string", "description": "Only this account or card: number, IBAN, holder or account name" },
 							"limit": { "type": "integer", "description": "Max rows to return (default 50)" }
 						},
 						"required": ["search"],
@@ -136,15 +201,147 @@ impl ToolRegistry {
 					input_schema: json!({
 						"type": "object",
 						"properties": {
-							"year": { "type": "integer" }
+							"year": { "type": "integer" },
+							"account": { "type": "string", "description": "Only this account or card: number, IBAN, holder or account name" }
+						},
+						"additionalProperties": false
+					}),
+				},
+				ToolDefinition {
+					name: "budget_status".to_string(),
+					description: "Compare actual spending against per-category budgets for a month (year + month) or a whole year (year only). Defaults to the most recent month with data. Reports spend, limit, percentage used and which categories are over budget.".to_string(),
+					input_schema: json!({
+						"type": "object",
+						"properties": {
+							"year": { "type": "integer" },
+							"month": { "type": "integer" },
+							"category": { "type": "string", "description": "Only report on this category" }
+						},
+						"additionalProperties": false
+					}),
+				},
+				ToolDefinition {
+					name: "recurring_payments".to_string(),
+					description: "Recurring payments and subscriptions (weekly, monthly, quarterly, annual charges with stable amounts), as detected at the last import. Reports cadence, current amount, annual cost, next expected date, price increases and missed occurrences.".to_string(),
+					input_schema: json!({
+						"type": "object",
+						"properties": {
+							"category": { "type": "string" },
+							"flagged_only": { "type": "boolean", "description": "Only return payments with a price increase or a missed occurrence" }
+						},
+						"additionalProperties": false
+					}),
+				},
+				ToolDefinition {
+					name: "run_sql".to_string(),
+					description: "Escape hatch: run a single read-only SELECT against the SQLite database when no other tool fits. Tables: transactions(id, date YYYY-MM-DD, raw_description, amount (negative = spend, in currency), currency, original_amount, original_currency, fx_rate (card charges abroad), base_rate (currency to the report currency), merchant_name, category, source, confidence, transaction_id, import_batch, transfer_of (id of the other side of an internal transfer), account_id, balance (reported balance after the booking, account statements only), details (the export's details text)), accounts(id, kind 'account' or 'card', number, iban, holder, name), statement_balances(account_id, date, kind 'opening' or 'closing', balance), import_log(id, filename, row_count, imported_at, file_hash, period_start, period_end), import_log_accounts(import_id, account_id), transaction_splits(parent_id = transactions.id, amount, category), the view transaction_lines (same columns as transactions plus native_amount and split_id, with amount converted to the report currency and split transactions replaced by one row per part; use it for totals), fx_rates(date, base, quote, rate), merchant_cache, categories(name, description), budgets(category, monthly_limit, annual_limit), recurring_payments. Results are capped at 200 rows and 5 seconds. Optionally request a chart built from result columns.".to_string(),
+					input_schema: json!({
+						"type": "object",
+						"properties": {
+							"query": { "type": "string", "description": "A single SELECT (or WITH ... SELECT) statement" },
+							"chart": {
+								"type": "object",
+								"properties": {
+									"type": { "type": "string", "enum": ["bar", "bar_h", "line", "pie"] },
+									"title": { "type": "string" },
+									"label_column": { "type": "string" },
+									"value_columns": { "type": "array", "items": { "type": "string" } }
+								},
+								"required": ["label_column", "value_columns"],
+								"additionalProperties": false
+							}
+						},
+						"required": ["query"],
+						"additionalProperties": false
+					}),
+				},
+				ToolDefinition {
+					name: "recategorise".to_string(),
+					description: "Move transactions matching a merchant/description search to a different category (optionally renaming the merchant). Two steps: call with search + category to get a preview and a confirmation_id; nothing changes until you call again with only the confirmation_id after the user has explicitly agreed. The confirmation is only accepted in the same conversation and in reply to a later user message, never in the turn that made the preview. Updates the transactions, the merchant cache and the few-shot examples.".to_string(),
+					input_schema: json!({
+						"type": "object",
+						"properties": {
+							"search": { "type": "string", "description": "Term matched against merchant name or raw description" },
+							"category": { "type": "string", "description": "Target category name" },
+							"from_category": { "type": "string", "description": "Only move transactions currently in this category" },
+							"merchant_name": { "type": "string", "description": "Optional new merchant name for the matched transactions" },
+							"confirmation_id": { "type": "string", "description": "Id returned by a preview; applies that exact change" }
+						},
+						"additionalProperties": false
+					}),
+				},
+				ToolDefinition {
+					name: "split_transaction".to_string(),
+					description: "Split one transaction across several categories, e.g. a supermarket receipt that is part groceries and part household goods. Take the id from list_transactions. Part amounts are positive CHF and must add up to the transaction amount; one part may leave out its amount to take the remainder. Replaces any existing split; an empty parts list removes it.".to_string(),
+					input_schema: json!({
+						"type": "object",
+						"properties": {
+							"id": { "type": "integer", "description": "Transaction id from list_transactions" },
+							"parts": {
+								"type": "array",
+								"items": {
+									"type": "object",
+									"properties": {
+										"category": { "type": "string" },
+										"amount": { "type": "number" }
+									},
+									"required": ["category"],
+									"additionalProperties": false
+								}
+							}
+						},
+						"required": ["id", "parts"],
+						"additionalProperties": false
+					}),
+				},
+				ToolDefinition {
+					name: "balance_history".to_string(),
+					description: "Chart month-end balances per account and total net worth over time. Balances are anchored on the latest balance a statement reported; accounts without one (e.g. cards) are counted from zero. Also reports periods where reported balances and imported transactions disagree, which usually means a statement was not imported.".to_string(),
+					input_schema: json!({
+						"type": "object",
+						"properties": {
+							"account": { "type": "string", "description": "Only this account or card: number, IBAN, holder or account name" },
+							"months": { "type": "integer", "description": "Only chart the most recent N months" }
+						},
+						"additionalProperties": false
+					}),
+				},
+				ToolDefinition {
+					name: "import_status".to_string(),
+					description: "Show which periods have been imported for each account or card, with gaps (e.g. a month never imported), overlapping statements and the same file imported under different names. Use it before drawing conclusions from a month with unusually little data.".to_string(),
+					input_schema: json!({
+						"type": "object",
+						"properties": {
+							"account": { "type": "string", "description": "Only this account or card: number, IBAN, holder or account name" }
+						},
+						"additionalProperties": false
+					}),
+				},
+				ToolDefinition {
+					name: "transaction_history".to_string(),
+					description: "Show how a transaction's merchant and category changed since import: what the classifier originally assigned, and each later change with when it happened and which command or tool made it (review, reclassify, recategorise, splits, transfer matching). Pass an id from list_transactions, or a search to look at the most recent matching transactions.".to_string(),
+					input_schema: json!({
+						"type": "object",
+						"properties": {
+							"id": { "type": "integer", "description": "Transaction id from list_transactions" },
+							"search": { "type": "string", "description": "Merchant or description text, if no id is known" }
 						},
 						"additionalProperties": false
 					}),
 				},
 			],
+			pending: PendingChanges::default(),
+			base_currency: "CHF".to_string(),
 		}
 	}
 
+	/// Report totals in `currency` instead of CHF. Transactions must have had their
+	/// rates refreshed for the same currency (`Database::refresh_base_rates`).
+	pub fn with_base_currency(mut self, currency: &str) -> Self {
+		self.base_currency = currency.to_string();
+		self
+	}
+
 	pub fn definitions(&self) -> &[ToolDefinition] {
 		&self.definitions
 	}
@@ -154,14 +351,24 @@ impl ToolRegistry {
 		pool: &SqlitePool,
 		name: &str,
 		input: serde_json::Value,
+		context: &ToolContext,
 	) -> Result<ToolOutput, ToolError> {
+		let base = self.base_currency.as_str();
 		match name {
-			"spending_by_category" => spending_by_category(pool, input).await,
-			"monthly_trend" => monthly_trend(pool, input).await,
-			"merchant_breakdown" => merchant_breakdown(pool, input).await,
-			"income_vs_spending" => income_vs_spending(pool, input).await,
-			"search_transactions" => search_transactions(pool, input).await,
-			"list_transactions" => list_transactions(pool, input).await,
+			"spending_by_category" => spending_by_category(pool, input, base).await,
+			"monthly_trend" => monthly_trend(pool, input, base).await,
+			"merchant_breakdown" => merchant_breakdown(pool, input, base).await,
+			"income_vs_spending" => income_vs_spending(pool, input, base).await,
+			"search_transactions" => search_transactions(pool, input, base).await,
+			"list_transactions" => list_transactions(pool, input, base).await,
+			"budget_status" => budgets::budget_status(pool, input, base).await,
+			"recurring_payments" => recurring::recurring_payments(pool, input).await,
+			"run_sql" => sql::run_sql(pool, input).await,
+			"recategorise" => recategorise::recategorise(pool, &self.pending, context, input).await,
+			"split_transaction" => splits::split_transaction(pool, input).await,
+			"balance_history" => balances::balance_history(pool, input, base).await,
+			"import_status" => imports::import_status(pool, input).await,
+			"transaction_history" => history::transaction_history(pool, input).await,
 			_ => Err(ToolError::InvalidInput(format!("Unknown tool: {}", name))),
 		}
 	}
@@ -171,23 +378,27 @@ impl ToolRegistry {
 struct SpendingByCategoryInput {
 	year: Option<i32>,
 	month: Option<u32>,
+	account: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct MonthlyTrendInput {
 	category: Option<String>,
 	year: Option<i32>,
+	account: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct MerchantBreakdownInput {
 	category: String,
 	top_n: Option<i64>,
+	account: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct IncomeVsSpendingInput {
 	year: Option<i32>,
+	account: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -196,6 +407,7 @@ struct TransactionSearchInput {
 	category: Option<String>,
 	year: Option<i32>,
 	month: Option<u32>,
+	account: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -204,12 +416,13 @@ struct ListTransactionsInput {
 	category: Option<String>,
 	year: Option<i32>,
 	month: Option<u32>,
+	account: Option<String>,
 	limit: Option<i64>,
 }
 
 /// Build WHERE clause and params for transaction search.
 /// Matches `search` term against merchant_name and raw_description (case-insensitive LIKE).
-fn build_search_conditions(search: &str, category: &Option<String>, year: &Option<i32>, month: &Option<u32>) -> (Vec<String>, Vec<String>) {
+fn build_search_conditions(search: &str, category: &Option<String>, year: &Option<i32>, month: &Option<u32>, account: &Option<String>) -> (Vec<String>, Vec<String>) {
 	let mut conditions = vec![
 		"amount < 0".to_string(),
 		"(LOWER(merchant_name) LIKE '%' || LOWER(?) || '%' OR LOWER(raw_description) LIKE '%' || LOWER(?) || '%')".to_string(),
@@ -228,18 +441,75 @@ fn build_search_conditions(search: &str, category: &Option<String>, year: &Optio
 		conditions.push("strftime('%m', date) = ?".to_string());
 		params.push(format!("{:02}", m));
 	}
+	push_account_filter(&mut conditions, &mut params, account);
 
 	(conditions, params)
 }
 
+/// Restrict to one account or card, matched by number, IBAN, holder or name.
+fn push_account_filter(conditions: &mut Vec<String>, params: &mut Vec<String>, account: &Option<String>) {
+	if let Some(account) = account.as_deref().map(str::trim).filter(|a| !a.is_empty()) {
+		conditions.push(crate::db::accounts::ACCOUNT_FILTER.to_string());
+		params.push(account.to_string());
+	}
+}
+
+/// Describe how amounts in other currencies were converted to `base` for the
+/// `transaction_lines` rows matching `conditions`. Empty when everything is in `base`.
+async fn conversion_note(pool: &SqlitePool, base: &str, conditions: &[String], params: &[String]) -> Result<String, ToolError> {
+	let query = format!(
+		"SELECT currency, COUNT(DISTINCT id) as count, COUNT(DISTINCT CASE WHEN base_rate IS NULL THEN id END) as missing,
+		        MIN(base_rate) as min_rate, MAX(base_rate) as max_rate
+		 FROM transaction_lines WHERE {} AND currency != ? GROUP BY currency ORDER BY currency",
+		conditions.join(" AND ")
+	);
+	let mut args = SqliteArguments::default();
+	for param in params {
+		let _ = args.add(param.clone());
+	}
+	let _ = args.add(base.to_string());
+	let rows = sqlx::query_with(&query, args).fetch_all(pool).await?;
+
+	let mut parts = Vec::new();
+	for row in rows.iter() {
+		let currency: String = row.try_get("currency").unwrap_or_default();
+		let count: i64 = row.try_get("count").unwrap_or(0);
+		let missing: i64 = row.try_get("missing").unwrap_or(0);
+		let min_rate: Option<f64> = row.try_get("min_rate").unwrap_or(None);
+		let max_rate: Option<f64> = row.try_get("max_rate").unwrap_or(None);
+		if let (Some(min), Some(max)) = (min_rate, max_rate) {
+			let rate = if (max - min).abs() < 0.00005 {
+				format!("{:.4}", min)
+			} else {
+				format!("{:.4}-{:.4}", min, max)
+			};
+			parts.push(format!("{} {} tx at {} {}/{}", count - missing, currency, rate, base, currency));
+		}
+		if missing > 0 {
+			parts.push(format!("{} {} tx have no stored rate and are counted unconverted", missing, currency));
+		}
+	}
+
+	if parts.is_empty() {
+		Ok(String::new())
+	} else {
+		Ok(format!(" Converted to {}: {}.", base, parts.join("; ")))
+	}
+}
+
 async fn spending_by_category(
 	pool: &SqlitePool,
 	input: serde_json::Value,
+	base: &str,
 ) -> Result<ToolOutput, ToolError> {
 	let input: SpendingByCategoryInput = serde_json::from_value(input)
 		.map_err(|e| ToolError::InvalidInput(e.to_string()))?;
 
-	let mut conditions = vec!["amount < 0".to_string(), "category != 'Transfers'".to_string()];
+	let mut conditions = vec![
+		"amount < 0".to_string(),
+		"category != 'Transfers'".to_string(),
+		"transfer_of IS NULL".to_string(),
+	];
 	let mut params: Vec<String> = Vec::new();
 
 	if let Some(year) = input.year {
@@ -251,6 +521,7 @@ async fn spending_by_category(
 		conditions.push("strftime('%m', date) = ?".to_string());
 		params.push(month_str);
 	}
+	push_account_filter(&mut conditions, &mut params, &input.account);
 
 	let where_clause = if conditions.is_empty() {
 		"".to_string()
@@ -259,13 +530,13 @@ async fn spending_by_category(
 	};
 
 	let query = format!(
-		"SELECT category, -SUM(amount) as spend, COUNT(*) as count\n         FROM transactions\n         {}\n         GROUP BY category\n         ORDER BY spend DESC",
+		"SELECT category, -SUM(amount) as spend, COUNT(*) as count\n         FROM transaction_lines\n         {}\n         GROUP BY category\n         ORDER BY spend DESC",
 		where_clause
 	);
 
 	let mut args = SqliteArguments::default();
-	for param in params {
-		let _ = args.add(param);
+	for param in &params {
+		let _ = args.add(param.clone());
 	}
 	let rows = sqlx::query_with(&query, args).fetch_all(pool).await?;
 
@@ -279,13 +550,14 @@ async fn spending_by_category(
 		let count: i64 = row.try_get("count").unwrap_or(0);
 		labels.push(category.clone());
 		values.push(spend);
-		summary_parts.push(format!("{} CHF {:.2} ({} tx)", category, spend, count));
+		summary_parts.push(format!("{} {} {:.2} ({} tx)", category, base, spend, count));
 	}
 
 	let summary = if summary_parts.is_empty() {
 		"No spending data found for the requested period.".to_string()
 	} else {
-		format!("Spending by category: {}.", summary_parts.join(", "))
+		let note = conversion_note(pool, base, &conditions, &params).await?;
+		format!("Spending by category: {}.{}", summary_parts.join(", "), note)
 	};
 
 	let chart = ChartSpec {
@@ -294,7 +566,7 @@ async fn spending_by_category(
 		data: ChartData {
 			labels,
 			datasets: vec![Dataset {
-				name: "CHF".to_string(),
+				name: base.to_string(),
 				values,
 			}],
 		},
@@ -310,6 +582,7 @@ async fn spending_by_category(
 async fn monthly_trend(
 	pool: &SqlitePool,
 	input: serde_json::Value,
+	base: &str,
 ) -> Result<ToolOutput, ToolError> {
 	let input: MonthlyTrendInput = serde_json::from_value(input)
 		.map_err(|e| ToolError::InvalidInput(e.to_string()))?;
@@ -325,6 +598,7 @@ async fn monthly_trend(
 		conditions.push("strftime('%Y', date) = ?".to_string());
 		params.push(year.to_string());
 	}
+	push_account_filter(&mut conditions, &mut params, &input.account);
 
 	let where_clause = if conditions.is_empty() {
 		"".to_string()
@@ -333,13 +607,13 @@ async fn monthly_trend(
 	};
 
 	let query = format!(
-		"SELECT strftime('%Y-%m', date) as month, -SUM(amount) as spend\n         FROM transactions\n         {}\n         GROUP BY month\n         ORDER BY month ASC",
+		"SELECT strftime('%Y-%m', date) as month, -SUM(amount) as spend\n         FROM transaction_lines\n         {}\n         GROUP BY month\n         ORDER BY month ASC",
 		where_clause
 	);
 
 	let mut args = SqliteArguments::default();
-	for param in params {
-		let _ = args.add(param);
+	for param in &params {
+		let _ = args.add(param.clone());
 	}
 	let rows = sqlx::query_with(&query, args).fetch_all(pool).await?;
 
@@ -356,7 +630,8 @@ async fn monthly_trend(
 	let summary = if labels.is_empty() {
 		"No monthly trend data found for the requested period.".to_string()
 	} else {
-		format!("Monthly spending recorded across {} months.", labels.len())
+		let note = conversion_note(pool, base, &conditions, &params).await?;
+		format!("Monthly spending in {} recorded across {} months.{}", base, labels.len(), note)
 	};
 
 	let chart = ChartSpec {
@@ -365,7 +640,7 @@ async fn monthly_trend(
 		data: ChartData {
 			labels,
 			datasets: vec![Dataset {
-				name: "CHF".to_string(),
+				name: base.to_string(),
 				values,
 			}],
 		},
@@ -381,23 +656,30 @@ async fn monthly_trend(
 async fn merchant_breakdown(
 	pool: &SqlitePool,
 	input: serde_json::Value,
+	base: &str,
 ) -> Result<ToolOutput, ToolError> {
 	let input: MerchantBreakdownInput = serde_json::from_value(input)
 		.map_err(|e| ToolError::InvalidInput(e.to_string()))?;
 	let top_n = input.top_n.unwrap_or(15).max(1) as usize;
 
-	let query = r#"
-		SELECT merchant_name as merchant, -SUM(amount) as spend, COUNT(*) as count, AVG(-amount) as avg_spend
-		FROM transactions
-		WHERE amount < 0 AND category = ?
-		GROUP BY merchant_name
-		ORDER BY spend DESC
-	"#;
+	let mut conditions = vec!["amount < 0".to_string(), "category = ?".to_string()];
+	let mut params = vec![input.category.clone()];
+	push_account_filter(&mut conditions, &mut params, &input.account);
 
-	let rows = sqlx::query(query)
-		.bind(&input.category)
-		.fetch_all(pool)
-		.await?;
+	let query = format!(
+		"SELECT merchant_name as merchant, -SUM(amount) as spend, COUNT(*) as count, AVG(-amount) as avg_spend
+		 FROM transaction_lines
+		 WHERE {}
+		 GROUP BY merchant_name
+		 ORDER BY spend DESC",
+		conditions.join(" AND ")
+	);
+
+	let mut args = SqliteArguments::default();
+	for param in &params {
+		let _ = args.add(param.clone());
+	}
+	let rows = sqlx::query_with(&query, args).fetch_all(pool).await?;
 
 	let mut entries: Vec<(String, f64, i64, f64)> = rows
 		.iter()
@@ -442,14 +724,16 @@ async fn merchant_breakdown(
 		labels.push(merchant.clone());
 		values.push(*spend);
 		if *merchant != "Other" {
-			summary_parts.push(format!("{} CHF {:.2} ({} tx, avg CHF {:.2})", merchant, spend, count, avg));
+			summary_parts.push(format!("{} {} {:.2} ({} tx, avg {} {:.2})", merchant, base, spend, count, base, avg));
 		}
 	}
 
+	let note = conversion_note(pool, base, &conditions, &params).await?;
 	let summary = format!(
-		"Top merchants for {}: {}.",
+		"Top merchants for {}: {}.{}",
 		input.category,
-		summary_parts.join(", ")
+		summary_parts.join(", "),
+		note
 	);
 
 	let bar_chart = ChartSpec {
@@ -458,7 +742,7 @@ async fn merchant_breakdown(
 		data: ChartData {
 			labels: labels.clone(),
 			datasets: vec![Dataset {
-				name: "CHF".to_string(),
+				name: base.to_string(),
 				values: values.clone(),
 			}],
 		},
@@ -471,7 +755,7 @@ async fn merchant_breakdown(
 		data: ChartData {
 			labels,
 			datasets: vec![Dataset {
-				name: "CHF".to_string(),
+				name: base.to_string(),
 				values,
 			}],
 		},
@@ -487,28 +771,31 @@ async fn merchant_breakdown(
 async fn income_vs_spending(
 	pool: &SqlitePool,
 	input: serde_json::Value,
+	base: &str,
 ) -> Result<ToolOutput, ToolError> {
 	let input: IncomeVsSpendingInput = serde_json::from_value(input)
 		.map_err(|e| ToolError::InvalidInput(e.to_string()))?;
 
-	let mut conditions = vec!["1 = 1".to_string()];
+	// Linked transfers move money between own accounts; neither side is income or spending
+	let mut conditions = vec!["transfer_of IS NULL".to_string()];
 	let mut params: Vec<String> = Vec::new();
 
 	if let Some(year) = input.year {
 		conditions.push("strftime('%Y', date) = ?".to_string());
 		params.push(year.to_string());
 	}
+	push_account_filter(&mut conditions, &mut params, &input.account);
 
 	let where_clause = format!("WHERE {}", conditions.join(" AND "));
 
 	let query = format!(
-		"SELECT strftime('%Y-%m', date) as month,\n             SUM(CASE WHEN amount > 0 THEN amount ELSE 0 END) as income,\n             SUM(CASE WHEN amount < 0 AND category != 'Transfers' THEN -amount ELSE 0 END) as spending\n         FROM transactions\n         {}\n         GROUP BY month\n         ORDER BY month ASC",
+		"SELECT strftime('%Y-%m', date) as month,\n             SUM(CASE WHEN amount > 0 THEN amount ELSE 0 END) as income,\n             SUM(CASE WHEN amount < 0 AND category != 'Transfers' THEN -amount ELSE 0 END) as spending\n         FROM transaction_lines\n         {}\n         GROUP BY month\n         ORDER BY month ASC",
 		where_clause
 	);
 
 	let mut args = SqliteArguments::default();
-	for param in params {
-		let _ = args.add(param);
+	for param in &params {
+		let _ = args.add(param.clone());
 	}
 	let rows = sqlx::query_with(&query, args).fetch_all(pool).await?;
 
@@ -528,7 +815,8 @@ async fn income_vs_spending(
 	let summary = if labels.is_empty() {
 		"No income/spending data found for the requested period.".to_string()
 	} else {
-		"Monthly income vs spending summary generated.".to_string()
+		let note = conversion_note(pool, base, &conditions, &params).await?;
+		format!("Monthly income vs spending in {} summary generated.{}", base, note)
 	};
 
 	let chart = ChartSpec {
@@ -559,16 +847,17 @@ async fn income_vs_spending(
 async fn search_transactions(
 	pool: &SqlitePool,
 	input: serde_json::Value,
+	base: &str,
 ) -> Result<ToolOutput, ToolError> {
 	let input: TransactionSearchInput = serde_json::from_value(input)
 		.map_err(|e| ToolError::InvalidInput(e.to_string()))?;
 
-	let (conditions, params) = build_search_conditions(&input.search, &input.category, &input.year, &input.month);
+	let (conditions, params) = build_search_conditions(&input.search, &input.category, &input.year, &input.month, &input.account);
 	let where_clause = format!("WHERE {}", conditions.join(" AND "));
 
 	// Summary query: total spend, count, avg, date range
 	let summary_query = format!(
-		"SELECT -SUM(amount) as total_spend, COUNT(*) as count, AVG(-amount) as avg_spend, MIN(date) as min_date, MAX(date) as max_date FROM transactions {}",
+		"SELECT -SUM(amount) as total_spend, COUNT(DISTINCT id) as count, -SUM(amount) / COUNT(DISTINCT id) as avg_spend, MIN(date) as min_date, MAX(date) as max_date FROM transaction_lines {}",
 		where_clause
 	);
 
@@ -594,7 +883,7 @@ async fn search_transactions(
 
 	// Distinct merchant names
 	let merchants_query = format!(
-		"SELECT DISTINCT merchant_name FROM transactions {} ORDER BY merchant_name",
+		"SELECT DISTINCT merchant_name FROM transaction_lines {} ORDER BY merchant_name",
 		where_clause
 	);
 	let mut args2 = SqliteArguments::default();
@@ -606,14 +895,15 @@ async fn search_transactions(
 		.map(|row| row.try_get::<String, _>("merchant_name").unwrap_or_else(|_| "Unknown".to_string()))
 		.collect();
 
+	let note = conversion_note(pool, base, &conditions, &params).await?;
 	let summary = format!(
-		"Search \"{}\": {} transactions, CHF {:.2} total, avg CHF {:.2}, range {} to {}. Merchant names: {}.",
-		input.search, count, total_spend, avg_spend, min_date, max_date, merchants.join(", ")
+		"Search \"{}\": {} transactions, {} {:.2} total, avg {} {:.2}, range {} to {}. Merchant names: {}.{}",
+		input.search, count, base, total_spend, base, avg_spend, min_date, max_date, merchants.join(", "), note
 	);
 
 	// Monthly trend chart
 	let trend_query = format!(
-		"SELECT strftime('%Y-%m', date) as month, -SUM(amount) as spend FROM transactions {} GROUP BY month ORDER BY month ASC",
+		"SELECT strftime('%Y-%m', date) as month, -SUM(amount) as spend FROM transaction_lines {} GROUP BY month ORDER BY month ASC",
 		where_clause
 	);
 	let mut args3 = SqliteArguments::default();
@@ -637,7 +927,7 @@ async fn search_transactions(
 		data: ChartData {
 			labels,
 			datasets: vec![Dataset {
-				name: "CHF".to_string(),
+				name: base.to_string(),
 				values,
 			}],
 		},
@@ -653,16 +943,17 @@ async fn search_transactions(
 async fn list_transactions(
 	pool: &SqlitePool,
 	input: serde_json::Value,
+	base: &str,
 ) -> Result<ToolOutput, ToolError> {
 	let input: ListTransactionsInput = serde_json::from_value(input)
 		.map_err(|e| ToolError::InvalidInput(e.to_string()))?;
 	let limit = input.limit.unwrap_or(50).max(1);
 
-	let (conditions, params) = build_search_conditions(&input.search, &input.category, &input.year, &input.month);
+	let (conditions, params) = build_search_conditions(&input.search, &input.category, &input.year, &input.month, &input.account);
 	let where_clause = format!("WHERE {}", conditions.join(" AND "));
 
 	// Get total count first
-	let count_query = format!("SELECT COUNT(*) as total FROM transactions {}", where_clause);
+	let count_query = format!("SELECT COUNT(DISTINCT id) as total FROM transaction_lines {}", where_clause);
 	let mut count_args = SqliteArguments::default();
 	for param in &params {
 		let _ = count_args.add(param.clone());
@@ -679,7 +970,7 @@ async fn list_transactions(
 
 	// Fetch rows with limit
 	let list_query = format!(
-		"SELECT date, amount, merchant_name, raw_description FROM transactions {} ORDER BY date DESC LIMIT ?",
+		"SELECT id, date, amount, native_amount, currency, original_amount, original_currency, fx_rate, merchant_name, raw_description, category, split_id FROM transaction_lines {} ORDER BY date DESC, id DESC LIMIT ?",
 		where_clause
 	);
 	let mut list_args = SqliteArguments::default();
@@ -690,25 +981,134 @@ async fn list_transactions(
 	let rows = sqlx::query_with(&list_query, list_args).fetch_all(pool).await?;
 
 	let mut lines = Vec::new();
+	let mut shown_ids = HashSet::new();
 	for row in rows.iter() {
+		let id: i64 = row.try_get("id").unwrap_or(0);
+		let split_id: Option<i64> = row.try_get("split_id").unwrap_or(None);
 		let date: String = row.try_get("date").unwrap_or_else(|_| "?".to_string());
 		let amount: f64 = row.try_get("amount").unwrap_or(0.0);
 		let merchant: String = row.try_get("merchant_name").unwrap_or_else(|_| "?".to_string());
 		let raw: String = row.try_get("raw_description").unwrap_or_else(|_| "?".to_string());
-		lines.push(format!("{} | CHF {:.2} | {} | {}", date, -amount, merchant, raw));
+		let currency: String = row.try_get("currency").unwrap_or_else(|_| base.to_string());
+		let native_amount: f64 = row.try_get("native_amount").unwrap_or(amount);
+		let original_amount: Option<f64> = row.try_get("original_amount").unwrap_or(None);
+		let original_currency: Option<String> = row.try_get("original_currency").unwrap_or(None);
+		let fx_rate: Option<f64> = row.try_get("fx_rate").unwrap_or(None);
+		let mut shown_amount = format!("{} {:.2}", base, -amount);
+		if currency != base {
+			shown_amount.push_str(&format!(" ({} {:.2})", currency, -native_amount));
+		}
+		if let (Some(original), Some(original_currency)) = (original_amount, original_currency) {
+			match fx_rate {
+				Some(rate) => shown_amount.push_str(&format!(" [charged {} {:.2} at {}]", original_currency, original, rate)),
+				None => shown_amount.push_str(&format!(" [charged {} {:.2}]", original_currency, original)),
+			}
+		}
+		let mut line = format!("#{} | {} | {} | {} | {}", id, date, shown_amount, merchant, raw);
+		if split_id.is_some() {
+			let category: String = row.try_get("category").unwrap_or_else(|_| "?".to_string());
+			line.push_str(&format!(" | split part: {}", category));
+		}
+		lines.push(line);
+		shown_ids.insert(id);
 	}
 
-	let shown = rows.len() as i64;
+	let shown = shown_ids.len() as i64;
 	let header = if shown < total {
 		format!("Showing {} of {} transactions matching \"{}\":", shown, total, input.search)
 	} else {
 		format!("{} transactions matching \"{}\":", total, input.search)
 	};
 
-	let summary = format!("{}\n{}", header, lines.join("\n"));
+	let note = conversion_note(pool, base, &conditions, &params).await?;
+	let summary = format!("{}\n{}{}", header, lines.join("\n"), note);
 
 	Ok(ToolOutput {
 		summary,
 		charts: Vec::new(),
 	})
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::fx::FxRate;
+	use chrono::NaiveDate;
+	use serde_json::json;
+
+	#[tokio::test]
+	async fn test_totals_convert_to_base_currency() -> Result<(), Box<dyn std::error::Error>> {
+		let dir = tempfile::tempdir()?;
+		let db_path = dir.path().join("fx.db");
+		let db = Database::open(&db_path)?;
+		let conn = rusqlite::Connection::open(&db_path)?;
+		for (i, (amount, currency)) in [(-100.0, "CHF"), (-50.0, "EUR"), (-10.0, "GBP")].iter().enumerate() {
+			conn.execute(
+				"INSERT INTO transactions (date, raw_description, amount, currency, merchant_name, category, source, confidence, transaction_id, created_at)
+				 VALUES ('2026-03-01', 'SHOP', ?, ?, 'Shop', 'Shopping', 'llm', 0.9, ?, '')",
+				rusqlite::params![amount, currency, format!("t{}", i)],
+			)?;
+		}
+		let date = NaiveDate::from_ymd_opt(2026, 2, 27).unwrap();
+		db.import_fx_rates(&[FxRate { date, base: "EUR".to_string(), quote: "CHF".to_string(), rate: 0.94 }], "test")?;
+		let missing = db.refresh_base_rates("CHF")?;
+		assert_eq!(missing.get("GBP"), Some(&1));
+
+		let pool = crate::db::connect_pool(db_path.to_str().unwrap()).await?;
+		let output = spending_by_category(&pool, json!({}), "CHF").await.map_err(|e| e.to_string())?;
+		assert_eq!(
+			output.summary,
+			"Spending by category: Shopping CHF 157.00 (3 tx). Converted to CHF: 1 EUR tx at 0.9400 CHF/EUR; 1 GBP tx have no stored rate and are counted unconverted."
+		);
+
+		// Switching the base currency converts the other way
+		db.refresh_base_rates("EUR")?;
+		let output = spending_by_category(&pool, json!({}), "EUR").await.map_err(|e| e.to_string())?;
+		assert!(output.summary.starts_with("Spending by category: Shopping EUR 166.38 (3 tx)."), "{}", output.summary);
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_account_filter_matches_holder_and_iban() -> Result<(), Box<dyn std::error::Error>> {
+		use crate::csv_parser::{AccountKind, AccountRef};
+
+		let dir = tempfile::tempdir()?;
+		let db_path = dir.path().join("accounts.db");
+		let db = Database::open(&db_path)?;
+		let card = |holder: &str| AccountRef {
+			kind: AccountKind::Card,
+			number: "1234 5678 9012 3456".to_string(),
+			iban: None,
+			holder: Some(holder.to_string()),
+		};
+		let anna = db.upsert_account(&card("ANNA MUSTER"))?;
+		let ben = db.upsert_account(&card("BEN MUSTER"))?;
+		let checking = db.upsert_account(&AccountRef {
+			kind: AccountKind::Account,
+			number: "0292 00123456.78".to_string(),
+			iban: Some("CH76 0029 2292 1234 5678 9".to_string()),
+			holder: None,
+		})?;
+		assert_eq!(db.upsert_account(&card("ANNA MUSTER"))?, anna);
+
+		let conn = rusqlite::Connection::open(&db_path)?;
+		for (i, (account, amount, category)) in [(anna, -30.0, "Dining"), (ben, -20.0, "Dining"), (checking, -100.0, "Groceries")].iter().enumerate() {
+			conn.execute(
+				"INSERT INTO transactions (date, raw_description, amount, currency, merchant_name, category, source, confidence, transaction_id, account_id, created_at)
+				 VALUES ('2026-03-01', 'SHOP', ?, 'CHF', 'Shop', ?, 'llm', 0.9, ?, ?, '')",
+				rusqlite::params![amount, category, format!("t{}", i), account],
+			)?;
+		}
+
+		let pool = crate::db::connect_pool(db_path.to_str().unwrap()).await?;
+		let output = spending_by_category(&pool, json!({ "account": "anna" }), "CHF").await.map_err(|e| e.to_string())?;
+		assert_eq!(output.summary, "Spending by category: Dining CHF 30.00 (1 tx).");
+		let output = spending_by_category(&pool, json!({ "account": "CH7600292292" }), "CHF").await.map_err(|e| e.to_string())?;
+		assert_eq!(output.summary, "Spending by category: Groceries CHF 100.00 (1 tx).");
+
+		assert!(db.rename_account(ben, "Ben's card")?);
+		let output = merchant_breakdown(&pool, json!({ "category": "Dining", "account": "ben's" }), "CHF").await.map_err(|e| e.to_string())?;
+		assert!(output.summary.starts_with("Top merchants for Dining: Shop CHF 20.00 (1 tx"), "{}", output.summary);
+		Ok(())
+	}
+}
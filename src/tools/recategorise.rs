@@ -0,0 +1,268 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+use sqlx::{Row, SqlitePool};
+use uuid::Uuid;
+
+use crate::cache::normalise_merchant_key;
+use super::{with_database, ToolContext, ToolError, ToolOutput};
+
+/// How long a preview stays valid for confirmation.
+const CONFIRMATION_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// A previewed change waiting for the user to confirm it. It can only be
+/// applied from the conversation that previewed it, in answer to a later
+/// message from the user.
+#[derive(Debug, Clone)]
+pub struct PendingRecategorise {
+	conversation_id: String,
+	turn: usize,
+	category: String,
+	merchant_name: Option<String>,
+	groups: Vec<MerchantGroup>,
+	created_at: Instant,
+}
+
+#[derive(Debug, Clone)]
+struct MerchantGroup {
+	key: String,
+	raw_description: String,
+	merchant_name: String,
+	transaction_ids: Vec<i64>,
+}
+
+pub type PendingChanges = Arc<Mutex<HashMap<String, PendingRecategorise>>>;
+
+#[derive(Debug, Deserialize)]
+struct RecategoriseInput {
+	search: Option<String>,
+	category: Option<String>,
+	from_category: Option<String>,
+	merchant_name: Option<String>,
+	confirmation_id: Option<String>,
+}
+
+pub(super) async fn recategorise(
+	pool: &SqlitePool,
+	pending: &PendingChanges,
+	context: &ToolContext,
+	input: serde_json::Value,
+) -> Result<ToolOutput, ToolError> {
+	let input: RecategoriseInput = serde_json::from_value(input)
+		.map_err(|e| ToolError::InvalidInput(e.to_string()))?;
+
+	match input.confirmation_id {
+		Some(id) => apply(pool, pending, context, &id).await,
+		None => preview(pool, pending, context, input).await,
+	}
+}
+
+async fn preview(
+	pool: &SqlitePool,
+	pending: &PendingChanges,
+	context: &ToolContext,
+	input: RecategoriseInput,
+) -> Result<ToolOutput, ToolError> {
+	let search = input.search
+		.filter(|s| !s.trim().is_empty())
+		.ok_or_else(|| ToolError::InvalidInput("search is required for a preview".to_string()))?;
+	let category = input.category
+		.ok_or_else(|| ToolError::InvalidInput("category is required for a preview".to_string()))?;
+
+	let known: Option<String> = sqlx::query_scalar("SELECT name FROM categories WHERE LOWER(name) = LOWER(?)")
+		.bind(&category)
+		.fetch_optional(pool)
+		.await?;
+	let Some(category) = known else {
+		return Err(ToolError::InvalidInput(format!("unknown category '{}'", category)));
+	};
+
+	let mut query = String::from(
+		"SELECT id, date, raw_description, amount, merchant_name, category FROM transactions
+		 WHERE (LOWER(merchant_name) LIKE '%' || LOWER(?) || '%' OR LOWER(raw_description) LIKE '%' || LOWER(?) || '%')"
+	);
+	if input.from_category.is_some() {
+		query.push_str(" AND category = ?");
+	}
+	query.push_str(" ORDER BY date ASC");
+
+	let mut q = sqlx::query(&query).bind(&search).bind(&search);
+	if let Some(from) = &input.from_category {
+		q = q.bind(from);
+	}
+	let rows = q.fetch_all(pool).await?;
+
+	if rows.is_empty() {
+		return Ok(ToolOutput {
+			summary: format!("No transactions match \"{}\"; nothing to recategorise.", search),
+			charts: Vec::new(),
+		});
+	}
+
+	// Group by normalised merchant key, like the review CLI does
+	let mut groups: BTreeMap<String, MerchantGroup> = BTreeMap::new();
+	let mut current: BTreeMap<String, (usize, f64)> = BTreeMap::new();
+	for row in &rows {
+		let id: i64 = row.try_get("id")?;
+		let raw: String = row.try_get("raw_description")?;
+		let merchant: String = row.try_get("merchant_name")?;
+		let old_category: String = row.try_get("category")?;
+		let amount: f64 = row.try_get("amount")?;
+
+		let group = groups.entry(normalise_merchant_key(&raw)).or_insert_with_key(|key| MerchantGroup {
+			key: key.clone(),
+			raw_description: raw.clone(),
+			merchant_name: merchant.clone(),
+			transaction_ids: Vec::new(),
+		});
+		group.transaction_ids.push(id);
+
+		let entry = current.entry(old_category).or_insert((0, 0.0));
+		entry.0 += 1;
+		entry.1 += amount;
+	}
+
+	let total: usize = groups.values().map(|g| g.transaction_ids.len()).sum();
+	let mut lines = vec![format!(
+		"Preview: {} transactions in {} merchant groups match \"{}\" and would move to {}{}.",
+		total,
+		groups.len(),
+		search,
+		category,
+		input.merchant_name.as_ref().map(|m| format!(" with merchant name \"{}\"", m)).unwrap_or_default()
+	)];
+	for (cat, (count, amount)) in &current {
+		lines.push(format!("- currently {}: {} tx, CHF {:.2}", cat, count, -amount));
+	}
+	for group in groups.values().take(20) {
+		lines.push(format!("- group {} ({}): {} tx", group.key, group.merchant_name, group.transaction_ids.len()));
+	}
+	if groups.len() > 20 {
+		lines.push(format!("- ... and {} more groups", groups.len() - 20));
+	}
+
+	let id = Uuid::new_v4().to_string();
+	{
+		let mut store = pending.lock().expect("pending changes lock poisoned");
+		store.retain(|_, p| p.created_at.elapsed() < CONFIRMATION_TTL);
+		store.insert(id.clone(), PendingRecategorise {
+			conversation_id: context.conversation_id.clone(),
+			turn: context.turn,
+			category,
+			merchant_name: input.merchant_name,
+			groups: groups.into_values().collect(),
+			created_at: Instant::now(),
+		});
+	}
+
+	lines.push(format!(
+		"Nothing has been changed yet. Ask the user to confirm; once they reply, call recategorise with confirmation_id \"{}\".",
+		id
+	));
+
+	Ok(ToolOutput {
+		summary: lines.join("\n"),
+		charts: Vec::new(),
+	})
+}
+
+async fn apply(
+	pool: &SqlitePool,
+	pending: &PendingChanges,
+	context: &ToolContext,
+	confirmation_id: &str,
+) -> Result<ToolOutput, ToolError> {
+	let change = {
+		let mut store = pending.lock().expect("pending changes lock poisoned");
+		let usable = store.get(confirmation_id).map(|c| {
+			(c.conversation_id == context.conversation_id && c.created_at.elapsed() < CONFIRMATION_TTL, c.turn < context.turn)
+		});
+		match usable {
+			Some((true, true)) => store.remove(confirmation_id),
+			Some((true, false)) => {
+				return Err(ToolError::InvalidInput(
+					"the user has not confirmed yet; show them the preview and wait for their reply".to_string(),
+				));
+			}
+			_ => None,
+		}
+	};
+	let Some(change) = change else {
+		return Err(ToolError::InvalidInput(
+			"unknown or expired confirmation_id; run a new preview first".to_string(),
+		));
+	};
+
+	let total: usize = change.groups.iter().map(|g| g.transaction_ids.len()).sum();
+	let group_count = change.groups.len();
+	let category = change.category.clone();
+
+	with_database(pool, move |db| {
+		for group in &change.groups {
+			let merchant = change.merchant_name.as_deref().unwrap_or(&group.merchant_name);
+			db.apply_manual_classification(&group.key, &group.raw_description, merchant, &change.category, &group.transaction_ids, "chat: recategorise")?;
+		}
+		Ok(())
+	})
+	.await?;
+
+	Ok(ToolOutput {
+		summary: format!(
+			"Recategorised {} transactions in {} merchant groups to {}. The merchant cache and few-shot examples were updated so future imports follow this correction.",
+			total, group_count, category
+		),
+		charts: Vec::new(),
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::db::Database;
+	use serde_json::json;
+
+	#[tokio::test]
+	async fn test_preview_then_confirm() -> Result<(), Box<dyn std::error::Error>> {
+		let dir = tempfile::tempdir()?;
+		let db_path = dir.path().join("recat.db");
+		Database::open(&db_path)?;
+		let conn = rusqlite::Connection::open(&db_path)?;
+		for (i, desc) in ["BALOISE LIFE 123", "BALOISE LIFE 456", "MIGROS BASEL"].iter().enumerate() {
+			conn.execute(
+				"INSERT INTO transactions (date, raw_description, amount, currency, merchant_name, category, source, confidence, transaction_id, created_at)
+				 VALUES ('2026-01-01', ?, -100.0, 'CHF', ?, 'Insurance', 'llm', 0.7, ?, '')",
+				rusqlite::params![desc, desc, format!("t{}", i)],
+			)?;
+		}
+
+		let pool = crate::db::connect_pool(db_path.to_str().unwrap()).await?;
+		let pending = PendingChanges::default();
+		let turn = |conversation_id: &str, turn: usize| ToolContext { conversation_id: conversation_id.to_string(), turn };
+
+		let preview = recategorise(&pool, &pending, &turn("a", 1), json!({ "search": "baloise", "category": "investments" }))
+			.await.map_err(|e| e.to_string())?;
+		assert!(preview.summary.contains("2 transactions in 1 merchant groups"), "{}", preview.summary);
+		let unchanged: i64 = conn.query_row("SELECT COUNT(*) FROM transactions WHERE category = 'Investments'", [], |r| r.get(0))?;
+		assert_eq!(unchanged, 0, "preview must not write");
+
+		let id = pending.lock().unwrap().keys().next().cloned().unwrap();
+		// Not in the turn that made the preview, and not from another conversation
+		let same_turn = recategorise(&pool, &pending, &turn("a", 1), json!({ "confirmation_id": id })).await;
+		assert!(same_turn.unwrap_err().to_string().contains("not confirmed"));
+		assert!(recategorise(&pool, &pending, &turn("b", 2), json!({ "confirmation_id": id })).await.is_err());
+		recategorise(&pool, &pending, &turn("a", 2), json!({ "confirmation_id": id })).await.map_err(|e| e.to_string())?;
+
+		let moved: i64 = conn.query_row("SELECT COUNT(*) FROM transactions WHERE category = 'Investments' AND source = 'manual'", [], |r| r.get(0))?;
+		assert_eq!(moved, 2);
+		let cached: String = conn.query_row("SELECT category FROM merchant_cache WHERE raw_key = 'BALOISE LIFE'", [], |r| r.get(0))?;
+		assert_eq!(cached, "Investments");
+		let examples: i64 = conn.query_row("SELECT COUNT(*) FROM few_shot_examples", [], |r| r.get(0))?;
+		assert_eq!(examples, 1);
+
+		// A confirmation id can only be used once
+		assert!(recategorise(&pool, &pending, &turn("a", 3), json!({ "confirmation_id": id })).await.is_err());
+		Ok(())
+	}
+}
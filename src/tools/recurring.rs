@@ -0,0 +1,113 @@
+use serde::Deserialize;
+use sqlx::SqlitePool;
+
+use super::{with_database, ChartData, ChartSpec, Dataset, ToolError, ToolOutput};
+
+#[derive(Debug, Deserialize)]
+struct RecurringPaymentsInput {
+	category: Option<String>,
+	flagged_only: Option<bool>,
+}
+
+pub(super) async fn recurring_payments(
+	pool: &SqlitePool,
+	input: serde_json::Value,
+) -> Result<ToolOutput, ToolError> {
+	let input: RecurringPaymentsInput = serde_json::from_value(input)
+		.map_err(|e| ToolError::InvalidInput(e.to_string()))?;
+
+	let (as_of, payments) = with_database(pool, |db| Ok((db.latest_transaction_date()?, db.recurring_payments()?))).await?;
+	let Some(as_of) = as_of else {
+		return Ok(ToolOutput {
+			summary: "No spending transactions found.".to_string(),
+			charts: Vec::new(),
+		});
+	};
+
+	let payments: Vec<_> = payments
+		.into_iter()
+		.filter(|p| input.category.as_ref().is_none_or(|c| c.eq_ignore_ascii_case(&p.category)))
+		.filter(|p| !input.flagged_only.unwrap_or(false) || p.price_increased() || p.missed > 0)
+		.collect();
+
+	if payments.is_empty() {
+		return Ok(ToolOutput {
+			summary: "No recurring payments detected for the requested filters.".to_string(),
+			charts: Vec::new(),
+		});
+	}
+
+	let total_annual: f64 = payments.iter().map(|p| p.annual_cost()).sum();
+	let mut lines = Vec::new();
+	for p in &payments {
+		let mut line = format!(
+			"{} ({}): {} CHF {:.2}, ~CHF {:.2}/year, {} charges since {}, last {}, next expected {}",
+			p.merchant_name, p.category, p.cadence, p.typical_amount, p.annual_cost(),
+			p.occurrences, p.first_date, p.last_date, p.next_expected
+		);
+		if let Some(prev) = p.previous_amount {
+			line.push_str(&format!(" [PRICE INCREASE from CHF {:.2}]", prev));
+		}
+		if p.missed > 0 {
+			line.push_str(&format!(" [MISSED {} expected charge(s) — possibly cancelled]", p.missed));
+		}
+		lines.push(line);
+	}
+
+	let summary = format!(
+		"{} recurring payments detected (data up to {}), ~CHF {:.2}/year in total:\n{}",
+		payments.len(), as_of, total_annual, lines.join("\n")
+	);
+
+	let top: Vec<_> = payments.iter().take(15).collect();
+	let chart = ChartSpec {
+		chart_type: "bar_h".to_string(),
+		title: "Recurring Payments (annual cost)".to_string(),
+		data: ChartData {
+			labels: top.iter().map(|p| p.merchant_name.clone()).collect(),
+			datasets: vec![Dataset {
+				name: "CHF / year".to_string(),
+				values: top.iter().map(|p| (p.annual_cost() * 100.0).round() / 100.0).collect(),
+			}],
+		},
+		height: Some(340),
+	};
+
+	Ok(ToolOutput {
+		summary,
+		charts: vec![chart],
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::db::Database;
+	use serde_json::json;
+
+	#[tokio::test]
+	async fn test_reports_stored_recurring_payments() -> Result<(), Box<dyn std::error::Error>> {
+		let dir = tempfile::tempdir()?;
+		let db_path = dir.path().join("recurring.db");
+		let db = Database::open(&db_path)?;
+		let conn = rusqlite::Connection::open(&db_path)?;
+		for (i, date) in ["2026-01-12", "2026-02-12", "2026-03-12"].iter().enumerate() {
+			conn.execute(
+				"INSERT INTO transactions (date, raw_description, amount, currency, merchant_name, category, source, confidence, transaction_id, created_at)
+				 VALUES (?, 'SPOTIFY', -12.95, 'CHF', 'Spotify', 'Subscriptions', 'llm', 0.9, ?, '')",
+				rusqlite::params![date, format!("t{}", i)],
+			)?;
+		}
+		let pool = crate::db::connect_pool(db_path.to_str().unwrap()).await?;
+
+		// Nothing is reported until detection has run and stored its result
+		let output = recurring_payments(&pool, json!({})).await.map_err(|e| e.to_string())?;
+		assert!(output.summary.starts_with("No recurring payments"), "{}", output.summary);
+
+		assert_eq!(db.refresh_recurring_payments()?, 1);
+		let output = recurring_payments(&pool, json!({})).await.map_err(|e| e.to_string())?;
+		assert!(output.summary.contains("Spotify (Subscriptions): monthly CHF 12.95"), "{}", output.summary);
+		assert_eq!(output.charts[0].data.labels, vec!["Spotify"]);
+		Ok(())
+	}
+}
@@ -0,0 +1,106 @@
+use serde::Deserialize;
+use sqlx::{Row, SqlitePool};
+
+use crate::splits::{resolve_splits, SplitRequest};
+use super::{with_database, ToolError, ToolOutput};
+
+#[derive(Debug, Deserialize)]
+struct SplitTransactionInput {
+	id: i64,
+	parts: Vec<SplitRequest>,
+}
+
+pub(super) async fn split_transaction(
+	pool: &SqlitePool,
+	input: serde_json::Value,
+) -> Result<ToolOutput, ToolError> {
+	let input: SplitTransactionInput = serde_json::from_value(input)
+		.map_err(|e| ToolError::InvalidInput(e.to_string()))?;
+
+	let row = sqlx::query("SELECT date, amount, currency, merchant_name FROM transactions WHERE id = ?")
+		.bind(input.id)
+		.fetch_optional(pool)
+		.await?
+		.ok_or_else(|| ToolError::InvalidInput(format!("no transaction with id {}", input.id)))?;
+	let date: String = row.try_get("date").unwrap_or_else(|_| "?".to_string());
+	let amount: f64 = row.try_get("amount").unwrap_or(0.0);
+	let currency: String = row.try_get("currency").unwrap_or_else(|_| "CHF".to_string());
+	let merchant: String = row.try_get("merchant_name").unwrap_or_else(|_| "?".to_string());
+
+	let splits = if input.parts.is_empty() {
+		Vec::new()
+	} else {
+		let categories = crate::db::load_categories(pool).await?;
+		resolve_splits(amount, &input.parts, &categories).map_err(ToolError::InvalidInput)?
+	};
+
+	let id = input.id;
+	let stored = splits.clone();
+	with_database(pool, move |db| db.set_splits(id, &stored, "chat: split_transaction")).await?;
+
+	let summary = if splits.is_empty() {
+		format!("Removed the split from #{} ({} {}, {} {:.2}).", id, date, merchant, currency, amount.abs())
+	} else {
+		let parts: Vec<String> = splits
+			.iter()
+			.map(|s| format!("{} {} {:.2}", s.category, currency, s.amount.abs()))
+			.collect();
+		format!("Split #{} ({} {}, {} {:.2}) into {}.", id, date, merchant, currency, amount.abs(), parts.join(", "))
+	};
+
+	Ok(ToolOutput {
+		summary,
+		charts: Vec::new(),
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::db::Database;
+	use serde_json::json;
+
+	#[tokio::test]
+	async fn test_split_counts_in_each_category() -> Result<(), Box<dyn std::error::Error>> {
+		let dir = tempfile::tempdir()?;
+		let db_path = dir.path().join("splits.db");
+		Database::open(&db_path)?;
+		let conn = rusqlite::Connection::open(&db_path)?;
+		for (i, (desc, amount)) in [("MIGROS BASEL", -100.0), ("COOP BASEL", -40.0)].iter().enumerate() {
+			conn.execute(
+				"INSERT INTO transactions (date, raw_description, amount, currency, merchant_name, category, source, confidence, transaction_id, created_at)
+				 VALUES ('2026-03-01', ?, ?, 'CHF', ?, 'Groceries', 'llm', 0.9, ?, '')",
+				rusqlite::params![desc, amount, desc, format!("t{}", i)],
+			)?;
+		}
+		let migros: i64 = conn.query_row("SELECT id FROM transactions WHERE merchant_name = 'MIGROS BASEL'", [], |r| r.get(0))?;
+
+		let pool = crate::db::connect_pool(db_path.to_str().unwrap()).await?;
+		let output = split_transaction(&pool, json!({ "id": migros, "parts": [
+			{ "category": "Groceries", "amount": 70 },
+			{ "category": "shopping" }
+		] })).await.map_err(|e| e.to_string())?;
+		assert!(output.summary.contains("Groceries CHF 70.00, Shopping CHF 30.00"), "{}", output.summary);
+
+		let totals = super::super::spending_by_category(&pool, json!({}), "CHF").await.map_err(|e| e.to_string())?;
+		assert!(totals.summary.contains("Groceries CHF 110.00 (2 tx)"), "{}", totals.summary);
+		assert!(totals.summary.contains("Shopping CHF 30.00 (1 tx)"), "{}", totals.summary);
+
+		let listed = super::super::list_transactions(&pool, json!({ "search": "migros" }), "CHF").await.map_err(|e| e.to_string())?;
+		assert!(listed.summary.starts_with("1 transactions"), "{}", listed.summary);
+		assert!(listed.summary.contains("split part: Shopping"), "{}", listed.summary);
+
+		// Parts that do not add up are rejected without touching the stored split
+		assert!(split_transaction(&pool, json!({ "id": migros, "parts": [
+			{ "category": "Groceries", "amount": 70 },
+			{ "category": "Shopping", "amount": 20 }
+		] })).await.is_err());
+		let parts: i64 = conn.query_row("SELECT COUNT(*) FROM transaction_splits", [], |r| r.get(0))?;
+		assert_eq!(parts, 2);
+
+		split_transaction(&pool, json!({ "id": migros, "parts": [] })).await.map_err(|e| e.to_string())?;
+		let totals = super::super::spending_by_category(&pool, json!({}), "CHF").await.map_err(|e| e.to_string())?;
+		assert_eq!(totals.summary, "Spending by category: Groceries CHF 140.00 (2 tx).");
+		Ok(())
+	}
+}
@@ -0,0 +1,300 @@
+use std::time::{Duration, Instant};
+
+use futures_util::TryStreamExt;
+use serde::Deserialize;
+use sqlx::{Column, Connection, Row, SqliteConnection, SqlitePool};
+
+use super::{ChartData, ChartSpec, Dataset, ToolError, ToolOutput};
+
+/// Maximum number of rows returned to the LLM.
+const MAX_ROWS: usize = 200;
+/// Wall-clock budget for a single query before SQLite is interrupted.
+const QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Keywords that can change the database or its connection state. Any of these
+/// appearing as a bare word outside string literals rejects the statement.
+/// REPLACE is handled separately, as it is also a string function.
+const FORBIDDEN_KEYWORDS: &[&str] = &[
+	"INSERT", "UPDATE", "DELETE", "UPSERT", "DROP", "ALTER", "CREATE", "ATTACH", "DETACH",
+	"PRAGMA", "VACUUM", "REINDEX", "ANALYZE", "BEGIN", "COMMIT", "ROLLBACK", "SAVEPOINT", "RELEASE",
+	"TRUNCATE", "LOAD_EXTENSION",
+];
+
+#[derive(Debug, Deserialize)]
+struct RunSqlInput {
+	query: String,
+	chart: Option<ChartRequest>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChartRequest {
+	#[serde(rename = "type")]
+	chart_type: Option<String>,
+	title: Option<String>,
+	label_column: String,
+	value_columns: Vec<String>,
+}
+
+/// Check that `sql` is a single read-only SELECT (or WITH ... SELECT) statement.
+/// Returns the statement with any trailing semicolon removed.
+pub(super) fn validate_read_only(sql: &str) -> Result<String, String> {
+	let trimmed = sql.trim().trim_end_matches(|c: char| c == ';' || c.is_whitespace());
+	if trimmed.is_empty() {
+		return Err("query is empty".to_string());
+	}
+
+	let words = scan_words(trimmed)?;
+	match words.first().map(|(w, _)| w.as_str()) {
+		Some("SELECT") | Some("WITH") => {}
+		_ => return Err("only SELECT statements (optionally starting with WITH) are allowed".to_string()),
+	}
+
+	// replace(x, y, z) is a string function; REPLACE anywhere else writes
+	if let Some((word, _)) = words
+		.iter()
+		.find(|(w, call)| FORBIDDEN_KEYWORDS.contains(&w.as_str()) || (w == "REPLACE" && !call))
+	{
+		return Err(format!("{} is not allowed in a read-only query", word));
+	}
+
+	Ok(trimmed.to_string())
+}
+
+/// Upper-cased bare words of a statement, skipping string literals, quoted
+/// identifiers and comments, each with whether it is followed by `(` as in a
+/// function call. Fails on a statement separator.
+fn scan_words(sql: &str) -> Result<Vec<(String, bool)>, String> {
+	let chars: Vec<char> = sql.chars().collect();
+	let mut words = Vec::new();
+	let mut i = 0;
+
+	while i < chars.len() {
+		let c = chars[i];
+		match c {
+			'\'' | '"' | '`' | '[' => {
+				let close = if c == '[' { ']' } else { c };
+				i += 1;
+				loop {
+					match chars.get(i) {
+						None => return Err("unterminated quoted string or identifier".to_string()),
+						// A doubled quote is an escaped quote inside the literal
+						Some(&q) if q == close && chars.get(i + 1) == Some(&close) && close != ']' => i += 2,
+						Some(&q) if q == close => {
+							i += 1;
+							break;
+						}
+						Some(_) => i += 1,
+					}
+				}
+			}
+			'-' if chars.get(i + 1) == Some(&'-') => {
+				while i < chars.len() && chars[i] != '\n' {
+					i += 1;
+				}
+			}
+			'/' if chars.get(i + 1) == Some(&'*') => {
+				i += 2;
+				while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+					i += 1;
+				}
+				i += 2;
+			}
+			';' => return Err("only a single statement is allowed".to_string()),
+			c if c.is_alphanumeric() || c == '_' => {
+				let start = i;
+				while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+					i += 1;
+				}
+				let word = chars[start..i].iter().collect::<String>().to_uppercase();
+				let next = chars[i..].iter().find(|c| !c.is_whitespace());
+				words.push((word, next == Some(&'(')));
+			}
+			_ => i += 1,
+		}
+	}
+
+	Ok(words)
+}
+
+/// Render a single cell as text, whatever SQLite storage class it holds.
+fn cell_to_string(row: &sqlx::sqlite::SqliteRow, idx: usize) -> String {
+	if let Ok(v) = row.try_get::<Option<i64>, _>(idx) {
+		return v.map(|v| v.to_string()).unwrap_or_else(|| "NULL".to_string());
+	}
+	if let Ok(v) = row.try_get::<Option<f64>, _>(idx) {
+		return v.map(|v| format!("{:.2}", v)).unwrap_or_else(|| "NULL".to_string());
+	}
+	if let Ok(v) = row.try_get::<Option<String>, _>(idx) {
+		return v.unwrap_or_else(|| "NULL".to_string());
+	}
+	if let Ok(Some(v)) = row.try_get::<Option<Vec<u8>>, _>(idx) {
+		return format!("<blob {} bytes>", v.len());
+	}
+	"NULL".to_string()
+}
+
+pub(super) async fn run_sql(
+	pool: &SqlitePool,
+	input: serde_json::Value,
+) -> Result<ToolOutput, ToolError> {
+	let input: RunSqlInput = serde_json::from_value(input)
+		.map_err(|e| ToolError::InvalidInput(e.to_string()))?;
+	let query = validate_read_only(&input.query).map_err(ToolError::InvalidInput)?;
+
+	// Separate read-only connection so even a statement that slips past validation
+	// cannot write through the shared pool.
+	let options = (*pool.connect_options())
+		.clone()
+		.read_only(true)
+		.pragma("query_only", "ON");
+	let mut conn = SqliteConnection::connect_with(&options).await?;
+
+	let deadline = Instant::now() + QUERY_TIMEOUT;
+	conn.lock_handle().await?.set_progress_handler(1000, move || Instant::now() < deadline);
+
+	let mut columns: Vec<String> = Vec::new();
+	let mut rows: Vec<Vec<String>> = Vec::new();
+	let mut truncated = false;
+	{
+		let mut stream = sqlx::query(&query).fetch(&mut conn);
+		loop {
+			let next = stream.try_next().await.map_err(|err| {
+				if Instant::now() >= deadline {
+					ToolError::InvalidInput(format!("query exceeded the {}s time limit", QUERY_TIMEOUT.as_secs()))
+				} else {
+					ToolError::Query(err)
+				}
+			})?;
+			let Some(row) = next else { break };
+			if columns.is_empty() {
+				columns = row.columns().iter().map(|c| c.name().to_string()).collect();
+			}
+			if rows.len() == MAX_ROWS {
+				truncated = true;
+				break;
+			}
+			rows.push((0..row.len()).map(|idx| cell_to_string(&row, idx)).collect());
+		}
+	}
+	let _ = conn.close().await;
+
+	if rows.is_empty() {
+		return Ok(ToolOutput {
+			summary: "Query returned no rows.".to_string(),
+			charts: Vec::new(),
+		});
+	}
+
+	let header = if truncated {
+		format!("Query returned more than {} rows; showing the first {}:", MAX_ROWS, MAX_ROWS)
+	} else {
+		format!("Query returned {} rows:", rows.len())
+	};
+	let mut lines = vec![header, columns.join(" | ")];
+	lines.extend(rows.iter().map(|r| r.join(" | ")));
+
+	let mut charts = Vec::new();
+	if let Some(req) = input.chart {
+		charts.push(build_chart(&req, &columns, &rows)?);
+	}
+
+	Ok(ToolOutput {
+		summary: lines.join("\n"),
+		charts,
+	})
+}
+
+fn build_chart(req: &ChartRequest, columns: &[String], rows: &[Vec<String>]) -> Result<ChartSpec, ToolError> {
+	let position = |name: &str| {
+		columns
+			.iter()
+			.position(|c| c.eq_ignore_ascii_case(name))
+			.ok_or_else(|| ToolError::InvalidInput(format!("chart column '{}' is not in the result", name)))
+	};
+
+	let chart_type = req.chart_type.clone().unwrap_or_else(|| "bar".to_string());
+	if !["bar", "bar_h", "line", "pie"].contains(&chart_type.as_str()) {
+		return Err(ToolError::InvalidInput(format!("unsupported chart type '{}'", chart_type)));
+	}
+
+	let label_idx = position(&req.label_column)?;
+	let mut datasets = Vec::new();
+	for name in &req.value_columns {
+		let idx = position(name)?;
+		datasets.push(Dataset {
+			name: columns[idx].clone(),
+			values: rows.iter().map(|r| r[idx].parse::<f64>().unwrap_or(0.0)).collect(),
+		});
+	}
+	if datasets.is_empty() {
+		return Err(ToolError::InvalidInput("chart needs at least one value column".to_string()));
+	}
+
+	Ok(ChartSpec {
+		chart_type,
+		title: req.title.clone().unwrap_or_else(|| "Query Result".to_string()),
+		data: ChartData {
+			labels: rows.iter().map(|r| r[label_idx].clone()).collect(),
+			datasets,
+		},
+		height: Some(320),
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::json;
+
+	#[test]
+	fn test_accepts_select_and_cte() {
+		assert!(validate_read_only("SELECT category, SUM(amount) FROM transactions GROUP BY category;").is_ok());
+		assert!(validate_read_only("with m as (select 1 as x) select x from m").is_ok());
+		// Keywords inside literals, identifiers and comments are fine
+		assert!(validate_read_only("SELECT 'DROP TABLE x; --' AS note, updated_at FROM merchant_cache -- delete me").is_ok());
+		assert!(validate_read_only("SELECT \"update\" FROM t").is_ok());
+		// The string function, not the statement
+		assert!(validate_read_only("SELECT replace(merchant_name, ' AG', '') FROM transactions").is_ok());
+		assert!(validate_read_only("SELECT REPLACE (merchant_name, 'x', 'y') AS m FROM transactions").is_ok());
+	}
+
+	#[test]
+	fn test_rejects_writes_and_side_effects() {
+		for sql in [
+			"DELETE FROM transactions",
+			"UPDATE transactions SET category = 'Other'",
+			"SELECT 1; DROP TABLE transactions",
+			"ATTACH DATABASE '/tmp/x.db' AS x",
+			"PRAGMA journal_mode = DELETE",
+			"WITH x AS (SELECT 1) DELETE FROM transactions",
+			"SELECT load_extension('evil')",
+			"INSERT INTO budgets VALUES ('Dining', 1, 1, '', '')",
+			"REPLACE INTO budgets VALUES ('Dining', 1, 1, '', '')",
+			"WITH x AS (SELECT 1) REPLACE INTO budgets SELECT * FROM budgets",
+			"INSERT OR REPLACE INTO budgets VALUES ('Dining', 1, 1, '', '')",
+			"SELECT 'unterminated",
+		] {
+			assert!(validate_read_only(sql).is_err(), "should reject: {}", sql);
+		}
+	}
+
+	#[tokio::test]
+	async fn test_run_sql_returns_rows_and_chart() -> Result<(), Box<dyn std::error::Error>> {
+		let dir = tempfile::tempdir()?;
+		let db_path = dir.path().join("sql.db");
+		crate::db::Database::open(&db_path)?;
+
+		let pool = crate::db::connect_pool(db_path.to_str().unwrap()).await?;
+		let output = run_sql(&pool, json!({
+			"query": "SELECT name, LENGTH(description) AS len FROM categories ORDER BY name LIMIT 3",
+			"chart": { "type": "bar", "label_column": "name", "value_columns": ["len"] }
+		})).await.map_err(|e| e.to_string())?;
+
+		assert!(output.summary.starts_with("Query returned 3 rows"), "{}", output.summary);
+		assert_eq!(output.charts[0].data.labels, vec!["Cash", "Children", "Dining"]);
+
+		let err = run_sql(&pool, json!({ "query": "DELETE FROM categories" })).await;
+		assert!(err.is_err());
+		Ok(())
+	}
+}
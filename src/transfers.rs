@@ -0,0 +1,147 @@
+use std::collections::HashSet;
+
+use chrono::NaiveDate;
+
+/// How far apart (in days) the two sides of a transfer may be booked.
+pub const TRANSFER_WINDOW_DAYS: i64 = 5;
+
+/// Wording banks use for moving money between own accounts and for settling
+/// a card bill, upper case.
+const TRANSFER_HINTS: [&str; 10] = [
+    "TRANSFER TO",
+    "TRANSFER FROM",
+    "ACCOUNT TRANSFER",
+    "ÜBERTRAG",
+    "UEBERTRAG",
+    "CREDIT CARD STATEMENT",
+    "KREDITKARTENABRECHNUNG",
+    "PAYMENT RECEIVED",
+    "YOUR PAYMENT",
+    "IHRE ZAHLUNG",
+];
+
+/// Whether a transaction itself says it is a transfer: it was classified as
+/// one, its description or details use transfer or card settlement wording,
+/// or they name another of the user's accounts by number or IBAN.
+/// `other_accounts` holds those numbers and IBANs.
+pub fn has_transfer_signal(category: &str, text: &str, other_accounts: &[&str]) -> bool {
+    let compact = |s: &str| s.chars().filter(|c| !c.is_whitespace()).flat_map(char::to_uppercase).collect::<String>();
+    let upper = text.to_uppercase();
+    let text = compact(text);
+    category == "Transfers"
+        || TRANSFER_HINTS.iter().any(|hint| upper.contains(hint))
+        || other_accounts.iter().map(|a| compact(a)).any(|a| a.len() >= 6 && text.contains(&a))
+}
+
+/// A transaction that could be one side of an internal transfer.
+#[derive(Debug, Clone)]
+pub struct TransferCandidate {
+    pub id: i64,
+    pub date: NaiveDate,
+    pub amount: f64,
+    pub currency: String,
+    /// The account it was booked on; both sides of a transfer must differ.
+    pub account: String,
+    /// See `has_transfer_signal`; at least one side of a pair needs it.
+    pub signal: bool,
+}
+
+/// Pair debits with credits of the same amount and currency on another account,
+/// booked within `window_days` of each other, where at least one side has a
+/// transfer signal and the pair is not in `rejected`. Each debit takes the
+/// closest unmatched credit by date, earliest debits first. Returns
+/// `(debit_id, credit_id)`.
+pub fn match_transfers(candidates: &[TransferCandidate], window_days: i64, rejected: &HashSet<(i64, i64)>) -> Vec<(i64, i64)> {
+    let mut debits: Vec<&TransferCandidate> = candidates.iter().filter(|c| c.amount < 0.0).collect();
+    debits.sort_by_key(|c| (c.date, c.id));
+    let credits: Vec<&TransferCandidate> = candidates.iter().filter(|c| c.amount > 0.0).collect();
+
+    let mut used = HashSet::new();
+    let mut pairs = Vec::new();
+    for debit in debits {
+        let best = credits
+            .iter()
+            .filter(|credit| {
+                !used.contains(&credit.id)
+                    && credit.currency == debit.currency
+                    && credit.account != debit.account
+                    && (credit.amount + debit.amount).abs() < 0.005
+                    && (credit.date - debit.date).num_days().abs() <= window_days
+                    && (debit.signal || credit.signal)
+                    && !rejected.contains(&(debit.id, credit.id))
+            })
+            .min_by_key(|credit| ((credit.date - debit.date).num_days().abs(), credit.id));
+        if let Some(credit) = best {
+            used.insert(credit.id);
+            pairs.push((debit.id, credit.id));
+        }
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn c(id: i64, date: &str, amount: f64, account: &str) -> TransferCandidate {
+        TransferCandidate {
+            id,
+            date: NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap(),
+            amount,
+            currency: "CHF".to_string(),
+            account: account.to_string(),
+            signal: true,
+        }
+    }
+
+    #[test]
+    fn test_pairs_closest_credit_on_another_account() {
+        let candidates = vec![
+            c(1, "2026-03-01", -1200.0, "account"),
+            c(2, "2026-03-03", 1200.0, "card"),
+            c(3, "2026-03-02", 1200.0, "savings"),
+            // Same account: a refund, not a transfer
+            c(4, "2026-03-01", 1200.0, "account"),
+            c(5, "2026-03-10", -50.0, "account"),
+            // Outside the window
+            c(6, "2026-03-20", 50.0, "savings"),
+        ];
+        assert_eq!(match_transfers(&candidates, TRANSFER_WINDOW_DAYS, &HashSet::new()), vec![(1, 3)]);
+    }
+
+    #[test]
+    fn test_each_credit_is_used_once() {
+        let candidates = vec![
+            c(1, "2026-03-01", -500.0, "account"),
+            c(2, "2026-03-02", -500.0, "account"),
+            c(3, "2026-03-02", 500.0, "savings"),
+        ];
+        assert_eq!(match_transfers(&candidates, TRANSFER_WINDOW_DAYS, &HashSet::new()), vec![(1, 3)]);
+    }
+
+    #[test]
+    fn test_pairs_need_a_signal_and_not_rejected() {
+        // A card purchase and a friend paying it back: same amount, no signal
+        let mut purchase = c(1, "2026-03-01", -42.0, "card");
+        purchase.signal = false;
+        let mut repaid = c(2, "2026-03-02", 42.0, "account");
+        repaid.signal = false;
+        assert!(match_transfers(&[purchase.clone(), repaid.clone()], TRANSFER_WINDOW_DAYS, &HashSet::new()).is_empty());
+
+        repaid.signal = true;
+        assert_eq!(match_transfers(&[purchase.clone(), repaid.clone()], TRANSFER_WINDOW_DAYS, &HashSet::new()), vec![(1, 2)]);
+        let rejected = HashSet::from([(1, 2)]);
+        assert!(match_transfers(&[purchase, repaid], TRANSFER_WINDOW_DAYS, &rejected).is_empty());
+    }
+
+    #[test]
+    fn test_transfer_signals() {
+        let own = ["CH93 0076 2011 6238 5295 7"];
+        assert!(has_transfer_signal("Transfers", "Bob", &[]));
+        assert!(has_transfer_signal("Other", "Transfer to savings", &[]));
+        assert!(has_transfer_signal("Other", "UBS Switzerland CREDIT CARD STATEMENT; REF: 16.01.2026", &[]));
+        assert!(has_transfer_signal("Other", "Max Muster Account no. IBAN: CH9300762011623852957", &own));
+        assert!(!has_transfer_signal("Shopping", "DIGITEC GALAXUS", &own));
+        assert!(!has_transfer_signal("Income", "Max Muster Reason for payment: Pizza", &own));
+    }
+}
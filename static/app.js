@@ -1,9 +1,14 @@
 // app.js: Application coordinator
 
 let chatManager;
+let importManager;
+let historyManager;
 
 document.addEventListener('DOMContentLoaded', () => {
     chatManager = new ChatManager();
+    importManager = new ImportManager(chatManager);
+    historyManager = new HistoryManager(chatManager);
+    historyManager.refresh();
 
     const chatInput = document.getElementById('chat-input');
     const sendBtn = document.getElementById('send-btn');
@@ -22,8 +27,21 @@ document.addEventListener('DOMContentLoaded', () => {
         }
     });
 
+    const importBtn = document.getElementById('import-btn');
+    const importFile = document.getElementById('import-file');
+
+    importBtn.addEventListener('click', () => importFile.click());
+
+    importFile.addEventListener('change', () => {
+        if (importFile.files.length > 0) {
+            importManager.upload(importFile.files[0]);
+        }
+        importFile.value = '';
+    });
+
     newChatBtn.addEventListener('click', () => {
         chatManager.reset();
+        historyManager.refresh();
     });
 
     chatInput.focus();
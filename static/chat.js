@@ -102,6 +102,9 @@ class ChatManager {
                 if (doneData.conversation_id) {
                     this.conversationId = doneData.conversation_id;
                 }
+                if (this.onConversationUpdated) {
+                    this.onConversationUpdated(this.conversationId);
+                }
             } catch (e) {
                 console.error('Failed to parse done event:', e);
             }
@@ -219,14 +222,8 @@ class ChatManager {
         return text.replace(/[&<>"']/g, c => map[c]);
     }
 
-    async reset() {
-        if (this.conversationId) {
-            await fetch('/api/chat/reset', {
-                method: 'POST',
-                headers: { 'Content-Type': 'application/json' },
-                body: JSON.stringify({ conversation_id: this.conversationId }),
-            });
-        }
+    // Start a fresh conversation; past ones stay available in the history panel
+    reset() {
         this.conversationId = null;
         this.messagesContainer.innerHTML = '';
         this.isWaiting = false;
@@ -0,0 +1,109 @@
+// history.js: list, reopen, rename and delete past conversations
+
+class HistoryManager {
+    constructor(chatManager) {
+        this.chat = chatManager;
+        this.list = document.getElementById('conversation-list');
+        this.chat.onConversationUpdated = () => this.refresh();
+    }
+
+    async refresh() {
+        try {
+            const response = await fetch('/api/conversations');
+            if (!response.ok) return;
+            const conversations = await response.json();
+            this.render(conversations);
+        } catch (e) {
+            console.error('Failed to load conversations:', e);
+        }
+    }
+
+    render(conversations) {
+        this.list.innerHTML = '';
+        for (const conv of conversations) {
+            const item = document.createElement('li');
+            item.className = 'conversation-item';
+            if (conv.id === this.chat.conversationId) item.classList.add('active');
+
+            const title = document.createElement('span');
+            title.className = 'title';
+            title.textContent = conv.title || 'Untitled';
+            title.title = conv.updated_at;
+            title.addEventListener('click', () => this.open(conv.id));
+
+            const rename = document.createElement('button');
+            rename.textContent = '✎';
+            rename.title = 'Rename';
+            rename.addEventListener('click', () => this.rename(conv));
+
+            const remove = document.createElement('button');
+            remove.textContent = '×';
+            remove.title = 'Delete';
+            remove.addEventListener('click', () => this.remove(conv));
+
+            item.append(title, rename, remove);
+            this.list.appendChild(item);
+        }
+    }
+
+    async open(id) {
+        if (this.chat.isWaiting) return;
+        const response = await fetch('/api/conversations/' + encodeURIComponent(id));
+        if (!response.ok) return;
+        const conv = await response.json();
+
+        this.chat.reset();
+        this.chat.conversationId = conv.id;
+
+        // Rebuild the transcript: each user text message starts a turn, and the
+        // turn's charts and assistant text go into one assistant bubble.
+        let current = null;
+        let text = '';
+        const flush = () => {
+            if (current && text) {
+                current.querySelector('.text-area').innerHTML = this.chat.renderMarkdown(text);
+            }
+        };
+
+        conv.messages.forEach((message, seq) => {
+            const texts = message.content.filter(b => b.type === 'text').map(b => b.text);
+            if (message.role === 'user' && texts.length > 0) {
+                flush();
+                this.chat.messagesContainer.appendChild(this.chat.createMessageElement('user', texts.join('\n')));
+                const assistantEl = this.chat.createMessageElement('assistant', '');
+                this.chat.messagesContainer.appendChild(assistantEl);
+                current = assistantEl.querySelector('.message-content');
+                text = '';
+                for (const chart of conv.charts.filter(c => c.message_seq === seq)) {
+                    this.chat.renderChart(current, chart.chart);
+                }
+            } else if (message.role === 'assistant' && current) {
+                text += texts.join('');
+            }
+        });
+        flush();
+
+        this.chat.autoScroll();
+        this.refresh();
+    }
+
+    async rename(conv) {
+        const title = prompt('Rename conversation', conv.title);
+        if (!title || !title.trim()) return;
+        await fetch('/api/conversations/' + encodeURIComponent(conv.id), {
+            method: 'PATCH',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify({ title }),
+        });
+        this.refresh();
+    }
+
+    async remove(conv) {
+        if (!confirm('Delete "' + (conv.title || 'Untitled') + '"?')) return;
+        await fetch('/api/conversations/' + encodeURIComponent(conv.id), { method: 'DELETE' });
+        if (this.chat.conversationId === conv.id) {
+            this.chat.reset();
+        }
+        this.refresh();
+    }
+}
@@ -0,0 +1,91 @@
+// import.js: CSV upload with streamed classification progress
+
+class ImportManager {
+    constructor(chatManager) {
+        this.chat = chatManager;
+        this.importBtn = document.getElementById('import-btn');
+    }
+
+    async upload(file) {
+        this.importBtn.disabled = true;
+
+        const el = this.chat.createMessageElement('assistant', '');
+        this.chat.messagesContainer.appendChild(el);
+        const textArea = el.querySelector('.text-area');
+        textArea.innerHTML = 'Importing <strong>' + this.chat.escapeHtml(file.name) + '</strong>...';
+        const log = document.createElement('div');
+        log.className = 'import-progress';
+        el.querySelector('.tool-area').appendChild(log);
+        this.chat.autoScroll();
+
+        const form = new FormData();
+        form.append('file', file);
+
+        try {
+            const response = await fetch('/api/import', { method: 'POST', body: form });
+            if (!response.ok) {
+                const body = await response.text();
+                textArea.innerHTML = '<p style="color: red;">Import failed: ' + this.chat.escapeHtml(body || String(response.status)) + '</p>';
+                return;
+            }
+
+            const reader = response.body.getReader();
+            const decoder = new TextDecoder();
+            let buffer = '';
+            let currentEvent = 'message';
+
+            while (true) {
+                const { value, done } = await reader.read();
+                if (done) break;
+
+                buffer += decoder.decode(value, { stream: true });
+                const lines = buffer.split('\n');
+                buffer = lines.pop() || '';
+
+                for (const line of lines) {
+                    if (line.startsWith('event: ')) {
+                        currentEvent = line.slice(7).trim();
+                    } else if (line.startsWith('data: ')) {
+                        this.handleSseEvent(currentEvent, line.slice(6).trim(), textArea, log);
+                    } else if (line === '') {
+                        currentEvent = 'message';
+                    }
+                }
+            }
+        } catch (err) {
+            textArea.innerHTML = '<p style="color: red;">Import failed: ' + this.chat.escapeHtml(err.message) + '</p>';
+        } finally {
+            this.importBtn.disabled = false;
+        }
+    }
+
+    handleSseEvent(eventType, jsonData, textArea, log) {
+        try {
+            const data = JSON.parse(jsonData);
+            if (eventType === 'progress') {
+                const row = document.createElement('div');
+                row.textContent = '[' + data.index + '/' + data.total + '] ' + data.description + ' → '
+                    + data.category + ' (' + data.confidence.toFixed(2) + ') via ' + data.source;
+                log.appendChild(row);
+                log.scrollTop = log.scrollHeight;
+            } else if (eventType === 'done') {
+                const s = data.stats;
+                textArea.innerHTML = this.chat.renderMarkdown(
+                    '**Imported ' + data.filename + '**\n'
+                    + s.new_insertions + ' new, ' + s.duplicates_skipped + ' duplicates skipped '
+                    + '(' + s.rules_hits + ' rules, ' + s.cache_hits + ' cache, ' + s.llm_calls + ' LLM), '
+                    + s.flagged + ' to review.\n'
+                    + data.total_transactions + ' transactions in the database.'
+                    + (s.balance_gaps > 0
+                        ? '\n\nThe statement\'s balances do not match its transactions; a statement may be missing. Ask for the balance history to see where.'
+                        : '')
+                );
+            } else if (eventType === 'error') {
+                textArea.innerHTML = '<p style="color: red;">Import failed: ' + this.chat.escapeHtml(data.message) + '</p>';
+            }
+            this.chat.autoScroll();
+        } catch (e) {
+            console.error('Failed to parse import event:', e);
+        }
+    }
+}